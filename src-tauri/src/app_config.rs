@@ -1,8 +1,15 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::path::Path;
 
-use crate::config::{copy_file, get_app_config_dir, get_app_config_path, write_json_file};
+use crate::config::{
+    archive_private_bytes, copy_file, get_app_config_dir, get_app_config_enc_path,
+    get_app_config_path, shred_file,
+};
 use crate::provider::ProviderManager;
+use crate::schema_migration::{self, MigrationRun};
+use crate::secure_store;
 
 /// MCP 配置：单客户端维度（claude 或 codex 下的一组服务器）
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -64,8 +71,33 @@ pub struct MultiAppConfig {
     pub mcp: McpRoot,
 }
 
+/// 配置加载失败的原因：只有文件本身损坏时才应隔离并从副本恢复
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    /// 无法解析或解密（文件已损坏）
+    Corrupt(String),
+    /// 配置锁超时、读写或权限错误、密钥不可用、版本高于当前支持等：文件本身可能完好
+    Other(String),
+}
+
+impl LoadError {
+    pub fn message(&self) -> &str {
+        match self {
+            LoadError::Corrupt(e) | LoadError::Other(e) => e,
+        }
+    }
+}
+
+impl From<LoadError> for String {
+    fn from(error: LoadError) -> Self {
+        match error {
+            LoadError::Corrupt(e) | LoadError::Other(e) => e,
+        }
+    }
+}
+
 fn default_version() -> u32 {
-    2
+    schema_migration::CURRENT_VERSION
 }
 
 impl Default for MultiAppConfig {
@@ -76,7 +108,7 @@ impl Default for MultiAppConfig {
         apps.insert("droid".to_string(), ProviderManager::default());
 
         Self {
-            version: 2,
+            version: schema_migration::CURRENT_VERSION,
             apps,
             mcp: McpRoot::default(),
         }
@@ -84,8 +116,26 @@ impl Default for MultiAppConfig {
 }
 
 impl MultiAppConfig {
-    /// 从文件加载配置（处理v1到v2的迁移）
-    pub fn load() -> Result<Self, String> {
+    /// 从文件加载配置：优先读取加密的 `config.enc.json`；否则读取旧明文 `config.json`，
+    /// 并自动迁移为加密存储，旧明文文件归档后移除。结构版本升级由 [`schema_migration`] 完成
+    ///
+    /// 错误区分文件损坏（[`LoadError::Corrupt`]）与其他原因，调用方只应对前者执行恢复
+    pub fn load() -> Result<Self, LoadError> {
+        let enc_path = get_app_config_enc_path();
+        if enc_path.exists() {
+            let value = secure_store::load_encrypted_json(&enc_path)?;
+            let (config, mut run) = Self::parse_value(&value, true)?;
+            if run.migrated() {
+                if let Err(e) = crate::config_sync::with_config_lock(|| config.save()) {
+                    run.mark_rolled_back(&e);
+                    schema_migration::append_log(&run.log);
+                    return Err(LoadError::Other(e));
+                }
+                schema_migration::append_log(&run.log);
+            }
+            return Ok(config);
+        }
+
         let config_path = get_app_config_path();
 
         if !config_path.exists() {
@@ -95,33 +145,27 @@ impl MultiAppConfig {
 
         // 尝试读取文件
         let content = std::fs::read_to_string(&config_path)
-            .map_err(|e| format!("读取配置文件失败: {}", e))?;
-
-        // 检查是否是旧版本格式（v1）
-        if let Ok(v1_config) = serde_json::from_str::<ProviderManager>(&content) {
-            log::info!("检测到v1配置，自动迁移到v2");
-
-            // 迁移到新格式
-            let mut apps = HashMap::new();
-            apps.insert("claude".to_string(), v1_config);
-            apps.insert("codex".to_string(), ProviderManager::default());
-            apps.insert("droid".to_string(), ProviderManager::default());
-
-            let config = Self {
-                version: 2,
-                apps,
-                mcp: McpRoot::default(),
-            };
-
-            // 迁移前备份旧版(v1)配置文件
-            let backup_dir = get_app_config_dir();
+            .map_err(|e| LoadError::Other(format!("读取配置文件失败: {}", e)))?;
+        let value: Value = serde_json::from_str(&content)
+            .map_err(|e| LoadError::Corrupt(format!("解析配置文件失败: {}", e)))?;
+        let (config, mut run) = Self::parse_value(&value, true)?;
+
+        if run.migrated() {
+            // 迁移前备份旧版配置文件
+            let from = schema_migration::detect_version(&value).map_err(LoadError::Corrupt)?;
             let ts = std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs();
-            let backup_path = backup_dir.join(format!("config.v1.backup.{}.json", ts));
+            let backup_path =
+                get_app_config_dir().join(format!("config.v{}.backup.{}.json", from, ts));
 
-            match copy_file(&config_path, &backup_path) {
+            // 备份同样加密保存，不在磁盘上留下明文密钥
+            let backup = std::fs::read(&config_path)
+                .map_err(|e| format!("读取旧版配置失败: {}", e))
+                .and_then(|data| secure_store::encrypt_json_bytes(&data))
+                .and_then(|sealed| secure_store::write_private_file(&backup_path, &sealed));
+            match backup {
                 Ok(()) => log::info!(
                     "已备份旧版配置文件: {} -> {}",
                     config_path.display(),
@@ -129,35 +173,112 @@ impl MultiAppConfig {
                 ),
                 Err(e) => log::warn!("备份旧版配置文件失败: {}", e),
             }
+        }
 
-            // 保存迁移后的配置
-            config.save()?;
-            return Ok(config);
+        // 迁移为加密存储：写入成功后再归档并移除旧明文
+        if let Err(e) = crate::config_sync::with_config_lock(|| config.save()) {
+            run.mark_rolled_back(&e);
+            schema_migration::append_log(&run.log);
+            return Err(LoadError::Other(e));
+        }
+        schema_migration::append_log(&run.log);
+        archive_plaintext_config();
+        log::info!(
+            "已将明文配置迁移为加密存储: {}",
+            get_app_config_enc_path().display()
+        );
+
+        Ok(config)
+    }
+
+    /// 只读加载：迁移只在内存中执行，不保存、不归档旧明文、不写迁移日志
+    pub fn load_read_only() -> Result<Self, String> {
+        for path in [get_app_config_enc_path(), get_app_config_path()] {
+            if path.exists() {
+                return Self::read_from_path(&path);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// 按 `version` 依次执行迁移步骤后反序列化，返回 (配置, 迁移记录)
+    ///
+    /// 迁移失败时原始 JSON 不受影响；`record_failure` 为真时把失败记录写入迁移日志。
+    /// 版本高于当前支持时返回 [`LoadError::Other`]（由新版本写入，文件并未损坏）
+    fn parse_value(value: &Value, record_failure: bool) -> Result<(Self, MigrationRun), LoadError> {
+        let version = schema_migration::detect_version(value).map_err(LoadError::Corrupt)?;
+        if version > schema_migration::CURRENT_VERSION {
+            return Err(LoadError::Other(format!(
+                "配置版本 v{} 高于当前支持的 v{}，请升级 CC Switch",
+                version,
+                schema_migration::CURRENT_VERSION
+            )));
         }
+        let run = schema_migration::run_migrations(value);
+        let migrated = match &run.result {
+            Ok(v) => v.clone(),
+            Err(e) => {
+                if record_failure {
+                    schema_migration::append_log(&run.log);
+                }
+                return Err(LoadError::Corrupt(e.clone()));
+            }
+        };
+        let mut config: Self = serde_json::from_value(migrated)
+            .map_err(|e| LoadError::Corrupt(format!("解析配置文件失败: {}", e)))?;
 
-        // 尝试读取v2格式
-        let mut config = serde_json::from_str::<Self>(&content).map_err(|e| format!("解析配置文件失败: {}", e))?;
-        
         // 确保所有应用类型都存在（向后兼容）
         config.ensure_app(&AppType::Claude);
         config.ensure_app(&AppType::Codex);
         config.ensure_app(&AppType::Droid);
-        
-        Ok(config)
+        Ok((config, run))
+    }
+
+    /// 从配置 JSON 构建配置（执行迁移但不写日志、不落盘）
+    pub fn from_json_value(value: &Value) -> Result<Self, String> {
+        Self::parse_value(value, false)
+            .map(|(config, _)| config)
+            .map_err(String::from)
+    }
+
+    /// 读取指定路径的配置副本（加密封装或明文均可），不产生任何写入，供损坏恢复使用
+    pub fn read_from_path(path: &Path) -> Result<Self, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("读取配置文件失败: {}", e))?;
+        let value = if secure_store::is_envelope(&content) {
+            secure_store::read_encrypted_json_from(path)?
+        } else {
+            serde_json::from_str(&content).map_err(|e| format!("解析配置文件失败: {}", e))?
+        };
+        Self::from_json_value(&value)
     }
 
-    /// 保存配置到文件
+    /// 保存配置到加密文件 `config.enc.json`（落盘前将明文密钥移入密钥库，仅保存引用）
     pub fn save(&self) -> Result<(), String> {
-        let config_path = get_app_config_path();
-        // 先备份旧版（若存在）到 ~/.cc-switch/config.json.bak，再写入新内容
-        if config_path.exists() {
-            let backup_path = get_app_config_dir().join("config.json.bak");
-            if let Err(e) = copy_file(&config_path, &backup_path) {
-                log::warn!("备份 config.json 到 .bak 失败: {}", e);
+        let mut persisted = self.clone();
+        crate::secrets::extract_config_secrets(&mut persisted)?;
+
+        let enc_path = get_app_config_enc_path();
+        // 先备份旧版（若存在）到 ~/.cc-switch/config.enc.json.bak，再写入新内容
+        if enc_path.exists() {
+            let backup_path = get_app_config_dir().join("config.enc.json.bak");
+            if let Err(e) = copy_file(&enc_path, &backup_path) {
+                log::warn!("备份 config.enc.json 到 .bak 失败: {}", e);
+            }
+            // 同时保留带时间戳的滚动快照（~/.cc-switch/snapshots/），按设置清理旧快照
+            if let Err(e) = crate::snapshot::snapshot_before_save(&enc_path) {
+                log::warn!("创建配置快照失败: {}", e);
             }
         }
 
-        write_json_file(&config_path, self)?;
+        secure_store::write_encrypted_config(&persisted)?;
+
+        // 配置落盘后再清理不再引用的密钥；失败只留下多余的密钥，不影响配置
+        match crate::secrets::prune_unreferenced_secrets(&persisted) {
+            Ok(0) => {}
+            Ok(n) => log::info!("已从密钥库移除 {} 个不再引用的密钥", n),
+            Err(e) => log::warn!("清理密钥库失败: {}", e),
+        }
         Ok(())
     }
 
@@ -197,3 +318,41 @@ impl MultiAppConfig {
         }
     }
 }
+
+/// 将旧明文 `config.json` 及其 `.bak` 加密后归档到 `~/.cc-switch/archive/<ts>/cc-switch/`，
+/// 再覆写并删除明文文件（归档名为 `config.enc.json` / `config.json.bak.enc`，可供损坏恢复读取）
+///
+/// 归档失败的文件保留在原处（保守策略），仅记录告警
+fn archive_plaintext_config() {
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let plain = get_app_config_path();
+    let plain_bak = get_app_config_dir().join("config.json.bak");
+    for (path, name) in [
+        (plain, "config.enc.json"),
+        (plain_bak, "config.json.bak.enc"),
+    ] {
+        if !path.exists() {
+            continue;
+        }
+        let archived = std::fs::read(&path)
+            .map_err(|e| format!("读取明文配置失败: {}", e))
+            .and_then(|data| secure_store::encrypt_json_bytes(&data))
+            .and_then(|sealed| archive_private_bytes(ts, "cc-switch", name, &sealed));
+        match archived {
+            Ok(dest) => {
+                log::info!(
+                    "已加密归档明文配置: {} -> {}",
+                    path.display(),
+                    dest.display()
+                );
+                if let Err(e) = shred_file(&path) {
+                    log::warn!("移除明文配置失败: {}", e);
+                }
+            }
+            Err(e) => log::warn!("归档明文配置失败: {}", e),
+        }
+    }
+}
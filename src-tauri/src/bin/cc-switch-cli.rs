@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(cc_switch_lib::cli::run());
+}
@@ -4,7 +4,8 @@ use std::path::{Path, PathBuf};
 
 use crate::config::atomic_write;
 
-fn user_config_path() -> PathBuf {
+/// 用户级 MCP 配置文件路径 (~/.claude.json)
+pub fn user_config_path() -> PathBuf {
     // 用户级 MCP 配置文件：~/.claude.json
     dirs::home_dir()
         .expect("无法获取用户主目录")
@@ -0,0 +1,387 @@
+use crate::app_config::AppType;
+use crate::preflight::{self, PreflightResult};
+use crate::provider::{Provider, TemporarySwitch};
+use crate::provider_service::ProviderService;
+use crate::schema_migration::MigrationStatus;
+use std::time::Duration;
+
+const USAGE: &str = "\
+cc-switch-cli - 无界面的供应商管理工具（读写 ~/.cc-switch/config.enc.json）
+
+用法:
+  cc-switch-cli list [app]          列出供应商（* 标记当前供应商）
+  cc-switch-cli current [app]       显示当前供应商 ID
+  cc-switch-cli switch <app> <id>   切换到指定供应商
+  cc-switch-cli switch <app> <id> --for <时长>
+                                    临时切换，到期后由主程序恢复为切换前的供应商
+                                    （时长如 30m、2h、90s，纯数字表示分钟）
+  cc-switch-cli preview <app> <id> [--json]
+                                    预演切换：输出各文件的差异，不写入任何文件
+                                    （--json 输出完整前后内容与 JSON 补丁）
+  cc-switch-cli check <app> <id> [--base-url URL]
+                                    检查供应商的连通性与凭据（不切换；--base-url 覆盖配置中的地址）
+  cc-switch-cli schedule [app]      显示自动切换规则下一次计划中的切换
+  cc-switch-cli disable <app>       停用当前供应商
+  cc-switch-cli show <app> <id>     以 JSON 输出供应商配置
+  cc-switch-cli unlock              由口令派生密钥文件（读取 CC_SWITCH_PASSPHRASE 或标准输入）
+  cc-switch-cli snapshots           列出配置快照（从新到旧）
+  cc-switch-cli snapshots diff <id> 比较快照与当前配置
+  cc-switch-cli snapshots restore <id>
+                                    从快照恢复配置
+  cc-switch-cli migrations          显示配置结构迁移日志
+  cc-switch-cli help                显示本帮助
+
+app 取值: claude | codex | droid";
+
+const ALL_APPS: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Droid];
+
+/// CLI 入口：解析参数并执行子命令，返回进程退出码
+pub fn run() -> i32 {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
+
+    let result = match args.as_slice() {
+        [] | ["help"] | ["-h"] | ["--help"] => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        ["list"] => cmd_list(&ALL_APPS),
+        ["list", app] => parse_app(app).and_then(|app| cmd_list(&[app])),
+        ["current"] => cmd_current(&ALL_APPS),
+        ["current", app] => parse_app(app).and_then(|app| cmd_current(&[app])),
+        ["switch", app, id] => parse_app(app).and_then(|app| cmd_switch(app, id, None)),
+        ["switch", app, id, "--for", duration] => parse_app(app).and_then(|app| {
+            let duration = parse_duration(duration)?;
+            cmd_switch(app, id, Some(duration))
+        }),
+        ["preview", app, id] => parse_app(app).and_then(|app| cmd_preview(app, id, false)),
+        ["preview", app, id, "--json"] => parse_app(app).and_then(|app| cmd_preview(app, id, true)),
+        ["check", app, id] => parse_app(app).and_then(|app| cmd_check(app, id, None)),
+        ["check", app, id, "--base-url", url] => {
+            parse_app(app).and_then(|app| cmd_check(app, id, Some(url)))
+        }
+        ["schedule"] => cmd_schedule(None),
+        ["schedule", app] => parse_app(app).and_then(|app| cmd_schedule(Some(app))),
+        ["disable", app] => parse_app(app).and_then(cmd_disable),
+        ["show", app, id] => parse_app(app).and_then(|app| cmd_show(app, id)),
+        ["unlock"] => cmd_unlock(),
+        ["snapshots"] | ["snapshots", "list"] => cmd_snapshots(),
+        ["snapshots", "diff", id] => cmd_snapshot_diff(id),
+        ["snapshots", "restore", id] => cmd_snapshot_restore(id),
+        ["migrations"] => cmd_migrations(),
+        _ => {
+            eprintln!("参数无效\n\n{}", USAGE);
+            return 2;
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            1
+        }
+    }
+}
+
+/// 严格解析应用类型（`AppType::from` 对未知值回落到 Claude，CLI 需要显式报错）
+fn parse_app(raw: &str) -> Result<AppType, String> {
+    match raw.to_lowercase().as_str() {
+        "claude" => Ok(AppType::Claude),
+        "codex" => Ok(AppType::Codex),
+        "droid" => Ok(AppType::Droid),
+        _ => Err(format!(
+            "未知的应用类型: {}（可选 claude / codex / droid）",
+            raw
+        )),
+    }
+}
+
+/// 解析时长：`30m`、`2h`、`90s`，纯数字按分钟计
+fn parse_duration(raw: &str) -> Result<Duration, String> {
+    let raw = raw.trim();
+    let (number, unit) = match raw.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => raw.split_at(pos),
+        None => (raw, "m"),
+    };
+    let secs_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("无效的时长: {}（示例: 30m、2h、90s）", raw)),
+    };
+    let duration = number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(secs_per_unit))
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("无效的时长: {}（示例: 30m、2h、90s）", raw))?;
+    TemporarySwitch::validate_duration(duration)?;
+    Ok(duration)
+}
+
+fn sorted_providers(service: &ProviderService, app: &AppType) -> Vec<Provider> {
+    let mut providers: Vec<Provider> = service
+        .providers(app)
+        .map(|p| p.values().cloned().collect())
+        .unwrap_or_default();
+    providers.sort_by_key(|p| p.name.to_lowercase());
+    providers
+}
+
+fn cmd_list(apps: &[AppType]) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    for app in apps {
+        let current = service.current_provider_id(app).unwrap_or("");
+        println!("[{}]", app.as_str());
+        let providers = sorted_providers(&service, app);
+        if providers.is_empty() {
+            println!("  (无供应商)");
+        }
+        for p in providers {
+            let mark = if p.id == current { "*" } else { " " };
+            println!("{} {}\t{}", mark, p.id, p.name);
+        }
+        if let Some(temporary) = service
+            .config()
+            .get_manager(app)
+            .and_then(|m| m.temporary.as_ref())
+        {
+            println!(
+                "  临时切换: {} 秒后恢复为 {}",
+                temporary.remaining().as_secs(),
+                temporary.previous_provider_id
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_current(apps: &[AppType]) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    for app in apps {
+        let current = service.current_provider_id(app).unwrap_or("");
+        if apps.len() == 1 {
+            println!("{}", current);
+        } else {
+            println!("{}\t{}", app.as_str(), current);
+        }
+    }
+    Ok(())
+}
+
+/// 在当前线程上运行异步任务（连通性检查等网络请求）
+fn block_on<F: std::future::Future>(future: F) -> Result<F::Output, String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("创建异步运行时失败: {}", e))?;
+    Ok(runtime.block_on(future))
+}
+
+fn print_preflight(result: &PreflightResult) {
+    let detail = match (result.http_status, result.latency_ms) {
+        (Some(code), Some(ms)) => format!("（HTTP {}，{} ms）", code, ms),
+        _ => String::new(),
+    };
+    println!(
+        "连通性检查 {}: {}{}",
+        result.status.as_str(),
+        result.message,
+        detail
+    );
+    if !result.url.is_empty() {
+        println!("  {}", result.url);
+    }
+}
+
+fn cmd_switch(app: AppType, id: &str, duration: Option<Duration>) -> Result<(), String> {
+    let mut service = ProviderService::load()?;
+    let plan = service.prepare_switch(&app, id)?;
+    let report = block_on(plan.execute(|| {
+        service.modify(|s| match duration {
+            Some(duration) => s.switch_provider_for(&app, id, duration),
+            None => s.switch_provider(&app, id),
+        })
+    }))??;
+    if let Some(result) = &report.preflight {
+        print_preflight(result);
+    }
+    println!(
+        "已切换 {} 供应商: {} ({})",
+        app.as_str(),
+        report.provider.name,
+        report.provider.id
+    );
+    if let Some(temporary) = service
+        .config()
+        .get_manager(&app)
+        .and_then(|m| m.temporary.as_ref())
+    {
+        println!(
+            "{} 秒后恢复为 {}（需主程序运行，未运行时在下次启动时恢复）",
+            temporary.remaining().as_secs(),
+            temporary.previous_provider_id
+        );
+    }
+    Ok(())
+}
+
+fn cmd_check(app: AppType, id: &str, base_url: Option<&str>) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    let provider = service.provider_resolved(&app, id)?;
+    let result = block_on(preflight::check(&app, &provider, base_url))?;
+    print_preflight(&result);
+    if result.is_failure() {
+        return Err(format!("{} 供应商 {} 检查未通过", app.as_str(), id));
+    }
+    Ok(())
+}
+
+fn cmd_preview(app: AppType, id: &str, json: bool) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    let preview = service.preview_switch(&app, id)?;
+    if json {
+        let text = serde_json::to_string_pretty(&preview)
+            .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
+        println!("{}", text);
+        return Ok(());
+    }
+
+    println!(
+        "预演切换 {} 供应商: {} ({})",
+        app.as_str(),
+        preview.provider_name,
+        preview.provider_id
+    );
+    for file in &preview.files {
+        if file.changed {
+            print!("\n{}", file.diff);
+        } else {
+            println!("\n{}: 无变化", file.path);
+        }
+    }
+    for note in &preview.notes {
+        println!("\n注意: {}", note);
+    }
+    Ok(())
+}
+
+fn cmd_schedule(app: Option<AppType>) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    match service.next_scheduled_switch(app.as_ref()) {
+        Some(next) => println!(
+            "{}\t{}\t{}{}",
+            next.at_local,
+            next.app_type.as_str(),
+            next.provider_id,
+            next.provider_name
+                .map(|name| format!(" ({})", name))
+                .unwrap_or_else(|| " (供应商不存在)".to_string())
+        ),
+        None => println!("没有计划中的切换"),
+    }
+    Ok(())
+}
+
+fn cmd_disable(app: AppType) -> Result<(), String> {
+    let mut service = ProviderService::load()?;
+    service.modify(|s| s.disable_current_provider(&app))?;
+    println!("已停用 {} 当前供应商", app.as_str());
+    Ok(())
+}
+
+fn cmd_show(app: AppType, id: &str) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    let provider = service.provider(&app, id)?;
+    let json =
+        serde_json::to_string_pretty(provider).map_err(|e| format!("序列化 JSON 失败: {}", e))?;
+    println!("{}", json);
+    Ok(())
+}
+
+fn cmd_unlock() -> Result<(), String> {
+    let passphrase = match std::env::var(crate::secure_store::PASSPHRASE_ENV) {
+        Ok(p) if !p.is_empty() => p,
+        _ => {
+            eprint!("请输入口令: ");
+            let mut line = String::new();
+            std::io::stdin()
+                .read_line(&mut line)
+                .map_err(|e| format!("读取口令失败: {}", e))?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+    crate::secure_store::unlock_with_passphrase(&passphrase)?;
+    println!(
+        "已写入密钥文件: {}",
+        crate::config::get_config_key_path().display()
+    );
+    Ok(())
+}
+
+fn cmd_snapshots() -> Result<(), String> {
+    let snapshots = crate::snapshot::list_snapshots()?;
+    if snapshots.is_empty() {
+        println!("(无快照)");
+    }
+    for s in snapshots {
+        println!("{}\t{} 字节", s.id, s.size);
+    }
+    Ok(())
+}
+
+fn cmd_snapshot_diff(id: &str) -> Result<(), String> {
+    let service = ProviderService::load_read_only()?;
+    let diff = service.diff_snapshot(id)?;
+    if diff.apps.is_empty() {
+        println!("快照 {} 与当前配置一致", id);
+    }
+    for app in diff.apps {
+        println!("[{}]", app.app);
+        for p in app.added {
+            println!("  + {}\t{}", p.id, p.name);
+        }
+        for p in app.removed {
+            println!("  - {}\t{}", p.id, p.name);
+        }
+        for p in app.changed {
+            println!("  ~ {}\t{}", p.id, p.name);
+        }
+        if app.snapshot_current != app.current {
+            println!("  当前供应商: {} -> {}", app.snapshot_current, app.current);
+        }
+    }
+    Ok(())
+}
+
+fn cmd_snapshot_restore(id: &str) -> Result<(), String> {
+    // 不加载当前配置：即使 config.enc.json 已损坏也能恢复
+    let mut service = ProviderService::default();
+    service.restore_snapshot(id)?;
+    println!("已从快照 {} 恢复配置", id);
+    Ok(())
+}
+
+fn cmd_migrations() -> Result<(), String> {
+    let entries = crate::schema_migration::read_log()?;
+    if entries.is_empty() {
+        println!("(无迁移记录)");
+    }
+    for e in entries {
+        let status = match e.status {
+            MigrationStatus::Applied => "applied",
+            MigrationStatus::RolledBack => "rolled-back",
+            MigrationStatus::Failed => "failed",
+        };
+        print!(
+            "{}\tv{} -> v{}\t{}\t{}",
+            e.timestamp, e.from, e.to, e.name, status
+        );
+        match e.error {
+            Some(err) => println!("\t{}", err),
+            None => println!(),
+        }
+    }
+    Ok(())
+}
@@ -4,9 +4,11 @@ use std::path::PathBuf;
 use crate::config::{
     atomic_write, delete_file, sanitize_provider_name, write_json_file, write_text_file,
 };
+use crate::provider::Provider;
 use serde_json::Value;
 use std::fs;
 use std::path::Path;
+use toml_edit::{DocumentMut, Item, Key, Table};
 
 /// 获取 Codex 配置目录路径
 pub fn get_codex_config_dir() -> PathBuf {
@@ -55,7 +57,9 @@ pub fn delete_codex_provider_config(provider_id: &str, provider_name: &str) -> R
 //（移除未使用的备份/保存/恢复/导入函数，避免 dead_code 告警）
 
 /// 原子写 Codex 的 `auth.json` 与 `config.toml`，在第二步失败时回滚第一步
-pub fn write_codex_live_atomic(auth: &Value, config_text_opt: Option<&str>) -> Result<(), String> {
+///
+/// `cfg_text` 为最终写入的全文（见 [`render_codex_config`] 与 [`render_codex_section_config`]）
+pub fn write_codex_live_atomic(auth: &Value, cfg_text: &str) -> Result<(), String> {
     let auth_path = get_codex_auth_path();
     let config_path = get_codex_config_path();
 
@@ -69,27 +73,12 @@ pub fn write_codex_live_atomic(auth: &Value, config_text_opt: Option<&str>) -> R
     } else {
         None
     };
-    let _old_config = if config_path.exists() {
-        Some(fs::read(&config_path).map_err(|e| format!("读取旧 config.toml 失败: {}", e))?)
-    } else {
-        None
-    };
-
-    // 准备写入内容
-    let cfg_text = match config_text_opt {
-        Some(s) => s.to_string(),
-        None => String::new(),
-    };
-    if !cfg_text.trim().is_empty() {
-        toml::from_str::<toml::Table>(&cfg_text)
-            .map_err(|e| format!("config.toml 格式错误: {}", e))?;
-    }
 
     // 第一步：写 auth.json
     write_json_file(&auth_path, auth)?;
 
-    // 第二步：写 config.toml（失败则回滚 auth.json）
-    if let Err(e) = write_text_file(&config_path, &cfg_text) {
+    // 第二步：写 config.toml（失败则回滚 auth.json；config.toml 为原子替换，失败时保持原内容）
+    if let Err(e) = write_text_file(&config_path, cfg_text) {
         // 回滚 auth.json
         if let Some(bytes) = old_auth {
             let _ = atomic_write(&auth_path, &bytes);
@@ -102,6 +91,400 @@ pub fn write_codex_live_atomic(auth: &Value, config_text_opt: Option<&str>) -> R
     Ok(())
 }
 
+/// 计算写入 `config.toml` 的文本：内容与 `cfg_text` 相同，但以现有文件为底，
+/// 未变化的键与表（连同注释和格式）保持原样，只改动有差异的部分
+pub fn render_codex_config(cfg_text: &str) -> Result<String, String> {
+    if cfg_text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    let target = parse_config_document(cfg_text)?;
+    let live_text = read_codex_config_text()?;
+    // 现有文件为空或无法解析时整体替换
+    let Some(mut live) = Some(live_text)
+        .filter(|text| !text.trim().is_empty())
+        .and_then(|text| text.parse::<DocumentMut>().ok())
+    else {
+        return Ok(cfg_text.to_string());
+    };
+    apply_table(live.as_table_mut(), target.as_table());
+    Ok(live.to_string())
+}
+
+/// 分段模式下供应商拥有的顶层键（另有片段中定义的 `[model_providers.*]`）
+const SECTION_KEYS: [&str; 2] = ["model", "model_provider"];
+
+/// 校验分段模式的片段：只能包含 `model`、`model_provider` 与 `[model_providers.*]` 表
+pub fn validate_section_config(text: &str) -> Result<(), String> {
+    let doc = parse_config_document(text)?;
+    if let Some((key, _)) = doc
+        .iter()
+        .find(|(key, _)| !SECTION_KEYS.contains(key) && *key != "model_providers")
+    {
+        return Err(format!(
+            "分段模式的 config.toml 只能包含 model、model_provider 与 [model_providers.*]，不能包含 {}",
+            key
+        ));
+    }
+    match doc.get("model_providers") {
+        None | Some(Item::Table(_)) => Ok(()),
+        Some(_) => Err("分段模式中 model_providers 须写成 [model_providers.<名称>] 表".to_string()),
+    }
+}
+
+/// 片段中定义的 `[model_providers.*]` 名称
+fn section_provider_names(section: &DocumentMut) -> Vec<String> {
+    match section.get("model_providers") {
+        Some(Item::Table(providers)) => providers.iter().map(|(k, _)| k.to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 分段模式：把供应商片段合并进现有 `config.toml`，其余内容（个人设置、MCP 等）保持原样
+///
+/// `previous_section` 为上一个分段供应商的片段，其定义而新片段没有的 `[model_providers.*]` 会被移除
+pub fn render_codex_section_config(
+    section_text: &str,
+    previous_section: Option<&str>,
+) -> Result<String, String> {
+    let section = parse_config_document(section_text)?;
+    let live_text = read_codex_config_text()?;
+    let mut live = parse_config_document(&live_text)
+        .map_err(|e| format!("现有 config.toml 无法合并: {}", e))?;
+
+    let keep = section_provider_names(&section);
+    if let Some(previous) = previous_section.and_then(|t| t.parse::<DocumentMut>().ok()) {
+        if let Some(Item::Table(providers)) = live.get_mut("model_providers") {
+            for name in section_provider_names(&previous) {
+                if !keep.contains(&name) {
+                    providers.remove(&name);
+                }
+            }
+            if providers.is_empty() {
+                live.remove("model_providers");
+            }
+        }
+    }
+
+    for key in SECTION_KEYS {
+        match section.get_key_value(key) {
+            Some((formatted, item)) => apply_entry(live.as_table_mut(), formatted, item),
+            None => {
+                live.remove(key);
+            }
+        }
+    }
+    if let Some((formatted, Item::Table(providers))) = section.get_key_value("model_providers") {
+        match live.get_mut("model_providers") {
+            Some(Item::Table(live_providers)) => {
+                for (name, item) in providers.iter() {
+                    if let Some((formatted, _)) = providers.get_key_value(name) {
+                        apply_entry(live_providers, formatted, item);
+                    }
+                }
+            }
+            _ => apply_entry(
+                live.as_table_mut(),
+                formatted,
+                &Item::Table(providers.clone()),
+            ),
+        }
+    }
+    Ok(live.to_string())
+}
+
+/// 分段模式的回填：以已保存的片段为底，写入 live 中的 `model`、`model_provider`
+/// 及其指向的 `[model_providers.<名称>]`（片段原有的注释与格式保持不变）
+pub fn extract_section_config(live_text: &str, stored_section: &str) -> Result<String, String> {
+    let live = parse_config_document(live_text)?;
+    let mut target = Table::new();
+    for key in SECTION_KEYS {
+        if let Some((formatted, item)) = live.get_key_value(key) {
+            target.insert_formatted(formatted, item.clone());
+        }
+    }
+    let active = live
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .and_then(|name| Some((name, live.get("model_providers")?.get(name)?)));
+    if let Some((name, item @ Item::Table(_))) = active {
+        let mut providers = Table::new();
+        providers.set_implicit(true);
+        providers.insert(name, item.clone());
+        target.insert("model_providers", Item::Table(providers));
+    }
+
+    let mut doc = stored_section.parse::<DocumentMut>().unwrap_or_default();
+    apply_table(doc.as_table_mut(), &target);
+    Ok(doc.to_string())
+}
+
+/// profile 模式下从供应商配置复制到 `[profiles.<id>]` 的顶层键
+const PROFILE_KEYS: [&str; 4] = [
+    "model",
+    "model_reasoning_effort",
+    "model_reasoning_summary",
+    "model_verbosity",
+];
+
+/// profile 模式下由 cc-switch 生成的两类表
+const PROFILE_CONTAINERS: [&str; 2] = ["model_providers", "profiles"];
+
+/// profile 模式下供应商未指定 `env_key` 时使用的环境变量名：`<供应商ID>_API_KEY`
+///
+/// ID 转为大写，字母与数字以外的字符替换为 `_`
+pub fn profile_env_key(provider_id: &str) -> String {
+    let id: String = provider_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}_API_KEY", id)
+}
+
+/// 供应商对应的 `[model_providers.<id>]` 与 `[profiles.<id>]`
+///
+/// 配置中没有 `model_provider` 指向的 `[model_providers.*]` 时（如官方登录）不生成；
+/// 表中未指定 `env_key` 时写入 `env_key = "<供应商ID>_API_KEY"`（见 [`profile_env_key`]），
+/// 否则各 profile 都会使用当前 `auth.json` 中的密钥。供应商开启 `codexInlineToken` 时改为把
+/// `auth.json` 的 `OPENAI_API_KEY` 明文写作 `experimental_bearer_token`（此时 `provider` 须已解析密钥引用）
+fn profile_tables(provider: &Provider) -> Result<Option<(Table, Table)>, String> {
+    let text = provider
+        .settings_config
+        .get("config")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let doc =
+        parse_config_document(text).map_err(|e| format!("供应商 {} 的 {}", provider.name, e))?;
+    let Some(mut model_provider) = doc
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .and_then(|name| doc.get("model_providers")?.get(name)?.as_table())
+        .cloned()
+    else {
+        return Ok(None);
+    };
+
+    if !model_provider.contains_key("env_key")
+        && !model_provider.contains_key("experimental_bearer_token")
+    {
+        // 未开启明文写入时 Key 可能仍是密钥引用，只判断是否填写
+        let api_key = provider
+            .settings_config
+            .pointer("/auth/OPENAI_API_KEY")
+            .filter(|v| !v.is_null() && !v.as_str().is_some_and(|s| s.trim().is_empty()));
+        match api_key {
+            Some(key) if provider.codex_inline_token() => {
+                let key = key
+                    .as_str()
+                    .ok_or_else(|| format!("供应商 {} 的 API Key 未解析", provider.name))?;
+                model_provider.insert("experimental_bearer_token", toml_edit::value(key));
+            }
+            Some(_) => {
+                model_provider.insert("env_key", toml_edit::value(profile_env_key(&provider.id)));
+            }
+            None => {}
+        }
+    }
+    let label = provider.name.replace(['\r', '\n'], " ");
+    model_provider
+        .decor_mut()
+        .set_prefix(format!("\n# {}\n", label));
+
+    let mut profile = Table::new();
+    profile.insert("model_provider", toml_edit::value(provider.id.as_str()));
+    for key in PROFILE_KEYS {
+        if let Some(item) = doc.get(key) {
+            profile.insert(key, item.clone());
+        }
+    }
+    profile.decor_mut().set_prefix(format!("\n# {}\n", label));
+    Ok(Some((model_provider, profile)))
+}
+
+/// profile 模式：为每个供应商写入 `[model_providers.<id>]` 与 `[profiles.<id>]`，
+/// 并移除 `previous_ids` 中已不再生成的表；其余内容（包括顶层的默认供应商）保持不变
+///
+/// 顶层 `model_provider` 指向的 `[model_providers.*]` 属于默认供应商，名称与某个 id 相同时不改动
+///
+/// 返回写入后的全文与本次生成的供应商 id（`providers` 为空表示只做清理）
+pub fn render_codex_profiles(
+    cfg_text: &str,
+    providers: &[Provider],
+    previous_ids: &[String],
+) -> Result<(String, Vec<String>), String> {
+    let mut doc = parse_config_document(cfg_text)?;
+    let active = doc
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .map(str::to_string);
+    let owned =
+        |container: &str, id: &str| container != "model_providers" || active.as_deref() != Some(id);
+    let mut ids = Vec::new();
+    let mut generated: [Vec<(String, Table)>; 2] = Default::default();
+    for provider in providers {
+        if let Some((model_provider, profile)) = profile_tables(provider)? {
+            ids.push(provider.id.clone());
+            generated[0].push((provider.id.clone(), model_provider));
+            generated[1].push((provider.id.clone(), profile));
+        }
+    }
+    ids.sort();
+
+    for (container, tables) in PROFILE_CONTAINERS.into_iter().zip(generated) {
+        match doc.get_mut(container) {
+            Some(Item::Table(live)) => {
+                for id in previous_ids.iter().filter(|id| !ids.contains(id)) {
+                    if owned(container, id) {
+                        live.remove(id);
+                    }
+                }
+                for (id, table) in tables.into_iter().filter(|(id, _)| owned(container, id)) {
+                    apply_entry(live, &Key::new(id), &Item::Table(table));
+                }
+                if live.is_empty() {
+                    doc.remove(container);
+                }
+            }
+            Some(_) => return Err(format!("config.toml 中的 {} 须写成表", container)),
+            None if !tables.iter().any(|(id, _)| owned(container, id)) => {}
+            None => {
+                let mut live = Table::new();
+                live.set_implicit(true);
+                for (id, table) in tables.into_iter().filter(|(id, _)| owned(container, id)) {
+                    live.insert(&id, Item::Table(table));
+                }
+                apply_entry(doc.as_table_mut(), &Key::new(container), &Item::Table(live));
+            }
+        }
+    }
+    Ok((doc.to_string(), ids))
+}
+
+/// 从 live `config.toml` 中去掉 profile 模式生成的表（回填到当前供应商前调用），无法解析时原样返回
+pub fn strip_codex_profiles(cfg_text: &str, ids: &[String]) -> String {
+    if ids.is_empty() {
+        return cfg_text.to_string();
+    }
+    render_codex_profiles(cfg_text, &[], ids)
+        .map(|(text, _)| text)
+        .unwrap_or_else(|_| cfg_text.to_string())
+}
+
+/// 修改顶层 `model_provider` 指向的 `[model_providers.<名称>]` 中的 `base_url`（保留原有注释与格式）
+pub fn set_provider_base_url(cfg_text: &str, url: &str) -> Result<String, String> {
+    let mut doc = parse_config_document(cfg_text)?;
+    let name = doc
+        .get("model_provider")
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "config.toml 未设置 model_provider，无法切换地址".to_string())?;
+    let table = doc
+        .get_mut("model_providers")
+        .and_then(|providers| providers.get_mut(&name))
+        .and_then(|item| item.as_table_like_mut())
+        .ok_or_else(|| {
+            format!(
+                "config.toml 中没有 [model_providers.{}]，无法切换地址",
+                name
+            )
+        })?;
+    let mut value = toml_edit::Value::from(url);
+    if let Some(old) = table.get("base_url").and_then(|item| item.as_value()) {
+        *value.decor_mut() = old.decor().clone();
+    }
+    table.insert("base_url", Item::Value(value));
+    Ok(doc.to_string())
+}
+
+/// 移除顶层的 `model_provider`（停用时使用，其余内容保持不变）
+pub fn remove_model_provider(cfg_text: &str) -> Result<String, String> {
+    let mut doc = parse_config_document(cfg_text)?;
+    doc.remove("model_provider");
+    Ok(doc.to_string())
+}
+
+/// 解析 config.toml 为可编辑的文档（保留注释、键顺序与原有格式）
+pub fn parse_config_document(text: &str) -> Result<DocumentMut, String> {
+    text.parse::<DocumentMut>()
+        .map_err(|e| format!("config.toml 语法错误: {}", e))
+}
+
+/// 两项是否语义相同（忽略注释、空白与书写格式）
+fn same_item(a: &Item, b: &Item) -> bool {
+    let normalize = |item: &Item| {
+        let mut doc = DocumentMut::new();
+        doc.insert("v", item.clone());
+        toml::from_str::<toml::Table>(&doc.to_string()).ok()
+    };
+    normalize(a).is_some_and(|a| Some(a) == normalize(b))
+}
+
+/// 表及其所有子表在文档中的最大位置
+fn max_position(table: &Table) -> Option<usize> {
+    table
+        .iter()
+        .filter_map(|(_, item)| match item {
+            Item::Table(t) => max_position(t),
+            Item::ArrayOfTables(arr) => arr.iter().filter_map(max_position).max(),
+            _ => None,
+        })
+        .chain(table.position())
+        .max()
+}
+
+/// 将新插入的表（及其子表）排在 `anchor` 位置之后，避免沿用来源文档中的位置
+fn place_at(item: &mut Item, anchor: usize) {
+    let place = |table: &mut Table| {
+        table.set_position(anchor);
+        for (_, child) in table.iter_mut() {
+            place_at(child, anchor);
+        }
+    };
+    match item {
+        Item::Table(t) => place(t),
+        Item::ArrayOfTables(arr) => arr.iter_mut().for_each(place),
+        _ => {}
+    }
+}
+
+/// 将 `live` 表改为与 `target` 语义相同：语义相同的项原样保留，子表逐项比较，
+/// 其余项替换为 `target` 中的写法，`target` 中没有的项删除
+pub fn apply_table(live: &mut Table, target: &Table) {
+    live.retain(|key, _| target.contains_key(key));
+    for (key, item) in target.iter() {
+        match target.get_key_value(key) {
+            Some((formatted, _)) => apply_entry(live, formatted, item),
+            None => apply_entry(live, &Key::new(key), item),
+        }
+    }
+}
+
+/// 将 `live` 中的 `key` 改为与 `target` 语义相同（规则同 [`apply_table`]），不影响其他键
+pub fn apply_entry(live: &mut Table, key: &Key, target: &Item) {
+    match (live.get_mut(key.get()), target) {
+        (Some(Item::Table(live_table)), Item::Table(target_table)) => {
+            apply_table(live_table, target_table);
+        }
+        (Some(existing), _) => {
+            if !same_item(existing, target) {
+                *existing = target.clone();
+            }
+        }
+        (None, _) => {
+            let mut item = target.clone();
+            if let Some(anchor) = max_position(live) {
+                place_at(&mut item, anchor);
+            }
+            live.insert_formatted(key, item);
+        }
+    }
+}
+
 /// 读取 `~/.codex/config.toml`，若不存在返回空字符串
 pub fn read_codex_config_text() -> Result<String, String> {
     let path = get_codex_config_path();
@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 use std::str::FromStr;
+use std::time::Duration;
 use tauri::State;
 use tauri_plugin_autostart::ManagerExt as AutostartManagerExt;
 use tauri_plugin_dialog::DialogExt;
@@ -12,54 +13,16 @@ use crate::app_config::AppType;
 use crate::claude_plugin;
 use crate::codex_config;
 use crate::config::{self, get_claude_settings_path, ConfigStatus};
-use crate::provider::Provider;
+use crate::config_recovery::RecoveryReport;
+use crate::preflight::{self, PreflightResult};
+use crate::provider::{Provider, TemporarySwitch};
+use crate::schedule::ScheduledSwitch;
+use crate::snapshot::{self, SnapshotDiff, SnapshotInfo};
 use crate::store::AppState;
+use crate::switch_hooks::SwitchReport;
+use crate::switch_preview::SwitchPreview;
 use crate::vscode;
 
-fn validate_provider_settings(app_type: &AppType, provider: &Provider) -> Result<(), String> {
-    match app_type {
-        AppType::Claude => {
-            if !provider.settings_config.is_object() {
-                return Err("Claude 配置必须是 JSON 对象".to_string());
-            }
-        }
-        AppType::Codex => {
-            let settings = provider
-                .settings_config
-                .as_object()
-                .ok_or_else(|| "Codex 配置必须是 JSON 对象".to_string())?;
-            let auth = settings
-                .get("auth")
-                .ok_or_else(|| "Codex 配置缺少 auth 字段".to_string())?;
-            if !auth.is_object() {
-                return Err("Codex auth 配置必须是 JSON 对象".to_string());
-            }
-            if let Some(config_value) = settings.get("config") {
-                if !(config_value.is_string() || config_value.is_null()) {
-                    return Err("Codex config 字段必须是字符串".to_string());
-                }
-                if let Some(cfg_text) = config_value.as_str() {
-                    codex_config::validate_config_toml(cfg_text)?;
-                }
-            }
-        }
-        AppType::Droid => {
-            let settings = provider
-                .settings_config
-                .as_object()
-                .ok_or_else(|| "Droid 配置必须是 JSON 对象".to_string())?;
-            let api_key = settings
-                .get("apiKey")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| "Droid 配置缺少 apiKey 字段".to_string())?;
-            if !api_key.starts_with("fk-") {
-                return Err("Droid API Key 格式错误，应以 fk- 开头".to_string());
-            }
-        }
-    }
-    Ok(())
-}
-
 /// 获取所有供应商
 #[tauri::command]
 pub async fn get_providers(
@@ -73,16 +36,17 @@ pub async fn get_providers(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    let config = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    // 其他进程（CLI、脚本等）修改过配置文件时先重新读取
+    if let Err(e) = service.refresh_from_disk() {
+        log::warn!("重新读取配置失败: {}", e);
+    }
 
-    let manager = config
-        .get_manager(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    Ok(manager.get_all_providers().clone())
+    // 界面编辑与余额查询需要明文，解析密钥引用后返回（不落盘）
+    service.providers_resolved(&app_type)
 }
 
 /// 获取当前供应商ID
@@ -98,16 +62,15 @@ pub async fn get_current_provider(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    let config = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    if let Err(e) = service.refresh_from_disk() {
+        log::warn!("重新读取配置失败: {}", e);
+    }
 
-    let manager = config
-        .get_manager(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    Ok(manager.current.clone())
+    Ok(service.current_provider_id(&app_type)?.to_string())
 }
 
 /// 添加供应商
@@ -124,91 +87,8 @@ pub async fn add_provider(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    validate_provider_settings(&app_type, &provider)?;
-
-    // 读取当前是否是激活供应商（短锁）
-    let is_current = {
-        let config = state
-            .config
-            .lock()
-            .map_err(|e| format!("获取锁失败: {}", e))?;
-        let manager = config
-            .get_manager(&app_type)
-            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-        manager.current == provider.id
-    };
-
-    // 若目标为当前供应商，则先写 live，成功后再落盘配置
-    if is_current {
-        match app_type {
-            AppType::Claude => {
-                use crate::config::{read_json_file, write_json_file};
-                let settings_path = crate::config::get_claude_settings_path();
-
-                // 读取现有配置（如果存在）
-                let mut final_config = if settings_path.exists() {
-                    read_json_file::<serde_json::Value>(&settings_path).unwrap_or(serde_json::json!({}))
-                } else {
-                    serde_json::json!({})
-                };
-
-                // 只更新 env 中的 ANTHROPIC_AUTH_TOKEN 和 ANTHROPIC_BASE_URL
-                if let Some(provider_env) = provider.settings_config.get("env") {
-                    if let Some(config_obj) = final_config.as_object_mut() {
-                        // 获取或创建 env 对象
-                        let env = config_obj.entry("env").or_insert(serde_json::json!({}));
-                        if let Some(env_obj) = env.as_object_mut() {
-                            // 只更新两个特定字段
-                            if let Some(token) = provider_env.get("ANTHROPIC_AUTH_TOKEN") {
-                                env_obj.insert("ANTHROPIC_AUTH_TOKEN".to_string(), token.clone());
-                            }
-                            if let Some(base_url) = provider_env.get("ANTHROPIC_BASE_URL") {
-                                env_obj.insert("ANTHROPIC_BASE_URL".to_string(), base_url.clone());
-                            }
-                        }
-                    }
-                }
-
-                // 写入合并后的配置
-                write_json_file(&settings_path, &final_config)?;
-            }
-            AppType::Codex => {
-                let auth = provider
-                    .settings_config
-                    .get("auth")
-                    .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
-                let cfg_text = provider
-                    .settings_config
-                    .get("config")
-                    .and_then(|v| v.as_str());
-                crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
-            }
-            AppType::Droid => {
-                // Droid: 设置环境变量
-                let api_key = provider
-                    .settings_config
-                    .get("apiKey")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| "Droid 配置缺少 apiKey 字段".to_string())?;
-                crate::droid_config::set_factory_api_key_env(api_key)?;
-            }
-        }
-    }
-
-    // 更新内存并保存配置
-    {
-        let mut config = state
-            .config
-            .lock()
-            .map_err(|e| format!("获取锁失败: {}", e))?;
-        let manager = config
-            .get_manager_mut(&app_type)
-            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-        manager
-            .providers
-            .insert(provider.id.clone(), provider.clone());
-    }
-    state.save()?;
+    // 若目标为当前供应商，服务会先写 live，成功后再更新内存
+    state.modify(|service| service.add_provider(&app_type, provider))?;
 
     Ok(true)
 }
@@ -227,97 +107,8 @@ pub async fn update_provider(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    validate_provider_settings(&app_type, &provider)?;
-
-    // 读取校验 & 是否当前（短锁）
-    let (exists, is_current) = {
-        let config = state
-            .config
-            .lock()
-            .map_err(|e| format!("获取锁失败: {}", e))?;
-        let manager = config
-            .get_manager(&app_type)
-            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-        (
-            manager.providers.contains_key(&provider.id),
-            manager.current == provider.id,
-        )
-    };
-    if !exists {
-        return Err(format!("供应商不存在: {}", provider.id));
-    }
-
-    // 若更新的是当前供应商，先写 live 成功再保存
-    if is_current {
-        match app_type {
-            AppType::Claude => {
-                use crate::config::{read_json_file, write_json_file};
-                let settings_path = crate::config::get_claude_settings_path();
-
-                // 读取现有配置（如果存在）
-                let mut final_config = if settings_path.exists() {
-                    read_json_file::<serde_json::Value>(&settings_path).unwrap_or(serde_json::json!({}))
-                } else {
-                    serde_json::json!({})
-                };
-
-                // 只更新 env 中的 ANTHROPIC_AUTH_TOKEN 和 ANTHROPIC_BASE_URL
-                if let Some(provider_env) = provider.settings_config.get("env") {
-                    if let Some(config_obj) = final_config.as_object_mut() {
-                        // 获取或创建 env 对象
-                        let env = config_obj.entry("env").or_insert(serde_json::json!({}));
-                        if let Some(env_obj) = env.as_object_mut() {
-                            // 只更新两个特定字段
-                            if let Some(token) = provider_env.get("ANTHROPIC_AUTH_TOKEN") {
-                                env_obj.insert("ANTHROPIC_AUTH_TOKEN".to_string(), token.clone());
-                            }
-                            if let Some(base_url) = provider_env.get("ANTHROPIC_BASE_URL") {
-                                env_obj.insert("ANTHROPIC_BASE_URL".to_string(), base_url.clone());
-                            }
-                        }
-                    }
-                }
-
-                // 写入合并后的配置
-                write_json_file(&settings_path, &final_config)?;
-            }
-            AppType::Codex => {
-                let auth = provider
-                    .settings_config
-                    .get("auth")
-                    .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
-                let cfg_text = provider
-                    .settings_config
-                    .get("config")
-                    .and_then(|v| v.as_str());
-                crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
-            }
-            AppType::Droid => {
-                // Droid: 设置环境变量
-                let api_key = provider
-                    .settings_config
-                    .get("apiKey")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| "Droid 配置缺少 apiKey 字段".to_string())?;
-                crate::droid_config::set_factory_api_key_env(api_key)?;
-            }
-        }
-    }
-
-    // 更新内存并保存
-    {
-        let mut config = state
-            .config
-            .lock()
-            .map_err(|e| format!("获取锁失败: {}", e))?;
-        let manager = config
-            .get_manager_mut(&app_type)
-            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-        manager
-            .providers
-            .insert(provider.id.clone(), provider.clone());
-    }
-    state.save()?;
+    // 若更新的是当前供应商，服务会先写 live 成功再更新内存
+    state.modify(|service| service.update_provider(&app_type, provider))?;
 
     Ok(true)
 }
@@ -336,56 +127,12 @@ pub async fn delete_provider(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    let mut config = state
-        .config
-        .lock()
-        .map_err(|e| format!("获取锁失败: {}", e))?;
-
-    let manager = config
-        .get_manager_mut(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    // 检查是否为当前供应商
-    if manager.current == id {
-        return Err("不能删除当前正在使用的供应商".to_string());
-    }
-
-    // 获取供应商信息
-    let provider = manager
-        .providers
-        .get(&id)
-        .ok_or_else(|| format!("供应商不存在: {}", id))?
-        .clone();
-
-    // 删除配置文件
-    match app_type {
-        AppType::Codex => {
-            codex_config::delete_codex_provider_config(&id, &provider.name)?;
-        }
-        AppType::Claude => {
-            use crate::config::{delete_file, get_provider_config_path};
-            // 兼容历史两种命名：settings-{name}.json 与 settings-{id}.json
-            let by_name = get_provider_config_path(&id, Some(&provider.name));
-            let by_id = get_provider_config_path(&id, None);
-            delete_file(&by_name)?;
-            delete_file(&by_id)?;
-        }
-        AppType::Droid => {
-            // Droid 配置存储在主配置文件中，不需要删除额外文件
-        }
-    }
-
-    // 从管理器删除
-    manager.providers.remove(&id);
-
-    // 保存配置
-    drop(config); // 释放锁
-    state.save()?;
+    state.modify(|service| service.delete_provider(&app_type, &id))?;
 
     Ok(true)
 }
 
-/// 切换供应商
+/// 切换供应商（开启连通性检查时一并返回检查结果）
 #[tauri::command]
 pub async fn switch_provider(
     state: State<'_, AppState>,
@@ -393,302 +140,172 @@ pub async fn switch_provider(
     app: Option<String>,
     appType: Option<String>,
     id: String,
-) -> Result<bool, String> {
+) -> Result<SwitchReport, String> {
     let app_type = app_type
         .or_else(|| app.as_deref().map(|s| s.into()))
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    let mut config = state
-        .config
-        .lock()
-        .map_err(|e| format!("获取锁失败: {}", e))?;
-
-    let manager = config
-        .get_manager_mut(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    // 检查供应商是否存在
-    let provider = manager
-        .providers
-        .get(&id)
-        .ok_or_else(|| format!("供应商不存在: {}", id))?
-        .clone();
-
-    // SSOT 切换：先回填 live 配置到当前供应商，然后从内存写入目标主配置
-    match app_type {
-        AppType::Codex => {
-            use serde_json::Value;
-
-            // 回填：读取 live（auth.json + config.toml）写回当前供应商 settings_config
-            if !manager.current.is_empty() {
-                let auth_path = codex_config::get_codex_auth_path();
-                let config_path = codex_config::get_codex_config_path();
-                if auth_path.exists() {
-                    let auth: Value = crate::config::read_json_file(&auth_path)?;
-                    let config_str = if config_path.exists() {
-                        std::fs::read_to_string(&config_path)
-                            .map_err(|e| format!("读取 config.toml 失败: {}", e))?
-                    } else {
-                        String::new()
-                    };
-
-                    let live = serde_json::json!({
-                        "auth": auth,
-                        "config": config_str,
-                    });
-
-                    if let Some(cur) = manager.providers.get_mut(&manager.current) {
-                        cur.settings_config = live;
-                    }
-                }
-            }
-
-            // 切换：从目标供应商 settings_config 写入主配置（Codex 双文件原子+回滚）
-            let auth = provider
-                .settings_config
-                .get("auth")
-                .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
-            let cfg_text = provider
-                .settings_config
-                .get("config")
-                .and_then(|v| v.as_str());
-            crate::codex_config::write_codex_live_atomic(auth, cfg_text)?;
-        }
-        AppType::Claude => {
-            use crate::config::{read_json_file, write_json_file};
-
-            let settings_path = get_claude_settings_path();
-
-            // 回填：回填 env 字段到当前供应商（包含模型配置）
-            if settings_path.exists() && !manager.current.is_empty() {
-                if let Ok(live) = read_json_file::<serde_json::Value>(&settings_path) {
-                    if let Some(cur) = manager.providers.get_mut(&manager.current) {
-                        // 提取并保存完整的 env 字段（包括模型配置）
-                        if let Some(env) = live.get("env") {
-                            cur.settings_config = serde_json::json!({
-                                "env": env.clone()
-                            });
-                        }
-                    }
-                }
-            }
-
-            // 切换：读取现有配置，只更新 env 字段，保留其他用户自定义配置
-            if let Some(parent) = settings_path.parent() {
-                std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
-            }
-
-            // 读取现有配置（如果存在）
-            let mut final_config = if settings_path.exists() {
-                read_json_file::<serde_json::Value>(&settings_path).unwrap_or(serde_json::json!({}))
-            } else {
-                serde_json::json!({})
-            };
-
-            // 更新 env 中的 API 配置字段和模型配置
-            if let Some(provider_env) = provider.settings_config.get("env") {
-                if let Some(config_obj) = final_config.as_object_mut() {
-                    // 获取或创建 env 对象
-                    let env = config_obj.entry("env").or_insert(serde_json::json!({}));
-                    if let Some(env_obj) = env.as_object_mut() {
-                        // 更新 API 认证字段
-                        if let Some(token) = provider_env.get("ANTHROPIC_AUTH_TOKEN") {
-                            env_obj.insert("ANTHROPIC_AUTH_TOKEN".to_string(), token.clone());
-                        }
-                        if let Some(base_url) = provider_env.get("ANTHROPIC_BASE_URL") {
-                            env_obj.insert("ANTHROPIC_BASE_URL".to_string(), base_url.clone());
-                        }
-                        
-                        // 更新模型配置（如果供应商有配置）
-                        if let Some(model) = provider_env.get("ANTHROPIC_MODEL") {
-                            env_obj.insert("ANTHROPIC_MODEL".to_string(), model.clone());
-                        } else {
-                            // 如果新供应商没有配置模型，移除旧的模型配置
-                            env_obj.remove("ANTHROPIC_MODEL");
-                        }
-                        
-                        if let Some(small_model) = provider_env.get("ANTHROPIC_SMALL_FAST_MODEL") {
-                            env_obj.insert("ANTHROPIC_SMALL_FAST_MODEL".to_string(), small_model.clone());
-                        } else {
-                            // 如果新供应商没有配置小模型，移除旧的配置
-                            env_obj.remove("ANTHROPIC_SMALL_FAST_MODEL");
-                        }
-                    }
-                }
-            }
-
-            // 写入合并后的配置
-            write_json_file(&settings_path, &final_config)?;
-        }
-        AppType::Droid => {
-            // Droid: 设置系统环境变量
-            let api_key = provider
-                .settings_config
-                .get("apiKey")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| "目标供应商缺少 apiKey 配置".to_string())?;
-            
-            crate::droid_config::set_factory_api_key_env(api_key)?;
-        }
-    }
-
-    // 更新当前供应商
-    manager.current = id;
-
-    log::info!("成功切换到供应商: {}", provider.name);
+    state.switch_provider(&app_type, &id).await
+}
 
-    // 保存配置
-    drop(config); // 释放锁
-    state.save()?;
+/// 临时切换供应商：`durationSecs` 秒后自动恢复为切换前的供应商
+#[tauri::command]
+pub async fn switch_provider_for(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+    id: String,
+    durationSecs: u64,
+) -> Result<SwitchReport, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    Ok(true)
+    let duration = Duration::from_secs(durationSecs);
+    TemporarySwitch::validate_duration(duration)?;
+    state.switch_provider_for(&app_type, &id, duration).await
 }
 
-/// 停用当前供应商（清空 env 字段）
+/// 获取进行中的临时切换（没有时返回 None）
 #[tauri::command]
-pub async fn disable_current_provider(
+pub async fn get_temporary_switch(
     state: State<'_, AppState>,
     app_type: Option<AppType>,
     app: Option<String>,
     appType: Option<String>,
-) -> Result<bool, String> {
+) -> Result<Option<TemporarySwitch>, String> {
     let app_type = app_type
         .or_else(|| app.as_deref().map(|s| s.into()))
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    // 仅支持 Claude 和 Droid（Codex 需要 auth.json 必须有内容）
-    if app_type != AppType::Claude && app_type != AppType::Droid {
-        return Err("停用功能仅支持 Claude Code 和 Droid".to_string());
-    }
-
-    let mut config = state
-        .config
+    let service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    Ok(service
+        .config()
+        .get_manager(&app_type)
+        .and_then(|m| m.temporary.clone()))
+}
 
-    let manager = config
-        .get_manager_mut(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    match app_type {
-        AppType::Claude => {
-            use crate::config::{read_json_file, write_json_file};
-            let settings_path = get_claude_settings_path();
-
-            // 读取现有配置
-            let mut final_config = if settings_path.exists() {
-                read_json_file::<serde_json::Value>(&settings_path).unwrap_or(serde_json::json!({}))
-            } else {
-                serde_json::json!({})
-            };
-
-            // 清空 env 字段（设置为空对象）
-            if let Some(config_obj) = final_config.as_object_mut() {
-                config_obj.insert("env".to_string(), serde_json::json!({}));
-            }
-
-            // 写入配置
-            if let Some(parent) = settings_path.parent() {
-                std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
-            }
-            write_json_file(&settings_path, &final_config)?;
-
-            log::info!("已停用 Claude 供应商，env 字段已清空");
-        }
-        AppType::Droid => {
-            // 清除系统环境变量
-            crate::droid_config::clear_factory_api_key_env()?;
-            log::info!("已停用 Droid 供应商，环境变量已清除");
-        }
-        _ => {}
-    }
-
-    // 清空当前供应商
-    manager.current = String::new();
+/// 放弃临时切换：保留当前供应商，不再自动恢复
+#[tauri::command]
+pub async fn cancel_temporary_switch(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+) -> Result<bool, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    // 保存配置
-    drop(config); // 释放锁
-    state.save()?;
+    state.modify(|service| service.clear_temporary_switch(&app_type))?;
 
     Ok(true)
 }
 
-/// 快速切换当前供应商的 API 地址（仅 Claude）
+/// 预演切换供应商：返回各 live 文件写入前后的内容、JSON 补丁与文本差异，不写入任何文件
 #[tauri::command]
-pub async fn switch_provider_url(state: State<'_, AppState>, url: String) -> Result<bool, String> {
-    use crate::config::{read_json_file, write_json_file};
+pub async fn preview_switch(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+    id: String,
+) -> Result<SwitchPreview, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    let mut config = state
-        .config
+    let service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    service.preview_switch(&app_type, &id)
+}
 
-    let manager = config
-        .get_manager_mut(&AppType::Claude)
-        .ok_or_else(|| "Claude 应用类型不存在".to_string())?;
-
-    // 获取当前供应商
-    if manager.current.is_empty() {
-        return Err("当前没有激活的供应商".to_string());
-    }
+/// 检查供应商的连通性与凭据（不切换）；`baseUrl` 覆盖配置中的地址
+#[tauri::command]
+pub async fn check_provider_connectivity(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+    id: String,
+    baseUrl: Option<String>,
+) -> Result<PreflightResult, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    let provider = manager
-        .providers
-        .get(&manager.current)
-        .ok_or_else(|| "当前供应商不存在".to_string())?
-        .clone();
+    let provider = state
+        .service
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?
+        .provider_resolved(&app_type, &id)?;
+    Ok(preflight::check(&app_type, &provider, baseUrl.as_deref()).await)
+}
 
-    // 验证 URL 是否在备选列表中
-    if let Some(ref alt_urls) = provider.alternative_urls {
-        if !alt_urls.contains(&url) {
-            return Err(format!("地址 {} 不在供应商的备选列表中", url));
-        }
-    } else {
-        return Err("当前供应商未配置备选地址".to_string());
-    }
+/// 按自动切换规则查询下一次计划中的切换（不指定应用时查询全部应用）
+#[tauri::command]
+pub async fn next_scheduled_switch(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+) -> Result<Option<ScheduledSwitch>, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()));
 
-    let settings_path = get_claude_settings_path();
+    let service = state
+        .service
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    Ok(service.next_scheduled_switch(app_type.as_ref()))
+}
 
-    // 读取现有配置
-    let mut final_config = if settings_path.exists() {
-        read_json_file::<serde_json::Value>(&settings_path).unwrap_or(serde_json::json!({}))
-    } else {
-        serde_json::json!({})
-    };
+/// 停用当前供应商（清理 live 配置并清空 current）
+#[tauri::command]
+pub async fn disable_current_provider(
+    state: State<'_, AppState>,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+) -> Result<bool, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    // 只更新 env.ANTHROPIC_BASE_URL
-    if let Some(config_obj) = final_config.as_object_mut() {
-        let env = config_obj.entry("env").or_insert(serde_json::json!({}));
-        if let Some(env_obj) = env.as_object_mut() {
-            env_obj.insert(
-                "ANTHROPIC_BASE_URL".to_string(),
-                serde_json::Value::String(url.clone()),
-            );
-        }
-    }
+    state.modify(|service| service.disable_current_provider(&app_type))?;
 
-    // 写入配置
-    write_json_file(&settings_path, &final_config)?;
-
-    // 同时更新内存中的供应商配置
-    if let Some(cur) = manager.providers.get_mut(&manager.current) {
-        if let Some(provider_env) = cur.settings_config.get_mut("env") {
-            if let Some(env_obj) = provider_env.as_object_mut() {
-                env_obj.insert(
-                    "ANTHROPIC_BASE_URL".to_string(),
-                    serde_json::Value::String(url.clone()),
-                );
-            }
-        }
-    }
+    Ok(true)
+}
 
-    log::info!("已切换当前供应商的 API 地址到: {}", url);
+/// 快速切换当前供应商的 API 地址（Claude / Codex，未指定应用时为 Claude）
+#[tauri::command]
+pub async fn switch_provider_url(
+    state: State<'_, AppState>,
+    url: String,
+    app_type: Option<AppType>,
+    app: Option<String>,
+    appType: Option<String>,
+) -> Result<bool, String> {
+    let app_type = app_type
+        .or_else(|| app.as_deref().map(|s| s.into()))
+        .or_else(|| appType.as_deref().map(|s| s.into()))
+        .unwrap_or(AppType::Claude);
 
-    // 保存配置
-    drop(config);
-    state.save()?;
+    state.modify(|service| service.switch_provider_url(&app_type, &url))?;
 
     Ok(true)
 }
@@ -706,80 +323,7 @@ pub async fn import_default_config(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    // 仅当 providers 为空时才从 live 导入一条默认项
-    {
-        let config = state
-            .config
-            .lock()
-            .map_err(|e| format!("获取锁失败: {}", e))?;
-
-        if let Some(manager) = config.get_manager(&app_type) {
-            if !manager.get_all_providers().is_empty() {
-                return Ok(true);
-            }
-        }
-    }
-
-    // 根据应用类型导入配置
-    // 读取当前主配置为默认供应商（不再写入副本文件）
-    let settings_config = match app_type {
-        AppType::Codex => {
-            let auth_path = codex_config::get_codex_auth_path();
-            if !auth_path.exists() {
-                return Err("Codex 配置文件不存在".to_string());
-            }
-            let auth: serde_json::Value =
-                crate::config::read_json_file::<serde_json::Value>(&auth_path)?;
-            let config_str = match crate::codex_config::read_and_validate_codex_config_text() {
-                Ok(s) => s,
-                Err(e) => return Err(e),
-            };
-            serde_json::json!({ "auth": auth, "config": config_str })
-        }
-        AppType::Claude => {
-            let settings_path = get_claude_settings_path();
-            if !settings_path.exists() {
-                return Err("Claude Code 配置文件不存在".to_string());
-            }
-            let full_config = crate::config::read_json_file::<serde_json::Value>(&settings_path)?;
-
-            // 只提取 env 字段
-            let env = full_config
-                .get("env")
-                .cloned()
-                .unwrap_or(serde_json::json!({}));
-            serde_json::json!({ "env": env })
-        }
-        AppType::Droid => {
-            return Err("Droid 不支持自动导入默认配置，请手动添加 API Key".to_string());
-        }
-    };
-
-    // 创建默认供应商（仅首次初始化）
-    let provider = Provider::with_id(
-        "default".to_string(),
-        "default".to_string(),
-        settings_config,
-        None,
-    );
-
-    // 添加到管理器
-    let mut config = state
-        .config
-        .lock()
-        .map_err(|e| format!("获取锁失败: {}", e))?;
-
-    let manager = config
-        .get_manager_mut(&app_type)
-        .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-    manager.providers.insert(provider.id.clone(), provider);
-    // 设置当前供应商为默认项
-    manager.current = "default".to_string();
-
-    // 保存配置
-    drop(config); // 释放锁
-    state.save()?;
+    state.modify(|service| service.import_default_config(&app_type))?;
 
     Ok(true)
 }
@@ -858,52 +402,9 @@ pub async fn sync_current_provider_config(
         .or_else(|| appType.as_deref().map(|s| s.into()))
         .unwrap_or(AppType::Claude);
 
-    match app_type {
-        AppType::Claude => {
-            let settings_path = get_claude_settings_path();
-            if !settings_path.exists() {
-                return Err("Claude Code 配置文件不存在".to_string());
-            }
-
-            // 读取 live settings.json
-            let live_config = crate::config::read_json_file::<serde_json::Value>(&settings_path)?;
-
-            // 更新当前供应商的配置
-            let mut config = state
-                .config
-                .lock()
-                .map_err(|e| format!("获取锁失败: {}", e))?;
-
-            let manager = config
-                .get_manager_mut(&app_type)
-                .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
-
-            if !manager.current.is_empty() {
-                if let Some(current_provider) = manager.providers.get_mut(&manager.current) {
-                    // 只提取并同步 env 字段
-                    let env = live_config
-                        .get("env")
-                        .cloned()
-                        .unwrap_or(serde_json::json!({}));
-                    current_provider.settings_config = serde_json::json!({ "env": env });
-                    log::info!("已同步当前供应商 '{}' 的 env 配置", current_provider.name);
-                }
-            }
+    state.modify(|service| service.sync_current_provider_config(&app_type))?;
 
-            // 保存配置
-            drop(config);
-            state.save()?;
-            Ok(true)
-        }
-        AppType::Codex => {
-            // Codex 的同步逻辑（如果需要的话）
-            Ok(true)
-        }
-        AppType::Droid => {
-            // Droid 不需要同步逻辑，配置存储在主配置文件中
-            Ok(true)
-        }
-    }
+    Ok(true)
 }
 
 /// 获取当前生效的配置目录
@@ -1014,12 +515,53 @@ pub async fn open_external(app: tauri::AppHandle, url: String) -> Result<bool, S
 /// 获取应用配置文件路径
 #[tauri::command]
 pub async fn get_app_config_path() -> Result<String, String> {
-    use crate::config::get_app_config_path;
+    use crate::config::get_app_config_enc_path;
 
-    let config_path = get_app_config_path();
+    let config_path = get_app_config_enc_path();
     Ok(config_path.to_string_lossy().to_string())
 }
 
+/// 获取启动时的配置恢复结果（配置正常加载时为 null）
+#[tauri::command]
+pub async fn get_config_recovery_status(
+    state: State<'_, AppState>,
+) -> Result<Option<RecoveryReport>, String> {
+    Ok(state.recovery.clone())
+}
+
+/// 列出配置快照（从新到旧）
+#[tauri::command]
+pub async fn list_config_snapshots() -> Result<Vec<SnapshotInfo>, String> {
+    snapshot::list_snapshots()
+}
+
+/// 比较配置快照与当前配置
+#[tauri::command]
+pub async fn diff_config_snapshot(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<SnapshotDiff, String> {
+    let service = state
+        .service
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    service.diff_snapshot(&id)
+}
+
+/// 从快照恢复配置（原子替换 config.enc.json）
+#[tauri::command]
+pub async fn restore_config_snapshot(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<bool, String> {
+    let mut service = state
+        .service
+        .lock()
+        .map_err(|e| format!("获取锁失败: {}", e))?;
+    service.restore_snapshot(&id)?;
+    Ok(true)
+}
+
 /// 打开应用配置文件夹
 #[tauri::command]
 pub async fn open_app_config_folder(handle: tauri::AppHandle) -> Result<bool, String> {
@@ -1051,6 +593,7 @@ pub async fn get_settings() -> Result<crate::settings::AppSettings, String> {
 #[tauri::command]
 pub async fn save_settings(
     app: tauri::AppHandle,
+    state: State<'_, AppState>,
     settings: crate::settings::AppSettings,
 ) -> Result<bool, String> {
     // 处理开机自启动设置
@@ -1108,7 +651,13 @@ pub async fn save_settings(
         }
     }
 
+    let codex_profiles_changed = old_settings.codex_profiles != settings.codex_profiles;
     crate::settings::update_settings(settings)?;
+
+    // 开启时为全部 Codex 供应商生成 profile，关闭时清理
+    if codex_profiles_changed {
+        state.modify(|s| s.sync_codex_profiles())?;
+    }
     Ok(true)
 }
 
@@ -1333,17 +882,18 @@ pub async fn get_mcp_config(
     state: State<'_, AppState>,
     app: Option<String>,
 ) -> Result<McpConfigResponse, String> {
-    let config_path = crate::config::get_app_config_path()
+    let config_path = crate::config::get_app_config_enc_path()
         .to_string_lossy()
         .to_string();
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config_mut();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
-    let (servers, normalized) = crate::mcp::get_servers_snapshot_for(&mut cfg, &app_ty);
+    let (servers, normalized) = crate::mcp::get_servers_snapshot_for(cfg, &app_ty);
     let need_save = normalized > 0;
-    drop(cfg);
+    drop(service);
     if need_save {
         state.save()?;
     }
@@ -1361,25 +911,26 @@ pub async fn upsert_mcp_server_in_config(
     id: String,
     spec: serde_json::Value,
 ) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config_mut();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
 
     // v3.5.1: 检查 MCP 是否已启用（编辑前）
-    let was_enabled = crate::mcp::is_mcp_enabled(&cfg, &app_ty, &id);
+    let was_enabled = crate::mcp::is_mcp_enabled(cfg, &app_ty, &id);
 
-    let changed = crate::mcp::upsert_in_config_for(&mut cfg, &app_ty, &id, spec)?;
+    let changed = crate::mcp::upsert_in_config_for(cfg, &app_ty, &id, spec)?;
 
     // v3.5.1: 如果 MCP 已启用，自动同步到 live 配置
     if was_enabled {
         match app_ty {
             crate::app_config::AppType::Claude => {
-                crate::mcp::sync_enabled_to_claude(&cfg)?;
+                crate::mcp::sync_enabled_to_claude(cfg)?;
             }
             crate::app_config::AppType::Codex => {
-                crate::mcp::sync_enabled_to_codex(&cfg)?;
+                crate::mcp::sync_enabled_to_codex(cfg)?;
             }
             crate::app_config::AppType::Droid => {
                 // Droid 暂不支持 MCP 同步
@@ -1388,7 +939,7 @@ pub async fn upsert_mcp_server_in_config(
         log::info!("已自动同步已启用的 MCP '{}' 到 live 配置", id);
     }
 
-    drop(cfg);
+    drop(service);
     state.save()?;
     Ok(changed)
 }
@@ -1400,22 +951,24 @@ pub async fn delete_mcp_server_in_config(
     app: Option<String>,
     id: String,
 ) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config_mut();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
-    let existed = crate::mcp::delete_in_config_for(&mut cfg, &app_ty, &id)?;
-    drop(cfg);
+    let existed = crate::mcp::delete_in_config_for(cfg, &app_ty, &id)?;
+    drop(service);
     state.save()?;
     // 若删除的是 Claude/Codex 客户端的条目，则同步一次，确保启用项从对应 live 配置中移除
-    let cfg2 = state
-        .config
+    let service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg2 = service.config();
     match app_ty {
-        crate::app_config::AppType::Claude => crate::mcp::sync_enabled_to_claude(&cfg2)?,
-        crate::app_config::AppType::Codex => crate::mcp::sync_enabled_to_codex(&cfg2)?,
+        crate::app_config::AppType::Claude => crate::mcp::sync_enabled_to_claude(cfg2)?,
+        crate::app_config::AppType::Codex => crate::mcp::sync_enabled_to_codex(cfg2)?,
         crate::app_config::AppType::Droid => {
             // Droid 暂不支持 MCP 同步
         }
@@ -1431,13 +984,14 @@ pub async fn set_mcp_enabled(
     id: String,
     enabled: bool,
 ) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config_mut();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
-    let changed = crate::mcp::set_enabled_and_sync_for(&mut cfg, &app_ty, &id, enabled)?;
-    drop(cfg);
+    let changed = crate::mcp::set_enabled_and_sync_for(cfg, &app_ty, &id, enabled)?;
+    drop(service);
     state.save()?;
     Ok(changed)
 }
@@ -1445,14 +999,15 @@ pub async fn set_mcp_enabled(
 /// 手动同步：将启用的 MCP 投影到 ~/.claude.json（不更改 config.json）
 #[tauri::command]
 pub async fn sync_enabled_mcp_to_claude(state: State<'_, AppState>) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let normalized = crate::mcp::normalize_servers_for(&mut cfg, &AppType::Claude);
-    crate::mcp::sync_enabled_to_claude(&cfg)?;
+    let cfg = service.config_mut();
+    let normalized = crate::mcp::normalize_servers_for(cfg, &AppType::Claude);
+    crate::mcp::sync_enabled_to_claude(cfg)?;
     let need_save = normalized > 0;
-    drop(cfg);
+    drop(service);
     if need_save {
         state.save()?;
     }
@@ -1462,14 +1017,15 @@ pub async fn sync_enabled_mcp_to_claude(state: State<'_, AppState>) -> Result<bo
 /// 手动同步：将启用的 MCP 投影到 ~/.codex/config.toml（不更改 config.json）
 #[tauri::command]
 pub async fn sync_enabled_mcp_to_codex(state: State<'_, AppState>) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let normalized = crate::mcp::normalize_servers_for(&mut cfg, &AppType::Codex);
-    crate::mcp::sync_enabled_to_codex(&cfg)?;
+    let cfg = service.config_mut();
+    let normalized = crate::mcp::normalize_servers_for(cfg, &AppType::Codex);
+    crate::mcp::sync_enabled_to_codex(cfg)?;
     let need_save = normalized > 0;
-    drop(cfg);
+    drop(service);
     if need_save {
         state.save()?;
     }
@@ -1479,12 +1035,13 @@ pub async fn sync_enabled_mcp_to_codex(state: State<'_, AppState>) -> Result<boo
 /// 从 ~/.claude.json 导入 MCP 定义到 config.json，返回变更数量
 #[tauri::command]
 pub async fn import_mcp_from_claude(state: State<'_, AppState>) -> Result<usize, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let changed = crate::mcp::import_from_claude(&mut cfg)?;
-    drop(cfg);
+    let cfg = service.config_mut();
+    let changed = crate::mcp::import_from_claude(cfg)?;
+    drop(service);
     if changed > 0 {
         state.save()?;
     }
@@ -1494,12 +1051,13 @@ pub async fn import_mcp_from_claude(state: State<'_, AppState>) -> Result<usize,
 /// 从 ~/.codex/config.toml 导入 MCP 定义到 config.json（Codex 作用域），返回变更数量
 #[tauri::command]
 pub async fn import_mcp_from_codex(state: State<'_, AppState>) -> Result<usize, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
-    let changed = crate::mcp::import_from_codex(&mut cfg)?;
-    drop(cfg);
+    let cfg = service.config_mut();
+    let changed = crate::mcp::import_from_codex(cfg)?;
+    drop(service);
     if changed > 0 {
         state.save()?;
     }
@@ -1517,12 +1075,13 @@ pub async fn check_mcp_sync_conflict(
     app: Option<String>,
     id: String,
 ) -> Result<bool, String> {
-    let cfg = state
-        .config
+    let service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
-    let exists = crate::mcp::check_mcp_exists_in_other_app(&cfg, &app_ty, &id);
+    let exists = crate::mcp::check_mcp_exists_in_other_app(cfg, &app_ty, &id);
     Ok(exists)
 }
 
@@ -1534,13 +1093,14 @@ pub async fn sync_mcp_to_other_app(
     id: String,
     overwrite: bool,
 ) -> Result<bool, String> {
-    let mut cfg = state
-        .config
+    let mut service = state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    let cfg = service.config_mut();
     let app_ty = crate::app_config::AppType::from(app.as_deref().unwrap_or("claude"));
-    let synced = crate::mcp::copy_mcp_to_other_app(&mut cfg, &app_ty, &id, overwrite)?;
-    drop(cfg);
+    let synced = crate::mcp::copy_mcp_to_other_app(cfg, &app_ty, &id, overwrite)?;
+    drop(service);
     if synced {
         state.save()?;
     }
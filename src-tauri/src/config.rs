@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 // unused import removed
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
 /// 获取 Claude Code 配置目录路径
 pub fn get_claude_config_dir() -> PathBuf {
@@ -38,11 +42,46 @@ pub fn get_app_config_dir() -> PathBuf {
         .join(".cc-switch")
 }
 
-/// 获取应用配置文件路径
+/// 获取旧版明文配置文件路径 (~/.cc-switch/config.json)，仅用于迁移与兼容
 pub fn get_app_config_path() -> PathBuf {
     get_app_config_dir().join("config.json")
 }
 
+/// 获取加密配置文件路径 (~/.cc-switch/config.enc.json)
+pub fn get_app_config_enc_path() -> PathBuf {
+    get_app_config_dir().join("config.enc.json")
+}
+
+/// 获取加密配置密钥文件路径 (~/.cc-switch/config.key)
+pub fn get_config_key_path() -> PathBuf {
+    get_app_config_dir().join("config.key")
+}
+
+/// 获取密钥库路径 (~/.cc-switch/secrets.json)
+pub fn get_secrets_path() -> PathBuf {
+    get_app_config_dir().join("secrets.json")
+}
+
+/// 跨进程配置锁文件路径 (~/.cc-switch/config.lock)
+pub fn get_config_lock_path() -> PathBuf {
+    get_app_config_dir().join("config.lock")
+}
+
+/// 切换供应商的预写日志路径 (~/.cc-switch/switch.journal.json)
+pub fn get_switch_journal_path() -> PathBuf {
+    get_app_config_dir().join("switch.journal.json")
+}
+
+/// 结构迁移日志路径 (~/.cc-switch/migrations.log)
+pub fn get_migration_log_path() -> PathBuf {
+    get_app_config_dir().join("migrations.log")
+}
+
+/// 配置快照目录 (~/.cc-switch/snapshots)
+pub fn get_snapshots_dir() -> PathBuf {
+    get_app_config_dir().join("snapshots")
+}
+
 /// 归档根目录 ~/.cc-switch/archive
 pub fn get_archive_root() -> PathBuf {
     get_app_config_dir().join("archive")
@@ -92,6 +131,21 @@ pub fn archive_file(ts: u64, category: &str, src: &Path) -> Result<Option<PathBu
     Ok(Some(dest))
 }
 
+/// 与 [`archive_file`] 相同的归档位置，但写入给定内容（权限 0600），用于需要先加密再归档的文件
+pub fn archive_private_bytes(
+    ts: u64,
+    category: &str,
+    file_name: &str,
+    data: &[u8],
+) -> Result<PathBuf, String> {
+    let mut dest_dir = get_archive_root();
+    dest_dir.push(ts.to_string());
+    dest_dir.push(category);
+    let dest = ensure_unique_path(dest_dir.join(file_name));
+    crate::secure_store::write_private_file(&dest, data)?;
+    Ok(dest)
+}
+
 /// 清理供应商名称，确保文件名安全
 pub fn sanitize_provider_name(name: &str) -> String {
     name.chars()
@@ -192,9 +246,35 @@ pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), String> {
     {
         fs::rename(&tmp, path).map_err(|e| format!("原子替换失败: {}", e))?;
     }
+
+    if let Ok(mut writes) = self_writes().lock() {
+        writes.insert(path.to_path_buf(), content_hash(data));
+    }
     Ok(())
 }
 
+/// 计算文件内容哈希（仅用于同一进程内的变化检测，不保证跨版本稳定）
+pub fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 本进程通过 [`atomic_write`] 最近写入的文件及其内容哈希
+fn self_writes() -> &'static Mutex<HashMap<PathBuf, u64>> {
+    static WRITES: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+    WRITES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 文件当前内容是否正是本进程最近一次写入的内容（用于文件监听时忽略自身写入）
+pub fn is_self_write(path: &Path, data: &[u8]) -> bool {
+    self_writes()
+        .lock()
+        .ok()
+        .and_then(|writes| writes.get(path).copied())
+        == Some(content_hash(data))
+}
+
 /// 复制文件
 pub fn copy_file(from: &Path, to: &Path) -> Result<(), String> {
     fs::copy(from, to).map_err(|e| format!("复制文件失败: {}", e))?;
@@ -209,6 +289,34 @@ pub fn delete_file(path: &Path) -> Result<(), String> {
     Ok(())
 }
 
+/// 先用零覆写内容再删除文件，用于含明文密钥的文件
+///
+/// 尽力而为：写时复制文件系统、快照或 SSD 磨损均衡下不保证原数据不可恢复
+pub fn shred_file(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let len = fs::metadata(path)
+        .map_err(|e| format!("读取文件信息失败: {}", e))?
+        .len();
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| format!("打开文件失败: {}", e))?;
+    let zeros = vec![0u8; 8192];
+    let mut remaining = len;
+    while remaining > 0 {
+        let n = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..n])
+            .map_err(|e| format!("覆写文件失败: {}", e))?;
+        remaining -= n as u64;
+    }
+    file.sync_all()
+        .map_err(|e| format!("覆写文件失败: {}", e))?;
+    drop(file);
+    delete_file(path)
+}
+
 /// 检查 Claude Code 配置状态
 #[derive(Serialize, Deserialize)]
 pub struct ConfigStatus {
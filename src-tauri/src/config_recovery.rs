@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_config::{LoadError, MultiAppConfig};
+use crate::config::{
+    get_app_config_dir, get_app_config_enc_path, get_app_config_path, get_archive_root,
+};
+
+/// 配置损坏后的恢复结果（通过 `config-recovered` 事件与 `get_config_recovery_status` 命令提供给前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    /// 原始加载失败原因
+    pub error: String,
+    /// 损坏文件的隔离路径（未隔离时为空）
+    pub quarantined_path: Option<String>,
+    /// 恢复来源：`backup`（.bak）或 `archive`（归档副本）；无法恢复时为空
+    pub source: Option<String>,
+    /// 恢复所用文件路径
+    pub source_path: Option<String>,
+    /// 是否进入只读模式（拒绝保存）
+    pub read_only: bool,
+}
+
+/// 加载配置；文件损坏时隔离损坏文件，依次尝试 `.bak` 与最新归档副本恢复，仍失败则进入只读模式
+///
+/// 锁超时、读写错误、密钥不可用或版本高于当前支持等非损坏错误不改动文件，直接进入只读模式。
+/// 返回的配置尚未落盘，恢复成功时由调用方保存
+pub fn load_or_recover() -> (MultiAppConfig, Option<RecoveryReport>) {
+    let error = match MultiAppConfig::load() {
+        Ok(config) => return (config, None),
+        Err(LoadError::Corrupt(e)) => e,
+        Err(LoadError::Other(e)) => {
+            // 文件本身未必损坏：不隔离，直接只读，等待重试、解锁或升级
+            log::error!("加载配置失败，进入只读模式: {}", e);
+            return (MultiAppConfig::default(), Some(read_only_report(e, None)));
+        }
+    };
+    log::error!("配置文件已损坏: {}", error);
+
+    let enc_path = get_app_config_enc_path();
+    let active = if enc_path.exists() {
+        enc_path
+    } else {
+        get_app_config_path()
+    };
+
+    let quarantined = match quarantine(&active) {
+        Ok(path) => path,
+        Err(e) => {
+            // 无法移走损坏文件时保存会覆盖它，只能只读
+            log::error!("隔离损坏的配置文件失败，进入只读模式: {}", e);
+            return (
+                MultiAppConfig::default(),
+                Some(read_only_report(error, None)),
+            );
+        }
+    };
+    let quarantined_path = quarantined.map(|p| p.to_string_lossy().to_string());
+
+    for (source, path) in recovery_candidates() {
+        match MultiAppConfig::read_from_path(&path) {
+            Ok(config) => {
+                log::warn!("已从 {} 恢复配置: {}", source, path.display());
+                let report = RecoveryReport {
+                    error,
+                    quarantined_path,
+                    source: Some(source.to_string()),
+                    source_path: Some(path.to_string_lossy().to_string()),
+                    read_only: false,
+                };
+                return (config, Some(report));
+            }
+            Err(e) => log::warn!("恢复候选不可用 {}: {}", path.display(), e),
+        }
+    }
+
+    log::error!("没有可用的配置副本，进入只读模式");
+    (
+        MultiAppConfig::default(),
+        Some(read_only_report(error, quarantined_path)),
+    )
+}
+
+fn read_only_report(error: String, quarantined_path: Option<String>) -> RecoveryReport {
+    RecoveryReport {
+        error,
+        quarantined_path,
+        source: None,
+        source_path: None,
+        read_only: true,
+    }
+}
+
+/// 将损坏文件重命名为 `<name>.corrupt.<ts>`，保留原始内容以便人工排查
+fn quarantine(path: &Path) -> Result<Option<PathBuf>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let file_name = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config".into());
+    let mut dest = path.with_file_name(format!("{}.corrupt.{}", file_name, ts));
+    let mut n = 1;
+    while dest.exists() {
+        dest = path.with_file_name(format!("{}.corrupt.{}.{}", file_name, ts, n));
+        n += 1;
+    }
+    fs::rename(path, &dest).map_err(|e| format!("隔离损坏的配置文件失败: {}", e))?;
+    log::warn!("已隔离损坏的配置文件: {}", dest.display());
+    Ok(Some(dest))
+}
+
+/// 恢复候选（按优先级）：`.bak` 备份，然后是最新的 `archive/<ts>/cc-switch` 副本
+fn recovery_candidates() -> Vec<(&'static str, PathBuf)> {
+    let dir = get_app_config_dir();
+    let mut candidates: Vec<(&'static str, PathBuf)> = ["config.enc.json.bak", "config.json.bak"]
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|p| p.exists())
+        .map(|p| ("backup", p))
+        .collect();
+    candidates.extend(archive_copies().into_iter().map(|p| ("archive", p)));
+    candidates
+}
+
+/// 归档中的配置副本，按时间戳从新到旧排列
+fn archive_copies() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(get_archive_root()) else {
+        return Vec::new();
+    };
+    let mut stamps: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let ts = e.file_name().to_string_lossy().parse::<u64>().ok()?;
+            Some((ts, e.path()))
+        })
+        .collect();
+    stamps.sort_by_key(|(ts, _)| std::cmp::Reverse(*ts));
+
+    stamps
+        .into_iter()
+        .flat_map(|(_, dir)| {
+            let category = dir.join("cc-switch");
+            ["config.enc.json", "config.json"]
+                .into_iter()
+                .map(move |name| category.join(name))
+        })
+        .filter(|p| p.exists())
+        .collect()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::schema_migration::CURRENT_VERSION;
+    use crate::test_support::TempHome;
+    use serde_json::json;
+    use std::sync::mpsc;
+
+    fn corrupt_copies() -> Vec<PathBuf> {
+        fs::read_dir(get_app_config_dir())
+            .unwrap()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.to_string_lossy().contains(".corrupt."))
+            .collect()
+    }
+
+    #[test]
+    fn lock_timeout_goes_read_only_without_touching_file() {
+        let _home = TempHome::new();
+        // 旧版明文配置：加载时需要迁移并在配置锁下保存
+        let plain = get_app_config_path();
+        let content = r#"{"version": 2, "claude": {"providers": {}, "current": ""}}"#;
+        fs::create_dir_all(get_app_config_dir()).unwrap();
+        fs::write(&plain, content).unwrap();
+
+        let (locked_tx, locked_rx) = mpsc::channel();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let holder = std::thread::spawn(move || {
+            crate::config_sync::with_config_lock(|| {
+                locked_tx.send(()).unwrap();
+                release_rx.recv().ok();
+                Ok(())
+            })
+        });
+        locked_rx.recv().unwrap();
+        let (_, report) = load_or_recover();
+        release_tx.send(()).unwrap();
+        holder.join().unwrap().unwrap();
+
+        let report = report.expect("加载失败应返回报告");
+        assert!(report.read_only);
+        assert!(report.error.contains("占用"), "{}", report.error);
+        assert!(report.quarantined_path.is_none());
+        assert_eq!(fs::read_to_string(&plain).unwrap(), content);
+        assert!(!get_app_config_enc_path().exists());
+        assert!(corrupt_copies().is_empty());
+    }
+
+    #[test]
+    fn newer_version_goes_read_only_without_touching_file() {
+        let _home = TempHome::new();
+        let newer = json!({
+            "version": CURRENT_VERSION + 1,
+            "claude": { "providers": {}, "current": "" }
+        });
+        let sealed = crate::secure_store::encrypt_json_bytes(newer.to_string().as_bytes()).unwrap();
+        let enc_path = get_app_config_enc_path();
+        crate::secure_store::write_private_file(&enc_path, &sealed).unwrap();
+
+        let (_, report) = load_or_recover();
+
+        let report = report.expect("加载失败应返回报告");
+        assert!(report.read_only);
+        assert!(report.error.contains("高于当前支持"), "{}", report.error);
+        assert!(report.quarantined_path.is_none());
+        assert_eq!(fs::read(&enc_path).unwrap(), sealed);
+        assert!(corrupt_copies().is_empty());
+    }
+
+    #[test]
+    fn corrupt_file_is_quarantined_and_recovered_from_backup() {
+        let _home = TempHome::new();
+        let config = MultiAppConfig::default();
+        // 第二次保存时把第一次的内容备份为 .bak
+        config.save().unwrap();
+        config.save().unwrap();
+        let enc_path = get_app_config_enc_path();
+        fs::write(&enc_path, "not json").unwrap();
+
+        let (_, report) = load_or_recover();
+
+        let report = report.expect("加载失败应返回报告");
+        assert!(!report.read_only);
+        assert_eq!(report.source.as_deref(), Some("backup"));
+        let quarantined = PathBuf::from(report.quarantined_path.unwrap());
+        assert_eq!(fs::read_to_string(quarantined).unwrap(), "not json");
+        assert!(!enc_path.exists());
+    }
+}
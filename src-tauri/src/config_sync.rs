@@ -0,0 +1,186 @@
+use serde::Serialize;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::app_config::MultiAppConfig;
+use crate::config::{content_hash, get_app_config_enc_path, get_config_lock_path};
+use crate::provider::ProviderManager;
+
+/// 等待其他进程释放配置锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    /// 当前线程已持有配置锁的层数（同一线程内嵌套调用时不重复加锁，避免自锁）
+    static LOCK_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+struct DepthGuard;
+
+impl DepthGuard {
+    fn enter() -> Self {
+        LOCK_DEPTH.with(|d| d.set(d.get() + 1));
+        DepthGuard
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        LOCK_DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+    }
+}
+
+/// 在 `~/.cc-switch/config.lock` 咨询锁保护下执行“读取 - 修改 - 保存”
+///
+/// GUI、CLI、脚本等多个进程通过同一把文件锁互斥；锁被占用时最多等待 10 秒。
+/// 同一线程内可嵌套调用
+pub fn with_config_lock<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    if LOCK_DEPTH.with(|d| d.get()) > 0 {
+        return f();
+    }
+
+    let path = get_config_lock_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(&path)
+        .map_err(|e| format!("打开配置锁文件失败: {}", e))?;
+    let mut lock = fd_lock::RwLock::new(file);
+
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        match lock.try_write() {
+            Ok(_guard) => {
+                let _depth = DepthGuard::enter();
+                return f();
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err("配置文件正被其他进程占用，请稍后重试".to_string());
+                }
+                std::thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(e) => return Err(format!("获取配置文件锁失败: {}", e)),
+        }
+    }
+}
+
+/// 配置文件在某一时刻的磁盘状态（修改时间 + 内容哈希），用于发现其他进程的写入
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskStamp {
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl DiskStamp {
+    /// 读取 `config.enc.json` 当前状态（文件不存在时返回 None）
+    pub fn current() -> Option<Self> {
+        let path = get_app_config_enc_path();
+        let modified = fs::metadata(&path).ok()?.modified().ok();
+        let content = fs::read(&path).ok()?;
+        Some(Self {
+            modified,
+            hash: content_hash(&content),
+        })
+    }
+}
+
+/// 自 `last` 记录以来配置文件的修改时间或内容是否发生变化
+pub fn disk_changed(last: Option<&DiskStamp>) -> bool {
+    DiskStamp::current().as_ref() != last
+}
+
+/// 两份配置是否等价（按序列化结果比较，忽略 HashMap 顺序）
+pub fn same_config(a: &MultiAppConfig, b: &MultiAppConfig) -> bool {
+    same_value(a, b)
+}
+
+fn same_value<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// 三方合并：以磁盘上的最新配置 `theirs` 为底，叠加本进程相对 `base`（上次加载 / 保存时的配置）所做的修改
+///
+/// 粒度为单个供应商、单个 MCP 服务器与各应用的当前供应商；双方改动同一项时以本进程为准
+pub fn merge_configs(
+    base: &MultiAppConfig,
+    ours: &MultiAppConfig,
+    theirs: &MultiAppConfig,
+) -> MultiAppConfig {
+    let mut merged = theirs.clone();
+    merged.version = ours.version.max(theirs.version);
+
+    let empty = ProviderManager::default();
+    let apps: HashSet<&String> = ours.apps.keys().chain(base.apps.keys()).collect();
+    for app in apps {
+        let base_manager = base.apps.get(app).unwrap_or(&empty);
+        let our_manager = ours.apps.get(app).unwrap_or(&empty);
+        let target = merged.apps.entry(app.clone()).or_default();
+
+        merge_map(
+            &base_manager.providers,
+            &our_manager.providers,
+            &mut target.providers,
+        );
+        if our_manager.current != base_manager.current {
+            target.current = our_manager.current.clone();
+        }
+        if our_manager.managed_env_keys != base_manager.managed_env_keys {
+            target.managed_env_keys = our_manager.managed_env_keys.clone();
+        }
+        if our_manager.managed_settings_paths != base_manager.managed_settings_paths {
+            target.managed_settings_paths = our_manager.managed_settings_paths.clone();
+        }
+        if our_manager.temporary != base_manager.temporary {
+            target.temporary = our_manager.temporary.clone();
+        }
+        if our_manager.codex_profile_ids != base_manager.codex_profile_ids {
+            target.codex_profile_ids = our_manager.codex_profile_ids.clone();
+        }
+    }
+
+    merge_map(
+        &base.mcp.claude.servers,
+        &ours.mcp.claude.servers,
+        &mut merged.mcp.claude.servers,
+    );
+    merge_map(
+        &base.mcp.codex.servers,
+        &ours.mcp.codex.servers,
+        &mut merged.mcp.codex.servers,
+    );
+    merge_map(
+        &base.mcp.droid.servers,
+        &ours.mcp.droid.servers,
+        &mut merged.mcp.droid.servers,
+    );
+
+    merged
+}
+
+/// 将 `ours` 相对 `base` 的新增、修改、删除应用到 `target`
+fn merge_map<V: Clone + Serialize>(
+    base: &HashMap<String, V>,
+    ours: &HashMap<String, V>,
+    target: &mut HashMap<String, V>,
+) {
+    for (id, value) in ours {
+        let changed = base.get(id).is_none_or(|old| !same_value(old, value));
+        if changed {
+            target.insert(id.clone(), value.clone());
+        }
+    }
+    for id in base.keys() {
+        if !ours.contains_key(id) {
+            target.remove(id);
+        }
+    }
+}
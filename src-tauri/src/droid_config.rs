@@ -171,19 +171,62 @@ fn broadcast_env_change() {
 /// Unix-like: 设置环境变量（通过修改 shell 配置文件）
 #[cfg(not(target_os = "windows"))]
 fn set_unix_env_var(name: &str, value: &str) -> Result<(), String> {
-    let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
-    
+    for (config_path, _, new_content) in plan_unix_env_var(name, value)? {
+        std::fs::write(&config_path, new_content)
+            .map_err(|e| format!("写入 {} 失败: {}", config_path.display(), e))?;
+
+        log::info!("已更新环境变量到: {}", config_path.display());
+    }
+
+    log::info!("已设置环境变量: {} = {}（需要重启终端生效）", name, value);
+    Ok(())
+}
+
+/// 计算设置 Factory_API_Key 时各 shell 配置文件的改动（不写入），返回 (路径, 原内容, 新内容)
+///
+/// Windows 下写入注册表中的用户环境变量，不涉及文件，返回空列表
+pub fn plan_factory_api_key_env(api_key: &str) -> Result<Vec<(PathBuf, String, String)>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        let _ = api_key;
+        Ok(Vec::new())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        plan_unix_env_var("Factory_API_Key", api_key)
+    }
+}
+
+/// 设置 / 清除 Factory_API_Key 时可能改写的文件（Windows 下写注册表，返回空列表）
+pub fn factory_api_key_env_files() -> Result<Vec<PathBuf>, String> {
+    #[cfg(target_os = "windows")]
+    {
+        Ok(Vec::new())
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = dirs::home_dir().ok_or("无法获取用户主目录")?;
+        Ok(vec![
+            home.join(".bashrc"),
+            home.join(".zshrc"),
+            home.join(".profile"),
+        ])
+    }
+}
+
+/// Unix-like: 计算写入环境变量后的 shell 配置文件内容（仅包含已存在的文件）
+#[cfg(not(target_os = "windows"))]
+fn plan_unix_env_var(name: &str, value: &str) -> Result<Vec<(PathBuf, String, String)>, String> {
     // 尝试写入多个常见的 shell 配置文件
-    let shell_configs = vec![
-        home.join(".bashrc"),
-        home.join(".zshrc"),
-        home.join(".profile"),
-    ];
+    let shell_configs = factory_api_key_env_files()?;
 
     let export_line = format!("export {}=\"{}\"", name, value);
     let marker_start = format!("# CC-Switch Droid Config Start");
     let marker_end = format!("# CC-Switch Droid Config End");
 
+    let mut plans = Vec::new();
     for config_path in shell_configs {
         if config_path.exists() {
             match std::fs::read_to_string(&config_path) {
@@ -203,10 +246,7 @@ fn set_unix_env_var(name: &str, value: &str) -> Result<(), String> {
                         format!("{}\n\n{}\n{}\n{}\n", content.trim_end(), marker_start, export_line, marker_end)
                     };
 
-                    std::fs::write(&config_path, new_content)
-                        .map_err(|e| format!("写入 {} 失败: {}", config_path.display(), e))?;
-
-                    log::info!("已更新环境变量到: {}", config_path.display());
+                    plans.push((config_path, content, new_content));
                 }
                 Err(e) => {
                     log::warn!("读取 {} 失败: {}", config_path.display(), e);
@@ -215,8 +255,7 @@ fn set_unix_env_var(name: &str, value: &str) -> Result<(), String> {
         }
     }
 
-    log::info!("已设置环境变量: {} = {}（需要重启终端生效）", name, value);
-    Ok(())
+    Ok(plans)
 }
 
 /// Unix-like: 清除环境变量
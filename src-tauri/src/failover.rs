@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use crate::app_config::AppType;
+use crate::provider::Provider;
+use crate::speedtest::{self, EndpointLatency};
+
+/// 当前供应商未开启监控时，隔一段时间重新读取
+const IDLE_INTERVAL: Duration = Duration::from_secs(15);
+const MIN_INTERVAL_SECS: u64 = 10;
+/// 冷却上限（7 天）：`cooldownSecs` 来自用户配置，过大的值会使截止时间溢出
+const MAX_COOLDOWN_SECS: u64 = 7 * 24 * 60 * 60;
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+fn default_latency_threshold_ms() -> u64 {
+    5000
+}
+
+fn default_cooldown_secs() -> u64 {
+    600
+}
+
+/// 备选地址自动故障转移策略（按供应商开启，Claude 与 Codex）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 探测当前地址的间隔秒数（最小 10）
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// 连续多少次探测异常（失败或超过延迟阈值）后切换
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 延迟超过该值的探测视为异常
+    #[serde(default = "default_latency_threshold_ms")]
+    pub latency_threshold_ms: u64,
+    /// 自动切换后的冷却秒数，期间不再探测与切换（最长 7 天）
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+impl FailoverPolicy {
+    /// 探测结果是否健康：请求成功、非 5xx 且延迟未超过阈值
+    fn is_healthy(&self, result: &EndpointLatency) -> bool {
+        result.error.is_none()
+            && result.status.is_some_and(|s| s < 500)
+            && result
+                .latency
+                .is_some_and(|ms| ms <= self.latency_threshold_ms as u128)
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(MIN_INTERVAL_SECS))
+    }
+
+    fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs.min(MAX_COOLDOWN_SECS))
+    }
+}
+
+/// 一次自动切换（同时作为 `endpoint-failover` 事件发送给前端）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailoverEvent {
+    pub app_type: AppType,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub from_url: String,
+    pub to_url: String,
+    /// 切换原因（最后一次探测的结果）
+    pub reason: String,
+    /// 新地址的探测延迟
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u128>,
+}
+
+/// 需要监控的目标：开启了故障转移且至少有一个其他备选地址的当前供应商
+struct Target {
+    app_type: AppType,
+    provider_id: String,
+    provider_name: String,
+    url: String,
+    alternatives: Vec<String>,
+    policy: FailoverPolicy,
+}
+
+impl Target {
+    fn from_provider(app_type: AppType, provider: &Provider) -> Option<Self> {
+        let policy = provider.failover.clone().filter(|p| p.enabled)?;
+        let url = provider.base_url(&app_type)?;
+        let alternatives: Vec<String> = provider
+            .alternative_urls
+            .iter()
+            .flatten()
+            .filter(|u| u.trim() != url.trim() && !u.trim().is_empty())
+            .cloned()
+            .collect();
+        if alternatives.is_empty() {
+            return None;
+        }
+        Some(Self {
+            app_type,
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            url,
+            alternatives,
+            policy,
+        })
+    }
+}
+
+/// 探测结果的简短描述（用于日志与事件）
+fn describe(result: &EndpointLatency, policy: &FailoverPolicy) -> String {
+    match (&result.error, result.status, result.latency) {
+        (Some(e), _, _) => e.clone(),
+        (None, Some(code), _) if code >= 500 => format!("HTTP {}", code),
+        (None, _, Some(ms)) if ms > policy.latency_threshold_ms as u128 => {
+            format!("延迟 {} ms 超过阈值 {} ms", ms, policy.latency_threshold_ms)
+        }
+        _ => "正常".to_string(),
+    }
+}
+
+/// 监控状态：当前地址的连续异常次数与冷却截止时间
+#[derive(Default)]
+struct MonitorState {
+    watching: Option<(String, String)>,
+    failures: u32,
+    cooldown_until: Option<Instant>,
+}
+
+async fn probe(url: &str) -> EndpointLatency {
+    speedtest::test_endpoints(vec![url.to_string()], None)
+        .await
+        .ok()
+        .and_then(|mut results| results.pop())
+        .unwrap_or_else(|| EndpointLatency {
+            url: url.to_string(),
+            latency: None,
+            status: None,
+            error: Some("探测失败".to_string()),
+        })
+}
+
+/// 对当前地址执行一次探测；达到阈值时挑选最健康的备选地址，返回待执行的切换
+async fn check(state: &mut MonitorState, target: &Target) -> Option<FailoverEvent> {
+    let key = (target.provider_id.clone(), target.url.clone());
+    if state.watching.as_ref() != Some(&key) {
+        // 供应商或地址已变化（手动切换或刚完成自动切换），重新计数
+        state.watching = Some(key);
+        state.failures = 0;
+    }
+
+    let policy = &target.policy;
+    let current = probe(&target.url).await;
+    if policy.is_healthy(&current) {
+        state.failures = 0;
+        return None;
+    }
+    state.failures += 1;
+    let reason = describe(&current, policy);
+    log::warn!(
+        "供应商 {} 地址 {} 探测异常（{}/{}）: {}",
+        target.provider_name,
+        target.url,
+        state.failures,
+        policy.failure_threshold.max(1),
+        reason
+    );
+    if state.failures < policy.failure_threshold.max(1) {
+        return None;
+    }
+
+    state.failures = 0;
+    // 无论是否找到可用地址都进入冷却，避免持续探测全部备选地址
+    state.cooldown_until = Some(Instant::now() + policy.cooldown());
+    let results = speedtest::test_endpoints(target.alternatives.clone(), None)
+        .await
+        .unwrap_or_default();
+    let best = results
+        .into_iter()
+        .filter(|r| policy.is_healthy(r))
+        .min_by_key(|r| r.latency.unwrap_or(u128::MAX));
+    match best {
+        Some(best) => Some(FailoverEvent {
+            app_type: target.app_type,
+            provider_id: target.provider_id.clone(),
+            provider_name: target.provider_name.clone(),
+            from_url: target.url.clone(),
+            to_url: best.url,
+            reason,
+            latency_ms: best.latency,
+        }),
+        None => {
+            log::warn!(
+                "供应商 {} 的备选地址均不可用，保持 {}",
+                target.provider_name,
+                target.url
+            );
+            None
+        }
+    }
+}
+
+/// 在后台线程中监控 `app_type` 当前供应商的 API 地址（Claude 为 `ANTHROPIC_BASE_URL`，
+/// Codex 为 `[model_providers.*]` 的 `base_url`），每个应用一个线程
+///
+/// `current_provider` 每轮调用一次，返回该应用的当前供应商；连续探测异常达到阈值时，
+/// 以最健康的备选地址调用 `apply`（应完成切换并保存配置），失败只记录日志
+pub fn start<C, A>(app_type: AppType, current_provider: C, apply: A)
+where
+    C: Fn() -> Option<Provider> + Send + 'static,
+    A: Fn(&FailoverEvent) -> Result<(), String> + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let runtime = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                log::error!("创建故障转移监控运行时失败: {}", e);
+                return;
+            }
+        };
+        let mut state = MonitorState::default();
+        loop {
+            if state.cooldown_until.is_some_and(|t| Instant::now() < t) {
+                std::thread::sleep(IDLE_INTERVAL);
+                continue;
+            }
+            let Some(target) = current_provider()
+                .as_ref()
+                .and_then(|p| Target::from_provider(app_type, p))
+            else {
+                state.watching = None;
+                std::thread::sleep(IDLE_INTERVAL);
+                continue;
+            };
+
+            if let Some(event) = runtime.block_on(check(&mut state, &target)) {
+                match apply(&event) {
+                    Ok(()) => log::warn!(
+                        "供应商 {} 自动切换地址 {} -> {}（原因: {}）",
+                        event.provider_name,
+                        event.from_url,
+                        event.to_url,
+                        event.reason
+                    ),
+                    Err(e) => log::error!("自动切换地址失败: {}", e),
+                }
+            }
+            std::thread::sleep(target.policy.interval());
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_cooldown_is_clamped() {
+        let policy: FailoverPolicy = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "cooldownSecs": u64::MAX,
+        }))
+        .unwrap();
+        assert_eq!(policy.cooldown(), Duration::from_secs(MAX_COOLDOWN_SECS));
+        // 截止时间的计算不应溢出
+        assert!(Instant::now().checked_add(policy.cooldown()).is_some());
+    }
+}
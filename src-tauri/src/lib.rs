@@ -1,24 +1,45 @@
 mod app_config;
 mod claude_mcp;
 mod claude_plugin;
+pub mod cli;
 mod codex_config;
 mod commands;
 mod config;
+mod config_recovery;
+mod config_sync;
 mod droid_config;
+mod failover;
+mod live_watcher;
 mod mcp;
 mod migration;
+mod preflight;
 mod provider;
+mod provider_service;
+mod schedule;
+mod schema_migration;
+mod secrets;
+mod secure_store;
 mod settings;
+mod snapshot;
 mod speedtest;
 mod store;
+mod switch_hooks;
+mod switch_journal;
+mod switch_preview;
+#[cfg(all(test, unix))]
+mod test_support;
 mod vscode;
 
+pub use app_config::{AppType, MultiAppConfig};
+pub use provider::{Provider, ProviderManager};
+pub use provider_service::ProviderService;
+
 use std::str::FromStr;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use store::AppState;
 use tauri::{
-    menu::{CheckMenuItem, Menu, MenuBuilder, MenuItem, SubmenuBuilder},
+    menu::{CheckMenuItem, Menu, MenuBuilder, MenuItem, Submenu, SubmenuBuilder},
     tray::{TrayIconBuilder, TrayIconEvent},
 };
 #[cfg(target_os = "macos")]
@@ -26,15 +47,173 @@ use tauri::{ActivationPolicy, RunEvent};
 use tauri::{Emitter, Manager};
 use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
 
+/// 处理 live 配置文件的外部修改：按设置自动回填当前供应商，并通知前端
+fn handle_live_config_change(app: &tauri::AppHandle, mut change: live_watcher::LiveConfigChange) {
+    if change.file.is_provider_config() && crate::settings::get_settings().auto_sync_live_config {
+        let app_type = change.file.app_type();
+        if let Some(state) = app.try_state::<AppState>() {
+            match state.modify(|service| service.sync_current_provider_config(&app_type)) {
+                Ok(()) => change.synced = true,
+                Err(e) => log::warn!("自动同步 live 配置失败: {}", e),
+            }
+        }
+    }
+    if let Err(e) = app.emit("live-config-changed", &change) {
+        log::error!("发射 live 配置变更事件失败: {}", e);
+    }
+}
+
+/// 应用的当前供应商（供故障转移监控读取）
+fn current_provider(app: &tauri::AppHandle, app_type: &AppType) -> Option<Provider> {
+    let state = app.try_state::<AppState>()?;
+    let service = state.service.lock().ok()?;
+    let id = service.current_provider_id(app_type).ok()?;
+    service.provider(app_type, id).ok().cloned()
+}
+
+/// 执行故障转移监控选出的地址切换，并刷新托盘、通知前端
+fn handle_endpoint_failover(
+    app: &tauri::AppHandle,
+    event: &failover::FailoverEvent,
+) -> Result<(), String> {
+    let state = app
+        .try_state::<AppState>()
+        .ok_or_else(|| "应用状态不可用".to_string())?;
+    state.modify(|service| {
+        // 探测期间可能已手动切换供应商或地址，此时放弃本次切换
+        let app_type = event.app_type;
+        let current = service.current_provider_id(&app_type)?.to_string();
+        let url = service.provider(&app_type, &current)?.base_url(&app_type);
+        if current != event.provider_id || url.as_deref() != Some(event.from_url.as_str()) {
+            return Err("当前供应商或地址已变化，放弃自动切换".to_string());
+        }
+        service.switch_provider_url(&app_type, &event.to_url)
+    })?;
+
+    if let Ok(new_menu) = create_tray_menu(app, state.inner()) {
+        if let Some(tray) = app.tray_by_id("main") {
+            let _ = tray.set_menu(Some(new_menu));
+        }
+    }
+    if let Err(e) = app.emit("endpoint-failover", event) {
+        log::error!("发射故障转移事件失败: {}", e);
+    }
+    Ok(())
+}
+
+/// 处理 `config.enc.json` 的外部修改：重新加载后重建托盘菜单并通知前端刷新
+fn handle_app_config_change(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    let reloaded = match state.service.lock() {
+        Ok(mut service) => service.reload_if_clean(),
+        Err(e) => Err(format!("获取锁失败: {}", e)),
+    };
+    match reloaded {
+        Ok(true) => {
+            if let Ok(new_menu) = create_tray_menu(app, state.inner()) {
+                if let Some(tray) = app.tray_by_id("main") {
+                    let _ = tray.set_menu(Some(new_menu));
+                }
+            }
+            if let Err(e) = app.emit("app-config-reloaded", ()) {
+                log::error!("发射配置重新加载事件失败: {}", e);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("重新加载配置失败: {}", e),
+    }
+}
+
+/// 托盘中的供应商名称：临时切换中的供应商附带恢复倒计时
+fn tray_provider_label(manager: &ProviderManager, id: &str, name: &str) -> String {
+    let Some(temporary) = manager.temporary.as_ref().filter(|t| t.provider_id == id) else {
+        return name.to_string();
+    };
+    let minutes = temporary.remaining().as_secs().div_ceil(60);
+    let remaining = if minutes >= 60 {
+        format!("{} 小时 {} 分钟", minutes / 60, minutes % 60)
+    } else {
+        format!("{} 分钟", minutes.max(1))
+    };
+    format!("{}（{}后恢复）", name, remaining)
+}
+
+/// 托盘端点菜单项的 id：`{app}_endpoint_{供应商 id}_{编码后的地址}`
+fn endpoint_menu_id(app_type: &AppType, provider_id: &str, url: &str) -> String {
+    let encoded_url = url.replace("://", "___").replace('/', "__");
+    format!("{}_endpoint_{}_{}", app_type.as_str(), provider_id, encoded_url)
+}
+
+/// 解析 [`endpoint_menu_id`] 生成的 id，返回应用、供应商 id 与地址
+fn parse_endpoint_menu_id(id: &str) -> Option<(AppType, String, String)> {
+    let (app_type, rest) = [AppType::Claude, AppType::Codex]
+        .into_iter()
+        .find_map(|app| Some((app, id.strip_prefix(&format!("{}_endpoint_", app.as_str()))?)))?;
+    // 地址以 http(s)___ 开头，之前的部分为供应商 id
+    let split = ["_https___", "_http___"]
+        .iter()
+        .filter_map(|marker| rest.find(marker))
+        .min()?;
+    let url = rest[split + 1..].replacen("___", "://", 1).replace("__", "/");
+    Some((app_type, rest[..split].to_string(), url))
+}
+
+/// 有多个备选地址的供应商子菜单：列出全部地址，点击即切换到该供应商并使用该地址
+fn create_endpoint_submenu(
+    app: &tauri::AppHandle,
+    app_type: &AppType,
+    manager: &ProviderManager,
+    id: &str,
+    provider: &Provider,
+) -> Result<Submenu<tauri::Wry>, String> {
+    let is_current = manager.current == id;
+    let label = tray_provider_label(manager, id, &provider.name);
+    let submenu_name = if is_current {
+        format!("{} ✓", label)
+    } else {
+        label
+    };
+    let mut provider_submenu = SubmenuBuilder::new(app, submenu_name);
+
+    // 当前使用的地址（只有激活时才有意义）
+    let current_url = if is_current {
+        provider.base_url(app_type).unwrap_or_default()
+    } else {
+        String::new()
+    };
+    for url in provider.alternative_urls.iter().flatten() {
+        let url_item = CheckMenuItem::with_id(
+            app,
+            endpoint_menu_id(app_type, id, url),
+            url,
+            true,
+            is_current && *url == current_url,
+            None::<&str>,
+        )
+        .map_err(|e| format!("创建端点菜单项失败: {}", e))?;
+        provider_submenu = provider_submenu.item(&url_item);
+    }
+
+    provider_submenu
+        .build()
+        .map_err(|e| format!("构建供应商子菜单失败: {}", e))
+}
+
 /// 创建动态托盘菜单
 fn create_tray_menu(
     app: &tauri::AppHandle,
     app_state: &AppState,
 ) -> Result<Menu<tauri::Wry>, String> {
-    let config = app_state
-        .config
+    let mut service = app_state
+        .service
         .lock()
         .map_err(|e| format!("获取锁失败: {}", e))?;
+    if let Err(e) = service.refresh_from_disk() {
+        log::warn!("重新读取配置失败: {}", e);
+    }
+    let config = service.config();
 
     let mut menu_builder = MenuBuilder::new(app);
 
@@ -54,63 +233,17 @@ fn create_tray_menu(
                 let is_current = claude_manager.current == *id;
                 
                 // 检查是否有多个端点
-                let has_multiple_endpoints = provider.alternative_urls.as_ref()
-                    .map(|urls| urls.len() > 1)
-                    .unwrap_or(false);
-
-                if has_multiple_endpoints {
+                if provider.alternative_urls.as_ref().is_some_and(|urls| urls.len() > 1) {
                     // 有多个端点：创建供应商子菜单（无论是否激活）
-                    let submenu_name = if is_current {
-                        format!("{} ✓", &provider.name)
-                    } else {
-                        provider.name.clone()
-                    };
-                    
-                    let mut provider_submenu = SubmenuBuilder::new(app, submenu_name);
-                    
-                    // 获取当前使用的URL（只有激活时才有意义）
-                    let current_url = if is_current {
-                        provider
-                            .settings_config
-                            .get("env")
-                            .and_then(|env| env.get("ANTHROPIC_BASE_URL"))
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                    } else {
-                        ""
-                    };
-
-                    // 添加所有端点（所有供应商的端点都可点击，实现一步到位切换）
-                    if let Some(ref alt_urls) = provider.alternative_urls {
-                        for url in alt_urls {
-                            let is_current_url = is_current && url == current_url;
-                            // 编码格式：provider_id + url
-                            let encoded_url = url.replace("://", "___").replace('/', "__");
-                            
-                            // 所有端点都可点击：点击时自动激活供应商并切换端点
-                            let url_item = CheckMenuItem::with_id(
-                                app,
-                                format!("claude_endpoint_{}_{}", id, encoded_url),
-                                url,
-                                true,
-                                is_current_url,
-                                None::<&str>,
-                            )
-                            .map_err(|e| format!("创建端点菜单项失败: {}", e))?;
-                            provider_submenu = provider_submenu.item(&url_item);
-                        }
-                    }
-
-                    let provider_menu = provider_submenu
-                        .build()
-                        .map_err(|e| format!("构建供应商子菜单失败: {}", e))?;
+                    let provider_menu =
+                        create_endpoint_submenu(app, &AppType::Claude, claude_manager, id, provider)?;
                     claude_submenu = claude_submenu.item(&provider_menu);
                 } else {
                     // 没有多个端点：普通菜单项
                     let item = CheckMenuItem::with_id(
                         app,
                         format!("claude_{}", id),
-                        &provider.name,
+                        tray_provider_label(claude_manager, id, &provider.name),
                         true,
                         is_current,
                         None::<&str>,
@@ -158,10 +291,17 @@ fn create_tray_menu(
         if !codex_manager.providers.is_empty() {
             for (id, provider) in &codex_manager.providers {
                 let is_current = codex_manager.current == *id;
+                if provider.alternative_urls.as_ref().is_some_and(|urls| urls.len() > 1) {
+                    // 与 Claude 相同：有多个端点时以子菜单列出
+                    let provider_menu =
+                        create_endpoint_submenu(app, &AppType::Codex, codex_manager, id, provider)?;
+                    codex_submenu = codex_submenu.item(&provider_menu);
+                    continue;
+                }
                 let item = CheckMenuItem::with_id(
                     app,
                     format!("codex_{}", id),
-                    &provider.name,
+                    tray_provider_label(codex_manager, id, &provider.name),
                     true,
                     is_current,
                     None::<&str>,
@@ -169,6 +309,20 @@ fn create_tray_menu(
                 .map_err(|e| format!("创建菜单项失败: {}", e))?;
                 codex_submenu = codex_submenu.item(&item);
             }
+
+            // 如果有当前供应商，添加停用按钮
+            if !codex_manager.current.is_empty() {
+                codex_submenu = codex_submenu.separator();
+                let disable_item = MenuItem::with_id(
+                    app,
+                    "codex_disable",
+                    "停用当前供应商",
+                    true,
+                    None::<&str>,
+                )
+                .map_err(|e| format!("创建停用菜单失败: {}", e))?;
+                codex_submenu = codex_submenu.item(&disable_item);
+            }
         } else {
             let empty_hint = MenuItem::with_id(
                 app,
@@ -197,7 +351,7 @@ fn create_tray_menu(
                 let item = CheckMenuItem::with_id(
                     app,
                     format!("droid_{}", id),
-                    &provider.name,
+                    tray_provider_label(droid_manager, id, &provider.name),
                     true,
                     is_current,
                     None::<&str>,
@@ -269,6 +423,19 @@ fn apply_tray_policy(app: &tauri::AppHandle, dock_visible: bool) {
 fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
     log::info!("处理托盘菜单事件: {}", event_id);
 
+    // 端点菜单项（`<app>_endpoint_<供应商>_<地址>`）同样以 `<app>_` 开头，先于供应商菜单项处理
+    if let Some((app_type, provider_id, url)) = parse_endpoint_menu_id(event_id) {
+        log::info!("一步切换：供应商 {} → 端点 {}", provider_id, url);
+        let app_handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = switch_endpoint_internal(&app_handle, app_type, provider_id, url).await
+            {
+                log::error!("切换端点失败: {}", e);
+            }
+        });
+        return;
+    }
+
     match event_id {
         "show_main" => {
             if let Some(window) = app.get_webview_window("main") {
@@ -334,6 +501,19 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
                 }
             });
         }
+        "codex_disable" => {
+            log::info!("停用Codex供应商");
+
+            // 执行停用
+            let app_handle = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) =
+                    disable_provider_internal(&app_handle, crate::app_config::AppType::Codex).await
+                {
+                    log::error!("停用Codex供应商失败: {}", e);
+                }
+            });
+        }
         id if id.starts_with("codex_") => {
             let provider_id = id.strip_prefix("codex_").unwrap();
             log::info!("切换到Codex供应商: {}", provider_id);
@@ -372,100 +552,6 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
                 }
             });
         }
-        id if id.starts_with("claude_endpoint_") => {
-            // 格式: claude_endpoint_{provider_id}_{encoded_url}
-            let parts = id.strip_prefix("claude_endpoint_").unwrap();
-            if let Some(last_underscore) = parts.rfind('_') {
-                let provider_id = &parts[..last_underscore];
-                let url_encoded = &parts[last_underscore + 1..];
-                let url = url_encoded.replace("___", "://").replace("__", "/");
-                
-                log::info!("一步切换：供应商 {} → 端点 {}", provider_id, url);
-
-                let app_handle = app.clone();
-                let provider_id = provider_id.to_string();
-                let url = url.to_string();
-                
-                tauri::async_runtime::spawn(async move {
-                    if let Some(app_state) = app_handle.try_state::<AppState>() {
-                        // 步骤 1: 切换到该供应商
-                        if let Err(e) = switch_provider_internal(
-                            &app_handle,
-                            crate::app_config::AppType::Claude,
-                            provider_id.clone(),
-                        ).await {
-                            log::error!("切换供应商失败: {}", e);
-                            return;
-                        }
-
-                        // 步骤 2: 切换端点
-                        match commands::switch_provider_url(app_state, url.clone()).await {
-                            Ok(_) => {
-                                log::info!("成功切换到端点: {}", url);
-                                // 更新托盘菜单
-                                if let Some(app_state) = app_handle.try_state::<AppState>() {
-                                    if let Ok(new_menu) = create_tray_menu(&app_handle, app_state.inner()) {
-                                        if let Some(tray) = app_handle.tray_by_id("main") {
-                                            let _ = tray.set_menu(Some(new_menu));
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => log::error!("切换端点失败: {}", e),
-                        }
-                    }
-                });
-            }
-        }
-        id if id.starts_with("claude_endpoint_") => {
-            // 格式: claude_endpoint_{provider_id}_{encoded_url}
-            // 找到最后一个下划线，分离 provider_id 和 url
-            let parts = id.strip_prefix("claude_endpoint_").unwrap();
-            
-            // 使用 rfind 找到最后一个下划线的位置
-            if let Some(last_underscore) = parts.rfind("___") {
-                // URL 中包含 ___ (://), 从这里分割
-                let provider_id = &parts[..last_underscore];
-                let url_with_protocol = &parts[last_underscore..];
-                let url = url_with_protocol.replace("___", "://").replace("__", "/");
-                
-                log::info!("一步切换：供应商 {} → 端点 {}", provider_id, url);
-
-                let app_handle = app.clone();
-                let provider_id = provider_id.to_string();
-                let url = url.to_string();
-                
-                tauri::async_runtime::spawn(async move {
-                    // 步骤 1: 切换到该供应商
-                    if let Err(e) = switch_provider_internal(
-                        &app_handle,
-                        crate::app_config::AppType::Claude,
-                        provider_id.clone(),
-                    ).await {
-                        log::error!("切换供应商失败: {}", e);
-                        return;
-                    }
-
-                    // 步骤 2: 切换端点
-                    if let Some(app_state) = app_handle.try_state::<AppState>() {
-                        match commands::switch_provider_url(app_state, url.clone()).await {
-                            Ok(_) => {
-                                log::info!("✅ 成功一步切换到端点: {}", url);
-                                // 更新托盘菜单
-                                if let Some(app_state) = app_handle.try_state::<AppState>() {
-                                    if let Ok(new_menu) = create_tray_menu(&app_handle, app_state.inner()) {
-                                        if let Some(tray) = app_handle.tray_by_id("main") {
-                                            let _ = tray.set_menu(Some(new_menu));
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => log::error!("切换端点失败: {}", e),
-                        }
-                    }
-                });
-            }
-        }
         id if id.starts_with("switch_url_") => {
             // 兼容旧格式（如果还有用到）
             let url_encoded = id.strip_prefix("switch_url_").unwrap();
@@ -476,7 +562,7 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
             let url = url.to_string();
             tauri::async_runtime::spawn(async move {
                 if let Some(app_state) = app_handle.try_state::<AppState>() {
-                    match commands::switch_provider_url(app_state, url.clone()).await {
+                    match commands::switch_provider_url(app_state, url.clone(), None, None, None).await {
                         Ok(_) => {
                             log::info!("成功切换API地址到: {}", url);
                             if let Some(app_state) = app_handle.try_state::<AppState>() {
@@ -507,38 +593,136 @@ async fn switch_provider_internal(
     provider_id: String,
 ) -> Result<(), String> {
     if let Some(app_state) = app.try_state::<AppState>() {
-        // 在使用前先保存需要的值
-        let app_type_str = app_type.as_str().to_string();
-        let provider_id_clone = provider_id.clone();
-
-        crate::commands::switch_provider(
-            app_state.clone(),
-            Some(app_type),
-            None,
-            None,
-            provider_id,
-        )
-        .await?;
+        let report = app_state.switch_provider(&app_type, &provider_id).await?;
+        notify_provider_switched(app, app_state.inner(), &app_type, &provider_id, &report);
+    }
+    Ok(())
+}
 
-        // 切换成功后重新创建托盘菜单
-        if let Ok(new_menu) = create_tray_menu(app, app_state.inner()) {
-            if let Some(tray) = app.tray_by_id("main") {
-                if let Err(e) = tray.set_menu(Some(new_menu)) {
-                    log::error!("更新托盘菜单失败: {}", e);
+/// 托盘端点菜单：切换到供应商并使用指定地址（供应商已是当前时只切换地址）
+async fn switch_endpoint_internal(
+    app: &tauri::AppHandle,
+    app_type: AppType,
+    provider_id: String,
+    url: String,
+) -> Result<(), String> {
+    if let Some(app_state) = app.try_state::<AppState>() {
+        let report = app_state
+            .switch_provider_endpoint(&app_type, &provider_id, &url)
+            .await?;
+        log::info!("成功切换到端点: {}", url);
+        notify_provider_switched(app, app_state.inner(), &app_type, &provider_id, &report);
+    }
+    Ok(())
+}
+
+/// 切换成功后重新创建托盘菜单，并通知前端供应商已切换
+fn notify_provider_switched(
+    app: &tauri::AppHandle,
+    app_state: &AppState,
+    app_type: &AppType,
+    provider_id: &str,
+    report: &crate::switch_hooks::SwitchReport,
+) {
+    if let Ok(new_menu) = create_tray_menu(app, app_state) {
+        if let Some(tray) = app.tray_by_id("main") {
+            if let Err(e) = tray.set_menu(Some(new_menu)) {
+                log::error!("更新托盘菜单失败: {}", e);
+            }
+        }
+    }
+
+    // 托盘切换时连通性检查的结果一并带上，由前端提示
+    let event_data = serde_json::json!({
+        "appType": app_type.as_str(),
+        "providerId": provider_id,
+        "preflight": report.preflight
+    });
+    if let Err(e) = app.emit("provider-switched", event_data) {
+        log::error!("发射供应商切换事件失败: {}", e);
+    }
+}
+
+/// 自动切换规则的检查间隔
+const SCHEDULE_TICK: Duration = Duration::from_secs(30);
+
+/// 按 `settings.json` 中的 `switchRules` 定时切换供应商（与托盘切换走同一流程）
+async fn run_switch_scheduler(app: tauri::AppHandle) {
+    let mut scheduler = schedule::Scheduler::default();
+    loop {
+        // 每轮重新读取设置，手动编辑规则后无需重启
+        let rules = crate::settings::AppSettings::load().switch_rules;
+        for (app_type, provider_id) in scheduler.tick(&rules, schedule::local_now()) {
+            let current = app.try_state::<AppState>().and_then(|state| {
+                let service = state.service.lock().ok()?;
+                service
+                    .current_provider_id(&app_type)
+                    .ok()
+                    .map(str::to_string)
+            });
+            if current.as_deref() == Some(provider_id.as_str()) {
+                continue;
+            }
+            log::info!(
+                "按自动切换规则切换 {} 供应商: {}",
+                app_type.as_str(),
+                provider_id
+            );
+            if let Err(e) = switch_provider_internal(&app, app_type, provider_id).await {
+                log::error!("按规则自动切换失败: {}", e);
+            }
+        }
+        tokio::time::sleep(SCHEDULE_TICK).await;
+    }
+}
+
+/// 临时切换的检查间隔（同时刷新托盘中的倒计时）
+const TEMPORARY_SWITCH_TICK: Duration = Duration::from_secs(15);
+
+/// 临时切换到期后恢复为切换前的供应商（与托盘切换走同一流程）
+///
+/// 恢复时间保存在配置中，启动后第一轮即处理重启期间已到期的临时切换
+async fn run_temporary_switch_reverts(app: tauri::AppHandle) {
+    loop {
+        let switches = app
+            .try_state::<AppState>()
+            .and_then(|state| {
+                let service = state.service.lock().ok()?;
+                Some(service.temporary_switches())
+            })
+            .unwrap_or_default();
+        for (app_type, temporary) in switches.iter().filter(|(_, t)| t.is_due()) {
+            log::info!(
+                "临时切换已到期，{} 恢复为供应商: {}",
+                app_type.as_str(),
+                temporary.previous_provider_id
+            );
+            let result =
+                switch_provider_internal(&app, *app_type, temporary.previous_provider_id.clone())
+                    .await;
+            if let Err(e) = result {
+                // 恢复失败时放弃临时切换，避免每轮重复执行钩子与检查
+                log::error!("临时切换到期恢复失败，保留当前供应商: {}", e);
+                if let Some(state) = app.try_state::<AppState>() {
+                    if let Err(e) = state.modify(|s| s.clear_temporary_switch(app_type)) {
+                        log::error!("清除临时切换失败: {}", e);
+                    }
                 }
             }
         }
 
-        // 发射事件到前端，通知供应商已切换
-        let event_data = serde_json::json!({
-            "appType": app_type_str,
-            "providerId": provider_id_clone
-        });
-        if let Err(e) = app.emit("provider-switched", event_data) {
-            log::error!("发射供应商切换事件失败: {}", e);
+        // 有进行中的临时切换时刷新托盘倒计时
+        if !switches.is_empty() {
+            if let Some(state) = app.try_state::<AppState>() {
+                if let Ok(new_menu) = create_tray_menu(&app, state.inner()) {
+                    if let Some(tray) = app.tray_by_id("main") {
+                        let _ = tray.set_menu(Some(new_menu));
+                    }
+                }
+            }
         }
+        tokio::time::sleep(TEMPORARY_SWITCH_TICK).await;
     }
-    Ok(())
 }
 
 /// 内部停用供应商函数
@@ -547,8 +731,7 @@ async fn disable_provider_internal(
     app_type: crate::app_config::AppType,
 ) -> Result<(), String> {
     if let Some(app_state) = app.try_state::<AppState>() {
-        crate::commands::disable_current_provider(app_state.clone(), Some(app_type), None, None)
-            .await?;
+        app_state.modify(|service| service.disable_current_provider(&app_type))?;
 
         // 停用成功后重新创建托盘菜单
         if let Ok(new_menu) = create_tray_menu(app, app_state.inner()) {
@@ -740,9 +923,12 @@ pub fn run() {
             let app_state = AppState::new();
 
             // 首次启动迁移：扫描副本文件，合并到 config.json，并归档副本；旧 config.json 先归档
-            {
-                let mut config_guard = app_state.config.lock().unwrap();
-                let migrated = migration::migrate_copies_into_config(&mut config_guard)?;
+            // 只读模式下配置为空壳，跳过迁移以免归档副本后无法保存
+            let read_only = app_state.service.lock().unwrap().is_read_only();
+            if !read_only {
+                let mut service_guard = app_state.service.lock().unwrap();
+                let config_guard = service_guard.config_mut();
+                let migrated = migration::migrate_copies_into_config(config_guard)?;
                 if migrated {
                     log::info!("已将副本文件导入到 config.json，并完成归档");
                 }
@@ -752,7 +938,16 @@ pub fn run() {
             }
 
             // 保存配置
-            let _ = app_state.save();
+            if !read_only {
+                let _ = app_state.save();
+            }
+
+            // 通知前端配置恢复结果（前端启动时也会通过 get_config_recovery_status 主动查询）
+            if let Some(report) = &app_state.recovery {
+                if let Err(e) = app.emit("config-recovered", report) {
+                    log::error!("发射配置恢复事件失败: {}", e);
+                }
+            }
 
             // 创建动态托盘菜单
             let menu = create_tray_menu(app.handle(), &app_state)?;
@@ -828,6 +1023,35 @@ pub fn run() {
             // 将同一个实例注入到全局状态，避免重复创建导致的不一致
             app.manage(app_state);
 
+            // 监听 config.enc.json 的外部修改（其他进程、git pull 等），无未保存修改时热重载
+            let app_handle_for_config = app.handle().clone();
+            live_watcher::start_app_config_watch(move || {
+                handle_app_config_change(&app_handle_for_config);
+            });
+
+            // 监听 live 配置文件的外部修改
+            let app_handle_for_watcher = app.handle().clone();
+            live_watcher::start(move |change| {
+                handle_live_config_change(&app_handle_for_watcher, change);
+            });
+
+            // 监控开启了故障转移的当前 Claude / Codex 供应商，地址异常时自动切换到备选地址
+            for app_type in [AppType::Claude, AppType::Codex] {
+                let app_handle_for_probe = app.handle().clone();
+                let app_handle_for_failover = app.handle().clone();
+                failover::start(
+                    app_type,
+                    move || current_provider(&app_handle_for_probe, &app_type),
+                    move |event| handle_endpoint_failover(&app_handle_for_failover, event),
+                );
+            }
+
+            // 按自动切换规则定时切换供应商
+            tauri::async_runtime::spawn(run_switch_scheduler(app.handle().clone()));
+
+            // 临时切换到期后自动恢复
+            tauri::async_runtime::spawn(run_temporary_switch_reverts(app.handle().clone()));
+
             // 注册全局快捷键
             let settings = crate::settings::get_settings();
             if let Some(shortcut_str) = settings.global_shortcut {
@@ -868,6 +1092,12 @@ pub fn run() {
             commands::update_provider,
             commands::delete_provider,
             commands::switch_provider,
+            commands::preview_switch,
+            commands::check_provider_connectivity,
+            commands::next_scheduled_switch,
+            commands::switch_provider_for,
+            commands::get_temporary_switch,
+            commands::cancel_temporary_switch,
             commands::disable_current_provider,
             commands::switch_provider_url,
             commands::import_default_config,
@@ -881,6 +1111,10 @@ pub fn run() {
             commands::pick_directory,
             commands::open_external,
             commands::get_app_config_path,
+            commands::get_config_recovery_status,
+            commands::list_config_snapshots,
+            commands::diff_config_snapshot,
+            commands::restore_config_snapshot,
             commands::open_app_config_folder,
             commands::get_mcp_config,
             commands::upsert_mcp_server_in_config,
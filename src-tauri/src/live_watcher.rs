@@ -0,0 +1,366 @@
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::app_config::AppType;
+
+/// 收到文件事件后等待这段时间再读取，合并编辑器连续写入产生的多个事件
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// 定期重新解析监听路径（配置目录可在设置中修改，目录也可能稍后才创建）
+const RESCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 被监听的 live 配置文件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LiveFile {
+    /// ~/.claude/settings.json（或旧版 claude.json）
+    ClaudeSettings,
+    /// ~/.claude.json，仅关注 mcpServers
+    ClaudeMcp,
+    /// ~/.codex/auth.json
+    CodexAuth,
+    /// ~/.codex/config.toml
+    CodexConfig,
+}
+
+impl LiveFile {
+    const ALL: [LiveFile; 4] = [
+        LiveFile::ClaudeSettings,
+        LiveFile::ClaudeMcp,
+        LiveFile::CodexAuth,
+        LiveFile::CodexConfig,
+    ];
+
+    /// 当前解析到的文件路径（随设置中的配置目录覆盖变化）
+    pub fn path(&self) -> PathBuf {
+        match self {
+            LiveFile::ClaudeSettings => crate::config::get_claude_settings_path(),
+            LiveFile::ClaudeMcp => crate::claude_mcp::user_config_path(),
+            LiveFile::CodexAuth => crate::codex_config::get_codex_auth_path(),
+            LiveFile::CodexConfig => crate::codex_config::get_codex_config_path(),
+        }
+    }
+
+    pub fn app_type(&self) -> AppType {
+        match self {
+            LiveFile::ClaudeSettings | LiveFile::ClaudeMcp => AppType::Claude,
+            LiveFile::CodexAuth | LiveFile::CodexConfig => AppType::Codex,
+        }
+    }
+
+    /// 是否属于供应商配置（可由 sync_current_provider_config 回填）；MCP 文件不属于
+    pub fn is_provider_config(&self) -> bool {
+        !matches!(self, LiveFile::ClaudeMcp)
+    }
+
+    /// 读取并解析为 JSON（TOML 转为等价 JSON）；文件不存在时为 Null
+    fn read(&self, path: &Path) -> Result<(Value, Vec<u8>), String> {
+        if !path.exists() {
+            return Ok((Value::Null, Vec::new()));
+        }
+        let bytes = std::fs::read(path).map_err(|e| format!("读取文件失败: {}", e))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let value = match self {
+            LiveFile::CodexConfig => {
+                let table: toml::Value =
+                    toml::from_str(&text).map_err(|e| format!("解析 TOML 失败: {}", e))?;
+                serde_json::to_value(table).map_err(|e| format!("转换 TOML 失败: {}", e))?
+            }
+            LiveFile::ClaudeMcp => {
+                let root: Value = if text.trim().is_empty() {
+                    Value::Null
+                } else {
+                    serde_json::from_str(&text).map_err(|e| format!("解析 JSON 失败: {}", e))?
+                };
+                // ~/.claude.json 由 Claude Code 频繁写入（启动次数、项目历史等），只比较 MCP 部分
+                root.get("mcpServers").cloned().unwrap_or(Value::Null)
+            }
+            _ => {
+                if text.trim().is_empty() {
+                    Value::Null
+                } else {
+                    serde_json::from_str(&text).map_err(|e| format!("解析 JSON 失败: {}", e))?
+                }
+            }
+        };
+        Ok((value, bytes))
+    }
+}
+
+/// 单个字段的变化（`path` 为 JSON Pointer）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonChange {
+    pub path: String,
+    /// added | removed | changed
+    pub kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// `live-config-changed` 事件内容
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveConfigChange {
+    pub app_type: String,
+    pub file: LiveFile,
+    pub path: String,
+    pub changes: Vec<JsonChange>,
+    /// 是否已自动回填到当前供应商
+    pub synced: bool,
+}
+
+/// 比较两份 JSON，逐字段列出新增 / 删除 / 修改（数组整体比较）；密钥类字段的值会被遮蔽
+pub fn diff_json(before: &Value, after: &Value) -> Vec<JsonChange> {
+    let mut changes = Vec::new();
+    diff_into(before, after, "", &mut changes);
+    changes
+}
+
+fn diff_into(before: &Value, after: &Value, path: &str, out: &mut Vec<JsonChange>) {
+    if before == after {
+        return;
+    }
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: HashSet<&String> = a.keys().chain(b.keys()).collect();
+            let mut keys: Vec<&String> = keys.into_iter().collect();
+            keys.sort();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_into(x, y, &child, out),
+                    (None, Some(y)) => out.push(change(&child, "added", None, Some(y))),
+                    (Some(x), None) => out.push(change(&child, "removed", Some(x), None)),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Null, _) => out.push(change(path, "added", None, Some(after))),
+        (_, Value::Null) => out.push(change(path, "removed", Some(before), None)),
+        _ => out.push(change(path, "changed", Some(before), Some(after))),
+    }
+}
+
+fn change(
+    path: &str,
+    kind: &'static str,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> JsonChange {
+    let masked = is_sensitive(path);
+    let show = |v: Option<&Value>| v.map(|v| if masked { mask(v) } else { v.clone() });
+    JsonChange {
+        path: if path.is_empty() {
+            "/".to_string()
+        } else {
+            path.to_string()
+        },
+        kind,
+        before: show(before),
+        after: show(after),
+    }
+}
+
+fn is_sensitive(path: &str) -> bool {
+    let last = path.rsplit('/').next().unwrap_or("").to_ascii_lowercase();
+    ["token", "key", "secret", "password"]
+        .iter()
+        .any(|k| last.contains(k))
+}
+
+fn mask(value: &Value) -> Value {
+    match value {
+        Value::String(s) if !s.is_empty() => Value::String("***".to_string()),
+        other => other.clone(),
+    }
+}
+
+/// 启动后台监听线程；每当 live 配置文件被外部（非本进程）修改时以结构化差异调用 `on_change`
+///
+/// 监听各文件所在目录（兼容编辑器“写临时文件再重命名”的保存方式），本进程自身的写入会被忽略
+pub fn start<F>(on_change: F)
+where
+    F: Fn(LiveConfigChange) + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let mut cache: HashMap<LiveFile, (PathBuf, Value)> = HashMap::new();
+        for file in LiveFile::ALL {
+            let path = file.path();
+            if let Ok((value, _)) = file.read(&path) {
+                cache.insert(file, (path, value));
+            }
+        }
+        let cache = RefCell::new(cache);
+
+        run_watch_loop(
+            || LiveFile::ALL.iter().map(|f| f.path()).collect(),
+            |touched| {
+                for file in LiveFile::ALL {
+                    let path = file.path();
+                    if touched.contains(&path) {
+                        if let Some(change) = check_file(file, &path, &mut cache.borrow_mut()) {
+                            on_change(change);
+                        }
+                    }
+                }
+            },
+            || {
+                // 路径因设置变更而改变时，以新文件内容为基准，不产生事件
+                let mut cache = cache.borrow_mut();
+                for file in LiveFile::ALL {
+                    let path = file.path();
+                    if cache.get(&file).map(|(p, _)| p) != Some(&path) {
+                        if let Ok((value, _)) = file.read(&path) {
+                            cache.insert(file, (path, value));
+                        }
+                    }
+                }
+            },
+        );
+    });
+}
+
+/// 启动后台监听线程；`~/.cc-switch/config.enc.json` 被其他进程或外部工具（如 `git pull`）修改时调用 `on_change`
+///
+/// 本进程自身保存产生的事件会被忽略
+pub fn start_app_config_watch<F>(on_change: F)
+where
+    F: Fn() + Send + 'static,
+{
+    std::thread::spawn(move || {
+        run_watch_loop(
+            || vec![crate::config::get_app_config_enc_path()],
+            |touched| {
+                let path = crate::config::get_app_config_enc_path();
+                if !touched.contains(&path) {
+                    return;
+                }
+                // 文件被删除时同样视为外部修改，交由调用方判断
+                let is_self = std::fs::read(&path)
+                    .map(|data| crate::config::is_self_write(&path, &data))
+                    .unwrap_or(false);
+                if !is_self {
+                    on_change();
+                }
+            },
+            || {},
+        );
+    });
+}
+
+/// 监听 `targets` 所在目录的事件循环：事件按 [`DEBOUNCE`] 合并后交给 `on_batch`，
+/// 空闲 [`RESCAN_INTERVAL`] 后重新解析路径（补充新出现的目录）并调用 `on_rescan`
+fn run_watch_loop(
+    targets: impl Fn() -> Vec<PathBuf>,
+    on_batch: impl Fn(&HashSet<PathBuf>),
+    on_rescan: impl Fn(),
+) {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(tx) {
+        Ok(w) => w,
+        Err(e) => {
+            log::error!("创建配置文件监听失败: {}", e);
+            return;
+        }
+    };
+
+    let mut watched_dirs: HashSet<PathBuf> = HashSet::new();
+    watch_dirs(&mut watcher, &mut watched_dirs, &targets());
+
+    loop {
+        match rx.recv_timeout(RESCAN_INTERVAL) {
+            Ok(first) => {
+                let mut touched: HashSet<PathBuf> = HashSet::new();
+                let mut collect = |res: notify::Result<notify::Event>| {
+                    // 忽略打开 / 读取产生的访问事件，否则回调中读取文件会再次触发监听
+                    match res {
+                        Ok(event) if !event.kind.is_access() => touched.extend(event.paths),
+                        _ => {}
+                    }
+                };
+                collect(first);
+                std::thread::sleep(DEBOUNCE);
+                while let Ok(more) = rx.try_recv() {
+                    collect(more);
+                }
+                on_batch(&touched);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                watch_dirs(&mut watcher, &mut watched_dirs, &targets());
+                on_rescan();
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+fn watch_dirs(
+    watcher: &mut notify::RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    targets: &[PathBuf],
+) {
+    for target in targets {
+        let Some(dir) = target.parent().map(Path::to_path_buf) else {
+            continue;
+        };
+        if watched.contains(&dir) || !dir.exists() {
+            continue;
+        }
+        match watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                log::info!("开始监听配置目录: {}", dir.display());
+                watched.insert(dir);
+            }
+            Err(e) => log::warn!("监听目录失败 {}: {}", dir.display(), e),
+        }
+    }
+}
+
+/// 读取文件并与缓存比较；外部修改返回差异，自身写入或无变化返回 None
+fn check_file(
+    file: LiveFile,
+    path: &Path,
+    cache: &mut HashMap<LiveFile, (PathBuf, Value)>,
+) -> Option<LiveConfigChange> {
+    let (value, bytes) = match file.read(path) {
+        Ok(v) => v,
+        Err(e) => {
+            // 编辑过程中可能短暂出现不完整内容，等待下一次事件
+            log::debug!("暂不处理 {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let previous = cache
+        .insert(file, (path.to_path_buf(), value.clone()))
+        .filter(|(p, _)| p == path)
+        .map(|(_, v)| v)
+        .unwrap_or(Value::Null);
+
+    if crate::config::is_self_write(path, &bytes) {
+        return None;
+    }
+    let changes = diff_json(&previous, &value);
+    if changes.is_empty() {
+        return None;
+    }
+    log::info!(
+        "检测到 live 配置被外部修改: {}（{} 处变化）",
+        path.display(),
+        changes.len()
+    );
+    Some(LiveConfigChange {
+        app_type: file.app_type().as_str().to_string(),
+        file,
+        path: path.to_string_lossy().to_string(),
+        changes,
+        synced: false,
+    })
+}
@@ -568,168 +568,132 @@ pub fn import_from_codex(config: &mut MultiAppConfig) -> Result<usize, String> {
 /// - 读取现有 config.toml；若语法无效则报错，不尝试覆盖
 /// - 仅更新 `mcp.servers` 或 `mcp_servers` 子表，保留 `mcp` 其它键
 /// - 仅写入启用项；无启用项时清理对应子表
+/// - 以可编辑文档修改：其余内容与未变化的服务器条目（含注释与格式）保持原样
 pub fn sync_enabled_to_codex(config: &MultiAppConfig) -> Result<(), String> {
-    use toml::{value::Value as TomlValue, Table as TomlTable};
+    use crate::codex_config::apply_entry;
+    use toml_edit::{Array, Item, Key, Table};
 
     // 1) 收集启用项（Codex 维度）
     let enabled = collect_enabled_servers(&config.mcp.codex);
 
-    // 2) 读取现有 config.toml 并解析为 Table（允许空文件）
+    // 2) 读取现有 config.toml 并解析为文档（允许空文件）
     let base_text = crate::codex_config::read_and_validate_codex_config_text()?;
-    let mut root: TomlTable = if base_text.trim().is_empty() {
-        TomlTable::new()
-    } else {
-        toml::from_str::<TomlTable>(&base_text)
-            .map_err(|e| format!("解析 config.toml 失败: {}", e))?
+    let mut doc = crate::codex_config::parse_config_document(&base_text)?;
+
+    // 清除 mcp.servers，但保留其他 mcp 字段
+    let drop_mcp_servers = |doc: &mut toml_edit::DocumentMut| {
+        let should_drop_mcp = match doc.get_mut("mcp") {
+            Some(Item::Table(tbl)) => {
+                tbl.remove("servers");
+                tbl.is_empty()
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if should_drop_mcp {
+            doc.remove("mcp");
+        }
     };
 
     // 3) 写入 servers 表（支持 mcp.servers 与 mcp_servers；优先沿用已有风格，默认 mcp_servers）
-    let prefer_mcp_servers = root.get("mcp_servers").is_some() || root.get("mcp").is_none();
+    let prefer_mcp_servers = doc.get("mcp_servers").is_some() || doc.get("mcp").is_none();
     if enabled.is_empty() {
         // 无启用项：移除两种节点
-        // 清除 mcp.servers，但保留其他 mcp 字段
-        let mut should_drop_mcp = false;
-        if let Some(mcp_val) = root.get_mut("mcp") {
-            match mcp_val {
-                TomlValue::Table(tbl) => {
-                    tbl.remove("servers");
-                    should_drop_mcp = tbl.is_empty();
-                }
-                _ => should_drop_mcp = true,
-            }
-        }
-        if should_drop_mcp {
-            root.remove("mcp");
-        }
-
-        // 清除顶层 mcp_servers
-        root.remove("mcp_servers");
+        drop_mcp_servers(&mut doc);
+        doc.remove("mcp_servers");
     } else {
-        let mut servers_tbl = TomlTable::new();
+        let mut servers_tbl = Table::new();
+        servers_tbl.set_implicit(true);
 
-        for (id, spec) in enabled.iter() {
-            let mut s = TomlTable::new();
+        // 按 id 排序，新增条目的顺序稳定
+        let mut entries: Vec<_> = enabled.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        for (id, spec) in entries {
+            let mut s = Table::new();
 
             // 类型（缺省视为 stdio）
             let typ = spec.get("type").and_then(|v| v.as_str()).unwrap_or("stdio");
-            s.insert("type".into(), TomlValue::String(typ.to_string()));
+            s.insert("type", toml_edit::value(typ));
 
             match typ {
                 "stdio" => {
-                    let cmd = spec
-                        .get("command")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    s.insert("command".into(), TomlValue::String(cmd));
+                    let cmd = spec.get("command").and_then(|v| v.as_str()).unwrap_or("");
+                    s.insert("command", toml_edit::value(cmd));
 
                     if let Some(args) = spec.get("args").and_then(|v| v.as_array()) {
-                        let arr = args
-                            .iter()
-                            .filter_map(|x| x.as_str())
-                            .map(|x| TomlValue::String(x.to_string()))
-                            .collect::<Vec<_>>();
+                        let arr: Array = args.iter().filter_map(|x| x.as_str()).collect();
                         if !arr.is_empty() {
-                            s.insert("args".into(), TomlValue::Array(arr));
+                            s.insert("args", toml_edit::value(arr));
                         }
                     }
 
                     if let Some(cwd) = spec.get("cwd").and_then(|v| v.as_str()) {
                         if !cwd.trim().is_empty() {
-                            s.insert("cwd".into(), TomlValue::String(cwd.to_string()));
+                            s.insert("cwd", toml_edit::value(cwd));
                         }
                     }
 
                     if let Some(env) = spec.get("env").and_then(|v| v.as_object()) {
-                        let mut env_tbl = TomlTable::new();
+                        let mut env_tbl = Table::new();
                         for (k, v) in env.iter() {
                             if let Some(sv) = v.as_str() {
-                                env_tbl.insert(k.clone(), TomlValue::String(sv.to_string()));
+                                env_tbl.insert(k, toml_edit::value(sv));
                             }
                         }
                         if !env_tbl.is_empty() {
-                            s.insert("env".into(), TomlValue::Table(env_tbl));
+                            s.insert("env", Item::Table(env_tbl));
                         }
                     }
                 }
                 "http" => {
-                    let url = spec
-                        .get("url")
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    s.insert("url".into(), TomlValue::String(url));
+                    let url = spec.get("url").and_then(|v| v.as_str()).unwrap_or("");
+                    s.insert("url", toml_edit::value(url));
 
                     if let Some(headers) = spec.get("headers").and_then(|v| v.as_object()) {
-                        let mut h_tbl = TomlTable::new();
+                        let mut h_tbl = Table::new();
                         for (k, v) in headers.iter() {
                             if let Some(sv) = v.as_str() {
-                                h_tbl.insert(k.clone(), TomlValue::String(sv.to_string()));
+                                h_tbl.insert(k, toml_edit::value(sv));
                             }
                         }
                         if !h_tbl.is_empty() {
-                            s.insert("headers".into(), TomlValue::Table(h_tbl));
+                            s.insert("headers", Item::Table(h_tbl));
                         }
                     }
                 }
                 _ => {}
             }
 
-            servers_tbl.insert(id.clone(), TomlValue::Table(s));
+            servers_tbl.insert(id, Item::Table(s));
         }
 
-        let servers_value = TomlValue::Table(servers_tbl.clone());
-
+        // 已有 servers 表时逐项比较，只改动有差异的服务器
+        let servers = Item::Table(servers_tbl);
         if prefer_mcp_servers {
-            root.insert("mcp_servers".into(), servers_value);
+            apply_entry(doc.as_table_mut(), &Key::new("mcp_servers"), &servers);
 
             // 若存在 mcp，则仅移除 servers 字段，保留其他键
-            let mut should_drop_mcp = false;
-            if let Some(mcp_val) = root.get_mut("mcp") {
-                match mcp_val {
-                    TomlValue::Table(tbl) => {
-                        tbl.remove("servers");
-                        should_drop_mcp = tbl.is_empty();
-                    }
-                    _ => should_drop_mcp = true,
-                }
-            }
-            if should_drop_mcp {
-                root.remove("mcp");
-            }
+            drop_mcp_servers(&mut doc);
         } else {
-            let mut inserted = false;
-
-            if let Some(mcp_val) = root.get_mut("mcp") {
-                match mcp_val {
-                    TomlValue::Table(tbl) => {
-                        tbl.insert("servers".into(), TomlValue::Table(servers_tbl.clone()));
-                        inserted = true;
-                    }
-                    _ => {
-                        let mut mcp_tbl = TomlTable::new();
-                        mcp_tbl.insert("servers".into(), TomlValue::Table(servers_tbl.clone()));
-                        *mcp_val = TomlValue::Table(mcp_tbl);
-                        inserted = true;
-                    }
+            match doc.get_mut("mcp") {
+                Some(Item::Table(mcp_tbl)) => {
+                    apply_entry(mcp_tbl, &Key::new("servers"), &servers);
+                }
+                _ => {
+                    let mut mcp_tbl = Table::new();
+                    mcp_tbl.set_implicit(true);
+                    mcp_tbl.insert("servers", servers);
+                    doc.insert("mcp", Item::Table(mcp_tbl));
                 }
             }
 
-            if !inserted {
-                let mut mcp_tbl = TomlTable::new();
-                mcp_tbl.insert("servers".into(), TomlValue::Table(servers_tbl));
-                root.insert("mcp".into(), TomlValue::Table(mcp_tbl));
-            }
-
-            root.remove("mcp_servers");
+            doc.remove("mcp_servers");
         }
     }
 
-    // 4) 序列化并写回 config.toml（仅改 TOML，不触碰 auth.json）
-    let new_text = toml::to_string(&TomlValue::Table(root))
-        .map_err(|e| format!("序列化 config.toml 失败: {}", e))?;
+    // 4) 写回 config.toml（仅改 TOML，不触碰 auth.json）
     let path = crate::codex_config::get_codex_config_path();
-    crate::config::write_text_file(&path, &new_text)?;
+    crate::config::write_text_file(&path, &doc.to_string())?;
 
     Ok(())
 }
@@ -1,7 +1,9 @@
 use crate::app_config::{AppType, MultiAppConfig};
 use crate::config::{
-    archive_file, delete_file, get_app_config_dir, get_app_config_path, get_claude_config_dir,
+    archive_file, delete_file, get_app_config_dir, get_app_config_enc_path, get_app_config_path,
+    get_claude_config_dir,
 };
+use crate::provider::ClaudeCredentialKind;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::fs;
@@ -36,12 +38,14 @@ fn next_unique_id(existing: &HashSet<String>, base: &str) -> String {
     format!("{}-dup", base)
 }
 
+/// 提取 Claude 凭据（带类型前缀，同一个 Key 以不同字段发送视为不同配置）
 fn extract_claude_api_key(value: &Value) -> Option<String> {
+    let kind = ClaudeCredentialKind::detect(value)?;
     value
         .get("env")
-        .and_then(|env| env.get("ANTHROPIC_AUTH_TOKEN"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
+        .and_then(|env| env.get(kind.env_key()))
+        .and_then(crate::secrets::resolve_secret_str)
+        .map(|key| format!("{}:{}", kind.env_key(), key))
 }
 
 fn extract_codex_api_key(value: &Value) -> Option<String> {
@@ -51,8 +55,7 @@ fn extract_codex_api_key(value: &Value) -> Option<String> {
             auth.get("OPENAI_API_KEY")
                 .or_else(|| auth.get("openai_api_key"))
         })
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
+        .and_then(crate::secrets::resolve_secret_str)
 }
 
 fn norm_name(s: &str) -> String {
@@ -162,11 +165,12 @@ pub fn migrate_copies_into_config(config: &mut MultiAppConfig) -> Result<bool, S
         return Ok(false);
     }
 
-    // 备份旧的 config.json
+    // 备份旧的 config.json / config.enc.json
     let ts = now_ts();
-    let app_cfg_path = get_app_config_path();
-    if app_cfg_path.exists() {
-        let _ = archive_file(ts, "cc-switch", &app_cfg_path);
+    for app_cfg_path in [get_app_config_path(), get_app_config_enc_path()] {
+        if app_cfg_path.exists() {
+            let _ = archive_file(ts, "cc-switch", &app_cfg_path);
+        }
     }
 
     // 读取 live：Claude（settings.json / claude.json）
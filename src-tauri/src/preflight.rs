@@ -0,0 +1,552 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Instant;
+
+use crate::app_config::AppType;
+use crate::provider::{ClaudeCredentialKind, Provider};
+
+const CLAUDE_DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+const CODEX_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const FACTORY_ORIGIN: &str = "https://app.factory.ai";
+const FACTORY_USAGE_PATH: &str = "/api/organization/members/chat-usage";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 切换前连通性检查的处理方式（按应用在 `settings.json` 的 `switchPreflight` 中配置）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreflightMode {
+    /// 不检查
+    #[default]
+    Off,
+    /// 检查失败仍然切换，结果返回给调用方用于提示
+    Warn,
+    /// 检查失败时放弃切换
+    Block,
+}
+
+/// 检查结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PreflightStatus {
+    /// 凭据有效且接口格式符合预期
+    Ok,
+    /// 服务返回 401 / 403
+    AuthFailed,
+    /// 连接失败、超时或服务端 5xx
+    Unreachable,
+    /// 能连通，但返回的状态码或内容不像目标 API（地址填错、代理不兼容等）
+    WrongApiShape,
+    /// 缺少凭据等原因无法检查
+    Skipped,
+}
+
+impl PreflightStatus {
+    /// 与序列化结果相同的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreflightStatus::Ok => "ok",
+            PreflightStatus::AuthFailed => "authFailed",
+            PreflightStatus::Unreachable => "unreachable",
+            PreflightStatus::WrongApiShape => "wrongApiShape",
+            PreflightStatus::Skipped => "skipped",
+        }
+    }
+}
+
+/// 一次检查的结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightResult {
+    pub status: PreflightStatus,
+    /// 实际请求的地址（跳过时为空字符串）
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u128>,
+    pub message: String,
+}
+
+impl PreflightResult {
+    fn skipped(message: impl Into<String>) -> Self {
+        Self {
+            status: PreflightStatus::Skipped,
+            url: String::new(),
+            http_status: None,
+            latency_ms: None,
+            message: message.into(),
+        }
+    }
+
+    /// 是否应视为失败（跳过不算失败）
+    pub fn is_failure(&self) -> bool {
+        !matches!(self.status, PreflightStatus::Ok | PreflightStatus::Skipped)
+    }
+}
+
+/// 检查请求：地址、认证头与成功响应中必须包含的字段
+#[derive(Debug, Clone)]
+struct PreflightRequest {
+    url: Url,
+    headers: Vec<(&'static str, String)>,
+    expect_key: &'static str,
+}
+
+/// 供应商配置中的字符串字段（空字符串视为未填写）
+fn non_empty<'a>(settings: &'a Value, pointer: &str) -> Option<&'a str> {
+    settings
+        .pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// `base` 去掉末尾的 `/` 后拼接 `path`
+fn join_url(base: &str, path: &str) -> Result<Url, String> {
+    let raw = format!("{}{}", base.trim().trim_end_matches('/'), path);
+    Url::parse(&raw).map_err(|e| format!("地址无效 {}: {}", raw, e))
+}
+
+impl PreflightRequest {
+    /// 按应用类型构造请求；`provider` 须已解析密钥引用
+    ///
+    /// `base_url_override` 替换配置中的地址（Droid 替换 Factory 的源站），用于指向本地模拟服务
+    fn for_provider(
+        app_type: &AppType,
+        provider: &Provider,
+        base_url_override: Option<&str>,
+    ) -> Result<Self, String> {
+        let settings = &provider.settings_config;
+        match app_type {
+            AppType::Claude => {
+                let kind = provider
+                    .claude_credential_kind()
+                    .ok_or_else(|| "未配置 API Key，跳过检查".to_string())?;
+                let secret = non_empty(settings, &format!("/env/{}", kind.env_key()))
+                    .ok_or_else(|| "未配置 API Key，跳过检查".to_string())?;
+                let auth = match kind {
+                    ClaudeCredentialKind::ApiKey => ("x-api-key", secret.to_string()),
+                    ClaudeCredentialKind::AuthToken => {
+                        ("Authorization", format!("Bearer {}", secret))
+                    }
+                };
+                let base = base_url_override
+                    .map(str::to_string)
+                    .or_else(|| provider.base_url(app_type))
+                    .filter(|s| !s.trim().is_empty())
+                    .unwrap_or_else(|| CLAUDE_DEFAULT_BASE_URL.to_string());
+                Ok(Self {
+                    url: join_url(&base, "/v1/models")?,
+                    headers: vec![auth, ("anthropic-version", ANTHROPIC_VERSION.to_string())],
+                    expect_key: "data",
+                })
+            }
+            AppType::Codex => {
+                let key = non_empty(settings, "/auth/OPENAI_API_KEY").ok_or_else(|| {
+                    "未配置 OPENAI_API_KEY（可能使用 ChatGPT 登录），跳过检查".to_string()
+                })?;
+                let base = base_url_override
+                    .map(str::to_string)
+                    .or_else(|| provider.base_url(app_type))
+                    .filter(|s| !s.trim().is_empty())
+                    .unwrap_or_else(|| CODEX_DEFAULT_BASE_URL.to_string());
+                Ok(Self {
+                    url: join_url(&base, "/models")?,
+                    headers: vec![("Authorization", format!("Bearer {}", key))],
+                    expect_key: "data",
+                })
+            }
+            AppType::Droid => {
+                let key = non_empty(settings, "/apiKey")
+                    .ok_or_else(|| "未配置 apiKey，跳过检查".to_string())?;
+                let origin = base_url_override.unwrap_or(FACTORY_ORIGIN);
+                Ok(Self {
+                    url: join_url(origin, FACTORY_USAGE_PATH)?,
+                    headers: vec![
+                        ("Authorization", format!("Bearer {}", key)),
+                        ("x-factory-client", "web-browser".to_string()),
+                    ],
+                    expect_key: "usage",
+                })
+            }
+        }
+    }
+
+    async fn send(&self) -> PreflightResult {
+        let url = self.url.to_string();
+        let result = |status, http_status, latency_ms, message: String| PreflightResult {
+            status,
+            url: url.clone(),
+            http_status,
+            latency_ms,
+            message,
+        };
+
+        let client = match crate::speedtest::build_client(crate::speedtest::sanitize_timeout(None))
+        {
+            Ok(client) => client,
+            Err(e) => return result(PreflightStatus::Unreachable, None, None, e),
+        };
+        let mut request = client.get(self.url.clone());
+        for (name, value) in &self.headers {
+            request = request.header(*name, value);
+        }
+
+        let start = Instant::now();
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                let message = if e.is_timeout() {
+                    "请求超时".to_string()
+                } else if e.is_connect() {
+                    "连接失败".to_string()
+                } else {
+                    e.to_string()
+                };
+                return result(PreflightStatus::Unreachable, None, None, message);
+            }
+        };
+        let latency = Some(start.elapsed().as_millis());
+        let code = response.status();
+        let http_status = Some(code.as_u16());
+        let body = response.text().await.unwrap_or_default();
+
+        if code.as_u16() == 401 || code.as_u16() == 403 {
+            return result(
+                PreflightStatus::AuthFailed,
+                http_status,
+                latency,
+                format!("凭据无效（HTTP {}）", code.as_u16()),
+            );
+        }
+        if code.is_server_error() {
+            return result(
+                PreflightStatus::Unreachable,
+                http_status,
+                latency,
+                format!("服务端错误（HTTP {}）", code.as_u16()),
+            );
+        }
+        if !code.is_success() {
+            return result(
+                PreflightStatus::WrongApiShape,
+                http_status,
+                latency,
+                format!("接口返回 HTTP {}，请确认地址是否正确", code.as_u16()),
+            );
+        }
+        let shaped = serde_json::from_str::<Value>(&body)
+            .ok()
+            .is_some_and(|v| v.get(self.expect_key).is_some());
+        if shaped {
+            result(
+                PreflightStatus::Ok,
+                http_status,
+                latency,
+                "连接正常".to_string(),
+            )
+        } else {
+            result(
+                PreflightStatus::WrongApiShape,
+                http_status,
+                latency,
+                format!("响应不是预期的 JSON（缺少 {} 字段）", self.expect_key),
+            )
+        }
+    }
+}
+
+/// 对供应商发起一次带认证的轻量请求（Claude / Codex 为 `GET /models`，Droid 为用量查询）
+///
+/// `provider` 须已解析密钥引用；无法构造请求时返回 `Skipped`
+pub async fn check(
+    app_type: &AppType,
+    provider: &Provider,
+    base_url_override: Option<&str>,
+) -> PreflightResult {
+    match PreflightRequest::for_provider(app_type, provider, base_url_override) {
+        Ok(request) => request.send().await,
+        Err(reason) => PreflightResult::skipped(reason),
+    }
+}
+
+/// 读取应用的检查方式（每次切换重新读取设置）
+pub fn mode_for(app_type: &AppType) -> PreflightMode {
+    crate::settings::AppSettings::load()
+        .switch_preflight
+        .get(app_type.as_str())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// 切换前要执行的检查
+#[derive(Debug, Clone)]
+pub struct Preflight {
+    mode: PreflightMode,
+    app_type: AppType,
+    request: Result<PreflightRequest, String>,
+}
+
+impl Preflight {
+    /// 准备以 `mode` 方式对 `provider`（已解析密钥引用）做的检查
+    pub fn new(mode: PreflightMode, app_type: &AppType, provider: &Provider) -> Self {
+        Self {
+            mode,
+            app_type: *app_type,
+            request: PreflightRequest::for_provider(app_type, provider, None),
+        }
+    }
+
+    /// 执行检查：`block` 模式下失败返回错误，`warn` 模式下返回结果由调用方提示
+    pub async fn run(&self) -> Result<PreflightResult, String> {
+        let result = match &self.request {
+            Ok(request) => request.send().await,
+            Err(reason) => PreflightResult::skipped(reason.clone()),
+        };
+        if result.is_failure() {
+            if self.mode == PreflightMode::Block {
+                return Err(format!("连通性检查失败，已取消切换: {}", result.message));
+            }
+            log::warn!(
+                "{} 切换前连通性检查失败（{}）: {}",
+                self.app_type.as_str(),
+                result.url,
+                result.message
+            );
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::switch_hooks::SwitchPlan;
+    use serde_json::json;
+    use std::cell::Cell;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// 只应答一次请求的本地模拟服务，返回地址与收到的请求头（小写）
+    async fn mock_server(status: u16, body: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            stream.shutdown().await.ok();
+            String::from_utf8_lossy(&request).to_ascii_lowercase()
+        });
+        (base, handle)
+    }
+
+    /// 已关闭的本地端口
+    async fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        base
+    }
+
+    fn claude_provider() -> Provider {
+        Provider::with_id(
+            "c1".to_string(),
+            "Claude".to_string(),
+            json!({"env": {"ANTHROPIC_AUTH_TOKEN": "sk-test", "ANTHROPIC_BASE_URL": "https://example.invalid"}}),
+            None,
+        )
+    }
+
+    fn codex_provider() -> Provider {
+        Provider::with_id(
+            "x1".to_string(),
+            "Codex".to_string(),
+            json!({"auth": {"OPENAI_API_KEY": "sk-codex"}, "config": ""}),
+            None,
+        )
+    }
+
+    fn droid_provider() -> Provider {
+        Provider::with_id(
+            "d1".to_string(),
+            "Droid".to_string(),
+            json!({"apiKey": "fk-droid"}),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn claude_ok_uses_override_and_credential() {
+        let (base, server) = mock_server(200, r#"{"data":[]}"#).await;
+        let result = check(&AppType::Claude, &claude_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Ok);
+        assert_eq!(result.http_status, Some(200));
+        assert_eq!(result.url, format!("{}/v1/models", base));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("get /v1/models "));
+        assert!(request.contains("authorization: bearer sk-test"));
+        assert!(request.contains("anthropic-version: 2023-06-01"));
+    }
+
+    #[tokio::test]
+    async fn claude_api_key_uses_x_api_key_header() {
+        let provider = Provider::with_id(
+            "c2".to_string(),
+            "Claude".to_string(),
+            json!({"env": {"ANTHROPIC_API_KEY": "sk-key"}}),
+            None,
+        );
+        let (base, server) = mock_server(200, r#"{"data":[]}"#).await;
+        let result = check(&AppType::Claude, &provider, Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Ok);
+        assert!(server.await.unwrap().contains("x-api-key: sk-key"));
+    }
+
+    #[tokio::test]
+    async fn codex_ok() {
+        let (base, server) = mock_server(200, r#"{"object":"list","data":[]}"#).await;
+        let result = check(&AppType::Codex, &codex_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Ok);
+        let request = server.await.unwrap();
+        assert!(request.starts_with("get /models "));
+        assert!(request.contains("authorization: bearer sk-codex"));
+    }
+
+    #[tokio::test]
+    async fn droid_ok() {
+        let (base, server) = mock_server(200, r#"{"usage":{}}"#).await;
+        let result = check(&AppType::Droid, &droid_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Ok);
+        let request = server.await.unwrap();
+        assert!(request.starts_with(&format!("get {} ", FACTORY_USAGE_PATH)));
+    }
+
+    #[tokio::test]
+    async fn auth_failed_on_401_and_403() {
+        for status in [401, 403] {
+            let (base, _server) = mock_server(status, r#"{"error":"denied"}"#).await;
+            let result = check(&AppType::Codex, &codex_provider(), Some(&base)).await;
+            assert_eq!(
+                result.status,
+                PreflightStatus::AuthFailed,
+                "HTTP {}",
+                status
+            );
+            assert_eq!(result.http_status, Some(status));
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_api_shape() {
+        for body in [r#"{"models":[]}"#, "<html>ok</html>"] {
+            let (base, _server) = mock_server(200, body).await;
+            let result = check(&AppType::Claude, &claude_provider(), Some(&base)).await;
+            assert_eq!(result.status, PreflightStatus::WrongApiShape, "{}", body);
+        }
+        let (base, _server) = mock_server(404, "{}").await;
+        let result = check(&AppType::Claude, &claude_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::WrongApiShape);
+    }
+
+    #[tokio::test]
+    async fn unreachable() {
+        let base = closed_port().await;
+        let result = check(&AppType::Codex, &codex_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Unreachable);
+        assert_eq!(result.http_status, None);
+
+        let (base, _server) = mock_server(502, "bad gateway").await;
+        let result = check(&AppType::Codex, &codex_provider(), Some(&base)).await;
+        assert_eq!(result.status, PreflightStatus::Unreachable);
+    }
+
+    #[tokio::test]
+    async fn skipped_without_credential() {
+        let provider = Provider::with_id(
+            "x2".to_string(),
+            "ChatGPT 登录".to_string(),
+            json!({"auth": {"OPENAI_API_KEY": ""}, "config": ""}),
+            None,
+        );
+        let result = check(&AppType::Codex, &provider, Some("http://127.0.0.1:9")).await;
+        assert_eq!(result.status, PreflightStatus::Skipped);
+        assert!(!result.is_failure());
+    }
+
+    fn plan(mode: PreflightMode, base: &str) -> SwitchPlan {
+        SwitchPlan {
+            preflight: Some(Preflight {
+                mode,
+                app_type: AppType::Codex,
+                request: PreflightRequest::for_provider(
+                    &AppType::Codex,
+                    &codex_provider(),
+                    Some(base),
+                ),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn warn_mode_switches_and_reports_failure() {
+        let (base, _server) = mock_server(401, "{}").await;
+        let switched = Cell::new(false);
+        let report = plan(PreflightMode::Warn, &base)
+            .execute(|| {
+                switched.set(true);
+                Ok(codex_provider())
+            })
+            .await
+            .unwrap();
+        assert!(switched.get());
+        assert_eq!(
+            report.preflight.map(|r| r.status),
+            Some(PreflightStatus::AuthFailed)
+        );
+    }
+
+    #[tokio::test]
+    async fn block_mode_cancels_switch_on_failure() {
+        let base = closed_port().await;
+        let switched = Cell::new(false);
+        let result = plan(PreflightMode::Block, &base)
+            .execute(|| {
+                switched.set(true);
+                Ok(codex_provider())
+            })
+            .await;
+        assert!(result.is_err());
+        assert!(!switched.get());
+    }
+
+    #[tokio::test]
+    async fn block_mode_switches_when_check_passes() {
+        let (base, _server) = mock_server(200, r#"{"data":[]}"#).await;
+        let report = plan(PreflightMode::Block, &base)
+            .execute(|| Ok(codex_provider()))
+            .await
+            .unwrap();
+        assert_eq!(
+            report.preflight.map(|r| r.status),
+            Some(PreflightStatus::Ok)
+        );
+    }
+}
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::app_config::AppType;
+use crate::failover::FailoverPolicy;
 
 // SSOT 模式：不再写供应商副本文件
 
@@ -22,6 +26,100 @@ pub struct Provider {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "alternativeUrls")]
     pub alternative_urls: Option<Vec<String>>,
+    /// Claude 凭据类型；未设置时按 env 中存在的字段推断
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "credentialKind")]
+    pub credential_kind: Option<ClaudeCredentialKind>,
+    /// 由该供应商管理的 settings.json 顶层字段（如 model、permissions、statusLine），
+    /// 切换时写入与回填的正是这些字段；env 始终按 env 合并策略处理，不在此列
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "managedPaths")]
+    pub managed_paths: Option<Vec<String>>,
+    /// 备选地址自动故障转移（Claude / Codex，需至少两个备选地址）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failover: Option<FailoverPolicy>,
+    /// Codex config.toml 的写入方式；未设置时为完整替换
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "codexConfigMode")]
+    pub codex_config_mode: Option<CodexConfigMode>,
+    /// profile 模式下把 API Key 以明文 `experimental_bearer_token` 写入 config.toml（需显式开启），
+    /// 默认改为写入 `env_key`，由用户自行导出对应的环境变量
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "codexInlineToken")]
+    pub codex_inline_token: Option<bool>,
+}
+
+/// Codex 供应商 `config` 的含义与切换时的写入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CodexConfigMode {
+    /// 完整的 config.toml，切换时整体替换（未变化的部分保持原有格式）
+    #[default]
+    Full,
+    /// 只包含 `model`、`model_provider` 与 `[model_providers.*]`，切换时合并进 live 文件，
+    /// 回填时也只取这几项；其余个人设置留在 live 文件中，不随供应商切换
+    Section,
+}
+
+/// Claude 供应商的凭据类型：两种字段对应不同的请求头，同时存在时 Claude Code 会都发送
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ClaudeCredentialKind {
+    /// `ANTHROPIC_AUTH_TOKEN`（Authorization: Bearer）
+    AuthToken,
+    /// `ANTHROPIC_API_KEY`（x-api-key）
+    ApiKey,
+}
+
+impl ClaudeCredentialKind {
+    pub const ALL: [ClaudeCredentialKind; 2] = [
+        ClaudeCredentialKind::AuthToken,
+        ClaudeCredentialKind::ApiKey,
+    ];
+
+    /// 对应的 env 字段名
+    pub fn env_key(&self) -> &'static str {
+        match self {
+            ClaudeCredentialKind::AuthToken => "ANTHROPIC_AUTH_TOKEN",
+            ClaudeCredentialKind::ApiKey => "ANTHROPIC_API_KEY",
+        }
+    }
+
+    /// 另一种凭据类型
+    pub fn other(&self) -> Self {
+        match self {
+            ClaudeCredentialKind::AuthToken => ClaudeCredentialKind::ApiKey,
+            ClaudeCredentialKind::ApiKey => ClaudeCredentialKind::AuthToken,
+        }
+    }
+
+    /// `env` 中是否填写了该类型的凭据（空字符串视为未填写，密钥引用视为已填写）
+    pub fn is_set(&self, env: &Value) -> bool {
+        env.get(self.env_key())
+            .is_some_and(|v| !v.as_str().is_some_and(str::is_empty))
+    }
+
+    /// 按 env 中已填写的凭据字段推断类型（两者都有时以 AUTH_TOKEN 为准）
+    pub fn detect(settings_config: &Value) -> Option<Self> {
+        let env = settings_config.get("env")?;
+        Self::ALL.into_iter().find(|kind| kind.is_set(env))
+    }
+
+    /// 旧配置或导入的 live 配置可能同时填写了两种凭据：保留 `preferred`
+    /// （未指定时与 [`Self::detect`] 一致，以 AUTH_TOKEN 为准）对应的字段，移除另一种
+    ///
+    /// 返回被移除的字段名；只填写了一种时不做修改
+    pub fn retain_one(env: &mut Value, preferred: Option<Self>) -> Option<&'static str> {
+        if !Self::ALL.iter().all(|kind| kind.is_set(env)) {
+            return None;
+        }
+        let removed = preferred
+            .unwrap_or(ClaudeCredentialKind::AuthToken)
+            .other()
+            .env_key();
+        env.as_object_mut()?.remove(removed);
+        Some(removed)
+    }
 }
 
 impl Provider {
@@ -40,6 +138,67 @@ impl Provider {
             category: None,
             created_at: None,
             alternative_urls: None,
+            credential_kind: None,
+            managed_paths: None,
+            failover: None,
+            codex_config_mode: None,
+            codex_inline_token: None,
+        }
+    }
+
+    /// Codex config.toml 的写入方式
+    pub fn codex_config_mode(&self) -> CodexConfigMode {
+        self.codex_config_mode.unwrap_or_default()
+    }
+
+    /// profile 模式下是否把 API Key 明文写入 config.toml
+    pub fn codex_inline_token(&self) -> bool {
+        self.codex_inline_token.unwrap_or(false)
+    }
+
+    /// 声明的受管顶层字段（去除空值、env 与重复项）
+    pub fn claude_managed_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .managed_paths
+            .iter()
+            .flatten()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty() && p != "env")
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Claude 凭据类型：显式设置优先，否则按 env 推断
+    pub fn claude_credential_kind(&self) -> Option<ClaudeCredentialKind> {
+        self.credential_kind
+            .or_else(|| ClaudeCredentialKind::detect(&self.settings_config))
+    }
+
+    /// 配置中声明的 API 地址：Claude 取 `env.ANTHROPIC_BASE_URL`，Codex 取当前 `model_provider` 的 `base_url`
+    ///
+    /// Droid 供应商只有 API Key（固定使用 Factory 官方地址），返回 None
+    pub fn base_url(&self, app_type: &AppType) -> Option<String> {
+        let settings = &self.settings_config;
+        match app_type {
+            AppType::Claude => settings
+                .pointer("/env/ANTHROPIC_BASE_URL")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            AppType::Codex => {
+                let text = settings.get("config").and_then(|v| v.as_str())?;
+                let table: toml::Table = toml::from_str(text).ok()?;
+                let from_provider = table
+                    .get("model_provider")
+                    .and_then(|v| v.as_str())
+                    .and_then(|name| table.get("model_providers")?.get(name)?.get("base_url"));
+                from_provider
+                    .or_else(|| table.get("base_url"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            }
+            AppType::Droid => None,
         }
     }
 }
@@ -49,6 +208,100 @@ impl Provider {
 pub struct ProviderManager {
     pub providers: HashMap<String, Provider>,
     pub current: String,
+    /// 当前供应商写入 live env 的键（仅 Claude 使用），切换时只移除这些键，其余视为用户自有
+    #[serde(
+        default,
+        rename = "managedEnvKeys",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub managed_env_keys: Vec<String>,
+    /// 当前供应商写入 settings.json 的顶层字段（仅 Claude 使用），切换时先移除再写入新供应商的字段
+    #[serde(
+        default,
+        rename = "managedSettingsPaths",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub managed_settings_paths: Vec<String>,
+    /// 临时切换：到期后自动恢复为切换前的供应商
+    #[serde(
+        default,
+        rename = "temporarySwitch",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub temporary: Option<TemporarySwitch>,
+    /// 已写入 config.toml 的 `[model_providers.<id>]` / `[profiles.<id>]`（仅 Codex 使用），
+    /// 供应商被删除或关闭 profile 模式时据此清理
+    #[serde(
+        default,
+        rename = "codexProfileIds",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub codex_profile_ids: Vec<String>,
+}
+
+/// 一次临时切换：`provider_id` 为临时使用的供应商，`revert_at` 之后恢复为 `previous_provider_id`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemporarySwitch {
+    pub provider_id: String,
+    /// 切换前的供应商
+    pub previous_provider_id: String,
+    /// 恢复时间（Unix 毫秒）
+    pub revert_at: i64,
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+impl TemporarySwitch {
+    /// 临时切换的最长时长（365 天）
+    pub const MAX_DURATION: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+    /// 校验临时切换的时长：须大于 0 且不超过 [`Self::MAX_DURATION`]
+    pub fn validate_duration(duration: Duration) -> Result<(), String> {
+        if duration.is_zero() {
+            return Err("临时切换的时长必须大于 0".to_string());
+        }
+        if duration > Self::MAX_DURATION {
+            return Err(format!(
+                "临时切换的时长不能超过 {} 天",
+                Self::MAX_DURATION.as_secs() / 86400
+            ));
+        }
+        Ok(())
+    }
+
+    /// 从现在起临时使用 `provider_id`，`duration` 后恢复为 `previous_provider_id`
+    pub fn new(
+        provider_id: &str,
+        previous_provider_id: &str,
+        duration: Duration,
+    ) -> Result<Self, String> {
+        Self::validate_duration(duration)?;
+        let revert_at = i64::try_from(duration.as_millis())
+            .ok()
+            .and_then(|millis| now_millis().checked_add(millis))
+            .ok_or_else(|| "临时切换的时长超出范围".to_string())?;
+        Ok(Self {
+            provider_id: provider_id.to_string(),
+            previous_provider_id: previous_provider_id.to_string(),
+            revert_at,
+        })
+    }
+
+    /// 距离恢复的剩余时间（已到期时为 0）
+    pub fn remaining(&self) -> Duration {
+        Duration::from_millis(self.revert_at.saturating_sub(now_millis()).max(0) as u64)
+    }
+
+    /// 是否已到恢复时间
+    pub fn is_due(&self) -> bool {
+        self.remaining().is_zero()
+    }
 }
 
 impl ProviderManager {
@@ -0,0 +1,1474 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::app_config::{AppType, MultiAppConfig};
+use crate::codex_config;
+use crate::config::{
+    get_app_config_enc_path, get_claude_settings_path, get_secrets_path, read_json_file,
+    write_json_file,
+};
+use crate::config_recovery::{self, RecoveryReport};
+use crate::config_sync::{self, DiskStamp};
+use crate::preflight::{self, Preflight, PreflightMode};
+use crate::provider::{
+    ClaudeCredentialKind, CodexConfigMode, Provider, ProviderManager, TemporarySwitch,
+};
+use crate::schedule::{self, ScheduledSwitch};
+use crate::secrets;
+use crate::snapshot::{self, SnapshotDiff};
+use crate::switch_hooks::{SwitchHookRun, SwitchPlan};
+use crate::switch_journal::{self, SwitchJournal};
+use crate::switch_preview::{FileFormat, FilePreview, SwitchPreview};
+
+/// 供应商核心服务：持有 `MultiAppConfig`，封装增删改查、切换、停用与导入等领域逻辑
+///
+/// 不依赖 Tauri，GUI 命令、托盘、CLI 以及其他链接 `cc_switch_lib` 的程序共用同一套实现。
+/// 所有方法仅修改内存与 live 配置文件，持久化由调用方通过 [`ProviderService::save`] 决定时机；
+/// 需要“读取 - 修改 - 保存”原子完成时使用 [`ProviderService::modify`]。
+#[derive(Debug, Clone, Default)]
+pub struct ProviderService {
+    config: MultiAppConfig,
+    /// 上次从磁盘加载或保存时的配置，作为与其他进程改动三方合并的基准
+    base: MultiAppConfig,
+    /// 上次加载或保存时配置文件的磁盘状态
+    disk: Option<DiskStamp>,
+    /// 配置损坏且无法恢复时为只读，拒绝任何修改与保存，避免覆盖原文件
+    read_only: bool,
+    /// 已写入 live 文件、等待配置保存的切换日志
+    journal: Option<SwitchJournal>,
+}
+
+impl ProviderService {
+    /// 以已加载的配置创建服务（视为与当前磁盘内容一致）
+    pub fn new(config: MultiAppConfig) -> Self {
+        Self {
+            base: config.clone(),
+            config,
+            disk: DiskStamp::current(),
+            read_only: false,
+            journal: None,
+        }
+    }
+
+    /// 从 `~/.cc-switch/config.enc.json`（或待迁移的旧明文 `config.json`）加载配置并创建服务
+    ///
+    /// 旧配置中内联的明文密钥会被移入密钥库并立即保存；上次中断的切换先完成或回滚
+    pub fn load() -> Result<Self, String> {
+        config_sync::with_config_lock(switch_journal::recover)?;
+        let mut config = MultiAppConfig::load()?;
+        let extracted = secrets::extract_config_secrets(&mut config)?;
+        if extracted > 0 {
+            log::info!("已将 {} 个供应商的明文密钥移入密钥库", extracted);
+            config_sync::with_config_lock(|| config.save())?;
+        }
+        Ok(Self::new(config))
+    }
+
+    /// 只读加载，供 `list`、`current` 等查询命令使用：不处理未完成的切换、不保存迁移结果、
+    /// 不把明文密钥移入密钥库，不写任何文件，避免与正在运行的主程序竞争
+    ///
+    /// 返回的服务处于只读模式，任何修改都会被拒绝
+    pub fn load_read_only() -> Result<Self, String> {
+        let mut service = Self::new(MultiAppConfig::load_read_only()?);
+        service.read_only = true;
+        Ok(service)
+    }
+
+    /// 加载配置，失败时尝试从备份 / 归档恢复（见 [`config_recovery::load_or_recover`]）
+    ///
+    /// 恢复成功会立即保存到加密配置；无法恢复时返回只读服务
+    pub fn load_or_recover() -> (Self, Option<RecoveryReport>) {
+        if let Err(e) = config_sync::with_config_lock(switch_journal::recover) {
+            log::error!("处理未完成的切换失败: {}", e);
+        }
+        let (config, report) = config_recovery::load_or_recover();
+        let mut service = Self::new(config);
+        if report.as_ref().is_some_and(|r| r.read_only) {
+            service.read_only = true;
+            return (service, report);
+        }
+        // 恢复来源可能是旧的明文配置，同样需要把密钥移入密钥库
+        let extracted = match secrets::extract_config_secrets(&mut service.config) {
+            Ok(n) => n,
+            Err(e) => {
+                log::error!("提取明文密钥失败: {}", e);
+                0
+            }
+        };
+        if extracted > 0 {
+            log::info!("已将 {} 个供应商的明文密钥移入密钥库", extracted);
+        }
+        if report.is_some() || extracted > 0 {
+            if let Err(e) = service.save() {
+                log::error!("保存配置失败: {}", e);
+            }
+        }
+        (service, report)
+    }
+
+    /// 是否处于只读模式
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn ensure_writable(&self) -> Result<(), String> {
+        if self.read_only {
+            return Err("配置文件损坏且无法恢复，当前为只读模式，已拒绝修改".to_string());
+        }
+        Ok(())
+    }
+
+    /// 内存中是否有尚未保存的修改
+    pub fn has_unsaved_changes(&self) -> bool {
+        !config_sync::same_config(&self.config, &self.base)
+    }
+
+    /// 若配置文件自上次加载 / 保存后被其他进程修改（修改时间或内容哈希变化），重新读取
+    ///
+    /// 内存中有未保存的修改时与磁盘内容三方合并；返回是否发生了重新读取
+    pub fn refresh_from_disk(&mut self) -> Result<bool, String> {
+        if self.read_only || !config_sync::disk_changed(self.disk.as_ref()) {
+            return Ok(false);
+        }
+        // 先记录磁盘状态再读取：读取期间若再次被修改，下次检查仍会发现
+        let stamp = DiskStamp::current();
+        let theirs = MultiAppConfig::load()?;
+        if self.has_unsaved_changes() {
+            self.config = config_sync::merge_configs(&self.base, &self.config, &theirs);
+            log::info!("配置文件已被其他进程修改，已与未保存的修改合并");
+        } else {
+            self.config = theirs.clone();
+            log::info!("配置文件已被其他进程修改，已重新加载");
+        }
+        self.base = theirs;
+        self.disk = stamp;
+        Ok(true)
+    }
+
+    /// 配置文件被外部修改时热重载：仅在内存中没有未保存修改时重新读取
+    ///
+    /// 有未保存修改时保持不动（保存时会按 [`Self::refresh_from_disk`] 合并），返回是否已重新加载
+    pub fn reload_if_clean(&mut self) -> Result<bool, String> {
+        if self.has_unsaved_changes() {
+            log::info!("配置文件已被外部修改，但存在未保存的修改，暂不重新加载");
+            return Ok(false);
+        }
+        // 文件被删除（如切换 git 分支的中间状态）时不把内存配置清空
+        if !get_app_config_enc_path().exists() {
+            return Ok(false);
+        }
+        self.refresh_from_disk()
+    }
+
+    /// 加密保存配置到 `~/.cc-switch/config.enc.json`（只读模式下拒绝）
+    ///
+    /// 持有跨进程配置锁，写入前先合并其他进程的改动，不会直接覆盖
+    pub fn save(&mut self) -> Result<(), String> {
+        self.ensure_writable()?;
+        config_sync::with_config_lock(|| {
+            self.refresh_from_disk()?;
+            self.config.save()?;
+            self.mark_synced();
+            // 配置已落盘，切换事务到此提交；日志删除失败时下次启动会判定为已完成
+            if let Some(journal) = self.journal.take() {
+                if let Err(e) = journal.commit() {
+                    log::warn!("{}", e);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// 在跨进程配置锁内完成“同步磁盘 - 修改 - 保存”；内存配置无变化时不写文件
+    ///
+    /// 修改或保存失败时，本次切换写入的 live 文件回滚，内存配置恢复到修改前
+    pub fn modify<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.ensure_writable()?;
+        config_sync::with_config_lock(|| {
+            // 其他进程在切换中途退出留下的日志
+            if self.journal.is_none() {
+                switch_journal::recover()?;
+            }
+            self.refresh_from_disk()?;
+            let before = self.config.clone();
+            let result = f(self).and_then(|out| {
+                if self.has_unsaved_changes() {
+                    self.save()?;
+                }
+                Ok(out)
+            });
+            result.map_err(|e| {
+                self.config = before;
+                match self.journal.take() {
+                    Some(journal) => rollback_journal(journal, e),
+                    None => e,
+                }
+            })
+        })
+    }
+
+    /// 记录当前内存配置与磁盘一致
+    fn mark_synced(&mut self) {
+        self.base = self.config.clone();
+        self.disk = DiskStamp::current();
+    }
+
+    /// 比较指定快照与当前配置（以快照为基准列出新增 / 删除 / 变更的供应商）
+    pub fn diff_snapshot(&self, snapshot_id: &str) -> Result<SnapshotDiff, String> {
+        snapshot::diff_snapshot(snapshot_id, &self.config)
+    }
+
+    /// 从快照恢复配置文件并替换内存中的配置，再按各应用恢复后的当前供应商重写 live 配置
+    ///
+    /// 只读模式下同样允许：恢复成功即视为配置已修复，退出只读模式
+    pub fn restore_snapshot(&mut self, snapshot_id: &str) -> Result<(), String> {
+        let restored = config_sync::with_config_lock(|| snapshot::restore_snapshot(snapshot_id))?;
+        let previous = restored.previous.unwrap_or_else(|| self.config.clone());
+        self.config = restored.config;
+        self.mark_synced();
+        self.read_only = false;
+        self.modify(|service| service.apply_restored_live(&previous))
+            .map_err(|e| format!("配置已恢复，但写入 live 配置失败: {}", e))
+    }
+
+    /// 恢复快照后同步 live 配置：当前供应商或其配置与恢复前不同的应用重新写入，
+    /// Codex 同时重新生成 profile（并清理恢复前写入的 profile）
+    fn apply_restored_live(&mut self, previous: &MultiAppConfig) -> Result<(), String> {
+        for app_type in [AppType::Claude, AppType::Codex, AppType::Droid] {
+            let Some(manager) = self.config.get_manager_mut(&app_type) else {
+                continue;
+            };
+            let empty = ProviderManager::default();
+            let old = previous.get_manager(&app_type).unwrap_or(&empty);
+            if app_type == AppType::Codex {
+                for id in &old.codex_profile_ids {
+                    if !manager.codex_profile_ids.contains(id) {
+                        manager.codex_profile_ids.push(id.clone());
+                    }
+                }
+            }
+            let unchanged = manager.current == old.current
+                && manager
+                    .providers
+                    .get(&manager.current)
+                    .map(|p| &p.settings_config)
+                    == old.providers.get(&old.current).map(|p| &p.settings_config);
+            if unchanged {
+                if app_type == AppType::Codex {
+                    sync_codex_profiles(manager)?;
+                }
+                continue;
+            }
+
+            // live 中是恢复前写入的内容：以恢复前记录的受管部分为准移除
+            if app_type == AppType::Claude {
+                ClaudeManaged::of(old).store(manager);
+            }
+            match manager.providers.get(&manager.current) {
+                Some(provider) => {
+                    let resolved = resolve_provider(provider)?;
+                    write_live_for_edit(&app_type, &resolved, manager)?;
+                }
+                None if app_type == AppType::Claude => {
+                    let previous = ClaudeManaged::of(manager);
+                    write_claude_live(None, &previous)?.store(manager);
+                }
+                None if app_type == AppType::Codex => sync_codex_profiles(manager)?,
+                None => {}
+            }
+            log::info!("已按恢复的配置重写 {} 的 live 配置", app_type.as_str());
+        }
+        Ok(())
+    }
+
+    /// 获取配置（不可变引用）
+    pub fn config(&self) -> &MultiAppConfig {
+        &self.config
+    }
+
+    /// 获取配置（可变引用），用于 MCP 等尚未纳入服务的操作
+    pub fn config_mut(&mut self) -> &mut MultiAppConfig {
+        &mut self.config
+    }
+
+    /// 取出内部配置
+    pub fn into_config(self) -> MultiAppConfig {
+        self.config
+    }
+
+    /// 获取指定应用的全部供应商
+    pub fn providers(&self, app_type: &AppType) -> Result<&HashMap<String, Provider>, String> {
+        self.config
+            .get_manager(app_type)
+            .map(|m| m.get_all_providers())
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))
+    }
+
+    /// 获取指定应用的全部供应商，并解析密钥引用（供界面编辑与余额查询使用，不落盘）
+    pub fn providers_resolved(
+        &self,
+        app_type: &AppType,
+    ) -> Result<HashMap<String, Provider>, String> {
+        self.providers(app_type)?
+            .iter()
+            .map(|(id, p)| {
+                let mut p = p.clone();
+                p.settings_config = secrets::resolve_secrets(&p.settings_config)?;
+                Ok((id.clone(), p))
+            })
+            .collect()
+    }
+
+    /// 获取指定供应商
+    pub fn provider(&self, app_type: &AppType, id: &str) -> Result<&Provider, String> {
+        self.providers(app_type)?
+            .get(id)
+            .ok_or_else(|| format!("供应商不存在: {}", id))
+    }
+
+    /// 获取指定供应商，并解析密钥引用（供连通性检查使用，不落盘）
+    pub fn provider_resolved(&self, app_type: &AppType, id: &str) -> Result<Provider, String> {
+        resolve_provider(self.provider(app_type, id)?)
+    }
+
+    /// 获取当前供应商 ID（未激活时为空字符串）
+    pub fn current_provider_id(&self, app_type: &AppType) -> Result<&str, String> {
+        self.config
+            .get_manager(app_type)
+            .map(|m| m.current.as_str())
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))
+    }
+
+    /// 添加供应商；若目标为当前供应商，则先写 live 成功后再更新内存
+    pub fn add_provider(&mut self, app_type: &AppType, provider: Provider) -> Result<(), String> {
+        self.upsert_provider(app_type, provider)
+    }
+
+    /// 更新已存在的供应商；若更新的是当前供应商，则先写 live 成功后再更新内存
+    pub fn update_provider(
+        &mut self,
+        app_type: &AppType,
+        provider: Provider,
+    ) -> Result<(), String> {
+        if !self.providers(app_type)?.contains_key(&provider.id) {
+            // 先校验配置结构，保持与添加时一致的错误优先级
+            validate_provider_settings(app_type, &resolve_provider(&provider)?)?;
+            return Err(format!("供应商不存在: {}", provider.id));
+        }
+        self.upsert_provider(app_type, provider)
+    }
+
+    fn upsert_provider(
+        &mut self,
+        app_type: &AppType,
+        mut provider: Provider,
+    ) -> Result<(), String> {
+        self.ensure_writable()?;
+        let resolved = resolve_provider(&provider)?;
+        validate_provider_settings(app_type, &resolved)?;
+
+        if self.current_provider_id(app_type)? == provider.id {
+            let manager = self
+                .config
+                .get_manager_mut(app_type)
+                .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+            write_live_for_edit(app_type, &resolved, manager)?;
+        }
+
+        // 明文密钥移入密钥库，内存中仅保留引用
+        secrets::extract_secrets(app_type, &provider.id, &mut provider.settings_config)?;
+
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+        let is_current = manager.current == provider.id;
+        manager.providers.insert(provider.id.clone(), provider);
+        // 当前供应商的 profile 已随 live 写入，其余供应商单独更新 profile
+        if *app_type == AppType::Codex && !is_current {
+            sync_codex_profiles(manager)?;
+        }
+        Ok(())
+    }
+
+    /// 删除供应商（不允许删除当前供应商），并清理历史副本文件
+    pub fn delete_provider(&mut self, app_type: &AppType, id: &str) -> Result<Provider, String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+        // 检查是否为当前供应商
+        if manager.current == id {
+            return Err("不能删除当前正在使用的供应商".to_string());
+        }
+        if manager
+            .temporary
+            .as_ref()
+            .is_some_and(|t| t.previous_provider_id == id)
+        {
+            return Err("不能删除临时切换到期后要恢复的供应商".to_string());
+        }
+
+        // 获取供应商信息
+        let provider = manager
+            .providers
+            .get(id)
+            .ok_or_else(|| format!("供应商不存在: {}", id))?
+            .clone();
+
+        // 删除配置文件
+        match app_type {
+            AppType::Codex => {
+                codex_config::delete_codex_provider_config(id, &provider.name)?;
+            }
+            AppType::Claude => {
+                use crate::config::{delete_file, get_provider_config_path};
+                // 兼容历史两种命名：settings-{name}.json 与 settings-{id}.json
+                let by_name = get_provider_config_path(id, Some(&provider.name));
+                let by_id = get_provider_config_path(id, None);
+                delete_file(&by_name)?;
+                delete_file(&by_id)?;
+            }
+            AppType::Droid => {
+                // Droid 配置存储在主配置文件中，不需要删除额外文件
+            }
+        }
+
+        manager.providers.remove(id);
+        if *app_type == AppType::Codex {
+            sync_codex_profiles(manager)?;
+        }
+        secrets::remove_provider_secrets(app_type, id)?;
+        Ok(provider)
+    }
+
+    /// 切换供应商：先回填 live 配置到当前供应商，再写入目标供应商并更新 current
+    pub fn switch_provider(&mut self, app_type: &AppType, id: &str) -> Result<Provider, String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+        // 检查供应商是否存在
+        let provider = manager
+            .providers
+            .get(id)
+            .ok_or_else(|| format!("供应商不存在: {}", id))?
+            .clone();
+        // 仅在写入 live 时解析密钥引用
+        let resolved = resolve_provider(&provider)?;
+
+        // 写入任何文件之前记录预写日志：进程在保存配置前退出时，下次启动据此回滚
+        if self.journal.is_some() {
+            return Err("上一次切换尚未保存，请稍后重试".to_string());
+        }
+        let mut paths = match app_type {
+            AppType::Claude => vec![get_claude_settings_path()],
+            AppType::Codex => vec![
+                codex_config::get_codex_auth_path(),
+                codex_config::get_codex_config_path(),
+            ],
+            AppType::Droid => crate::droid_config::factory_api_key_env_files()?,
+        };
+        paths.push(get_secrets_path());
+        let journal = SwitchJournal::begin(app_type, &manager.current, id, &paths)?;
+        let snapshot = manager.clone();
+        if let Err(e) = write_switch_live(app_type, manager, &resolved) {
+            // 写入中途失败：已写的文件与已回填的内存一并恢复
+            *manager = snapshot;
+            return Err(rollback_journal(journal, e));
+        }
+        self.journal = Some(journal);
+        // 任何切换（包括到期恢复）都结束进行中的临时切换
+        manager.temporary = None;
+
+        log::info!("成功切换到供应商: {}", provider.name);
+
+        Ok(provider)
+    }
+
+    /// 临时切换到 `id`，`duration` 后恢复为切换前的供应商
+    ///
+    /// 恢复时间记录在配置中，重启后仍然有效；已处于临时切换时沿用最初的恢复目标，
+    /// 只更新临时供应商与恢复时间。到期恢复由调用方按 [`Self::temporary_switches`] 执行
+    pub fn switch_provider_for(
+        &mut self,
+        app_type: &AppType,
+        id: &str,
+        duration: Duration,
+    ) -> Result<Provider, String> {
+        TemporarySwitch::validate_duration(duration)?;
+        let manager = self
+            .config
+            .get_manager(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+        let previous = manager
+            .temporary
+            .as_ref()
+            .map(|t| t.previous_provider_id.clone())
+            .unwrap_or_else(|| manager.current.clone());
+        if previous.is_empty() {
+            return Err("当前没有激活的供应商，请直接切换".to_string());
+        }
+        if previous == id {
+            return Err("目标供应商就是到期后要恢复的供应商，请直接切换".to_string());
+        }
+
+        let provider = self.switch_provider(app_type, id)?;
+        let temporary = TemporarySwitch::new(id, &previous, duration)?;
+        log::info!(
+            "临时切换到供应商 {}，{} 秒后恢复为 {}",
+            provider.name,
+            duration.as_secs(),
+            previous
+        );
+        if let Some(manager) = self.config.get_manager_mut(app_type) {
+            manager.temporary = Some(temporary);
+        }
+        Ok(provider)
+    }
+
+    /// 各应用进行中的临时切换（含已到恢复时间、尚未恢复的）
+    pub fn temporary_switches(&self) -> Vec<(AppType, TemporarySwitch)> {
+        [AppType::Claude, AppType::Codex, AppType::Droid]
+            .into_iter()
+            .filter_map(|app_type| {
+                let temporary = self.config.get_manager(&app_type)?.temporary.clone()?;
+                Some((app_type, temporary))
+            })
+            .collect()
+    }
+
+    /// 放弃临时切换（保留当前供应商，不再自动恢复）
+    pub fn clear_temporary_switch(&mut self, app_type: &AppType) -> Result<(), String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+        manager.temporary = None;
+        Ok(())
+    }
+
+    /// 按 profile 模式重新生成 Codex live config.toml 中的 profile（开关 profile 模式后调用）
+    pub fn sync_codex_profiles(&mut self) -> Result<(), String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(&AppType::Codex)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", AppType::Codex))?;
+        sync_codex_profiles(manager)
+    }
+
+    /// 切换到 `id` 前后要执行的钩子与连通性检查（见 [`SwitchPlan::execute`]）
+    ///
+    /// 界面、托盘与 CLI 的切换都经由此流程（界面通过 [`crate::store::AppState::switch_provider`]）
+    pub fn prepare_switch(&self, app_type: &AppType, id: &str) -> Result<SwitchPlan, String> {
+        let provider = self.provider(app_type, id)?;
+        let previous = self.current_provider_id(app_type)?;
+        let hooks = SwitchHookRun::prepare(app_type, provider, previous);
+        let preflight = match preflight::mode_for(app_type) {
+            PreflightMode::Off => None,
+            mode => Some(Preflight::new(mode, app_type, &resolve_provider(provider)?)),
+        };
+        Ok(SwitchPlan { hooks, preflight })
+    }
+
+    /// 按 `settings.json` 中的规则，`app_type`（None 表示全部应用）下一次计划中的切换
+    pub fn next_scheduled_switch(&self, app_type: Option<&AppType>) -> Option<ScheduledSwitch> {
+        let rules = crate::settings::AppSettings::load().switch_rules;
+        let mut next = schedule::next_switch(&rules, app_type, schedule::local_now())?;
+        next.provider_name = self
+            .provider(&next.app_type, &next.provider_id)
+            .ok()
+            .map(|p| p.name.clone());
+        Some(next)
+    }
+
+    /// 预演切换：计算切换到 `id` 时各 live 文件写入前后的内容与差异，不写入任何文件
+    pub fn preview_switch(&self, app_type: &AppType, id: &str) -> Result<SwitchPreview, String> {
+        let manager = self
+            .config
+            .get_manager(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+        let provider = manager
+            .providers
+            .get(id)
+            .ok_or_else(|| format!("供应商不存在: {}", id))?;
+        let resolved = resolve_provider(provider)?;
+
+        let mut files = Vec::new();
+        let mut notes = Vec::new();
+        match app_type {
+            AppType::Codex => {
+                // 与切换相同：先把 live 中的修改回填到当前供应商（只在副本上进行）
+                let mut manager = manager.clone();
+                backfill_codex_plain(&mut manager)?;
+                let auth = resolved
+                    .settings_config
+                    .get("auth")
+                    .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
+                let cfg_text =
+                    render_codex_live(&resolved, manager.providers.get(&manager.current))?;
+                let cfg_text = apply_codex_profiles(cfg_text, &mut manager, Some(&resolved))?;
+                let auth_text = serde_json::to_string_pretty(auth)
+                    .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
+                files.push(FilePreview::new(
+                    &codex_config::get_codex_auth_path(),
+                    FileFormat::Json,
+                    auth_text,
+                )?);
+                files.push(FilePreview::new(
+                    &codex_config::get_codex_config_path(),
+                    FileFormat::Toml,
+                    cfg_text,
+                )?);
+            }
+            AppType::Claude => {
+                let previous = ClaudeManaged::of(manager);
+                let (final_config, _) =
+                    render_claude_live(read_claude_live_or_empty(), Some(&resolved), &previous);
+                let after = serde_json::to_string_pretty(&final_config)
+                    .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
+                files.push(FilePreview::new(
+                    &get_claude_settings_path(),
+                    FileFormat::Json,
+                    after,
+                )?);
+            }
+            AppType::Droid => {
+                let api_key = resolved
+                    .settings_config
+                    .get("apiKey")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| "目标供应商缺少 apiKey 配置".to_string())?;
+                for (path, before, after) in crate::droid_config::plan_factory_api_key_env(api_key)?
+                {
+                    files.push(FilePreview::from_contents(
+                        &path,
+                        FileFormat::Text,
+                        true,
+                        before,
+                        after,
+                    ));
+                }
+                if cfg!(target_os = "windows") {
+                    notes.push(
+                        "将写入用户环境变量 Factory_API_Key（注册表），不涉及文件".to_string(),
+                    );
+                } else if files.is_empty() {
+                    notes.push(
+                        "未找到 ~/.bashrc、~/.zshrc 或 ~/.profile，切换不会写入任何文件"
+                            .to_string(),
+                    );
+                }
+            }
+        }
+
+        Ok(SwitchPreview {
+            app_type: app_type.as_str().to_string(),
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            files,
+            notes,
+        })
+    }
+
+    /// 停用当前供应商（清理 live 配置并清空 current）
+    pub fn disable_current_provider(&mut self, app_type: &AppType) -> Result<(), String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+        match app_type {
+            AppType::Claude => {
+                // 只移除当前供应商写入的 env 键与受管字段，用户自有的配置保持不变
+                let previous = ClaudeManaged::of(manager);
+                write_claude_live(None, &previous)?.store(manager);
+
+                log::info!("已停用 Claude 供应商，已移除其写入的配置");
+            }
+            AppType::Codex => {
+                disable_codex_live()?;
+                log::info!("已停用 Codex 供应商，已清除 API Key 与 model_provider");
+            }
+            AppType::Droid => {
+                // 清除系统环境变量
+                crate::droid_config::clear_factory_api_key_env()?;
+                log::info!("已停用 Droid 供应商，环境变量已清除");
+            }
+        }
+
+        // 清空当前供应商
+        manager.current = String::new();
+        manager.temporary = None;
+
+        Ok(())
+    }
+
+    /// 切换当前供应商的 API 地址（须在供应商的备选地址中）：Claude 修改 `env.ANTHROPIC_BASE_URL`，
+    /// Codex 修改 `model_provider` 指向的 `[model_providers.*]` 中的 `base_url`
+    pub fn switch_provider_url(&mut self, app_type: &AppType, url: &str) -> Result<(), String> {
+        self.ensure_writable()?;
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+        // 获取当前供应商
+        if manager.current.is_empty() {
+            return Err("当前没有激活的供应商".to_string());
+        }
+
+        let provider = manager
+            .providers
+            .get(&manager.current)
+            .ok_or_else(|| "当前供应商不存在".to_string())?;
+
+        // 验证 URL 是否在备选列表中
+        match provider.alternative_urls {
+            Some(ref alt_urls) if !alt_urls.iter().any(|u| u == url) => {
+                return Err(format!("地址 {} 不在供应商的备选列表中", url));
+            }
+            Some(_) => {}
+            None => return Err("当前供应商未配置备选地址".to_string()),
+        }
+
+        match app_type {
+            AppType::Claude => switch_claude_url(manager, url)?,
+            AppType::Codex => switch_codex_url(manager, url)?,
+            AppType::Droid => return Err("Droid 不支持切换 API 地址".to_string()),
+        }
+
+        log::info!("已切换当前供应商的 API 地址到: {}", url);
+        Ok(())
+    }
+
+    /// 切换到供应商 `id` 并使用其备选地址 `url`（托盘端点菜单）；供应商已是当前时只切换地址
+    ///
+    /// 应在同一次 [`Self::modify`] 中调用：地址切换失败时，供应商切换随之回滚
+    pub fn switch_provider_with_url(
+        &mut self,
+        app_type: &AppType,
+        id: &str,
+        url: &str,
+    ) -> Result<Provider, String> {
+        // 先校验地址，避免切换供应商后才发现地址无效
+        match self.provider(app_type, id)?.alternative_urls {
+            Some(ref alt_urls) if !alt_urls.iter().any(|u| u == url) => {
+                return Err(format!("地址 {} 不在供应商的备选列表中", url));
+            }
+            Some(_) => {}
+            None => return Err("该供应商未配置备选地址".to_string()),
+        }
+        if self.current_provider_id(app_type)? != id {
+            self.switch_provider(app_type, id)?;
+        }
+        self.switch_provider_url(app_type, url)?;
+        self.provider(app_type, id).cloned()
+    }
+
+    /// 当该应用没有任何供应商时，从 live 配置导入一条 `default` 供应商并设为当前
+    ///
+    /// 返回是否实际导入
+    pub fn import_default_config(&mut self, app_type: &AppType) -> Result<bool, String> {
+        self.ensure_writable()?;
+        // 仅当 providers 为空时才从 live 导入一条默认项
+        if let Some(manager) = self.config.get_manager(app_type) {
+            if !manager.get_all_providers().is_empty() {
+                return Ok(false);
+            }
+        }
+
+        // 读取当前主配置为默认供应商（不再写入副本文件）
+        let settings_config = match app_type {
+            AppType::Codex => {
+                let auth_path = codex_config::get_codex_auth_path();
+                if !auth_path.exists() {
+                    return Err("Codex 配置文件不存在".to_string());
+                }
+                let auth: Value = read_json_file::<Value>(&auth_path)?;
+                let config_str = codex_config::read_and_validate_codex_config_text()?;
+                json!({ "auth": auth, "config": config_str })
+            }
+            AppType::Claude => {
+                let settings_path = get_claude_settings_path();
+                if !settings_path.exists() {
+                    return Err("Claude Code 配置文件不存在".to_string());
+                }
+                let full_config = read_json_file::<Value>(&settings_path)?;
+
+                // 只提取 env 字段；两种凭据都有时只保留 AUTH_TOKEN，否则导入后无法编辑
+                let mut env = full_config.get("env").cloned().unwrap_or(json!({}));
+                ClaudeCredentialKind::retain_one(&mut env, None);
+                json!({ "env": env })
+            }
+            AppType::Droid => {
+                return Err("Droid 不支持自动导入默认配置，请手动添加 API Key".to_string());
+            }
+        };
+
+        // 创建默认供应商（仅首次初始化），密钥移入密钥库
+        let mut settings_config = settings_config;
+        secrets::extract_secrets(app_type, "default", &mut settings_config)?;
+        let provider = Provider::with_id(
+            "default".to_string(),
+            "default".to_string(),
+            settings_config,
+            None,
+        );
+
+        let manager = self
+            .config
+            .get_manager_mut(app_type)
+            .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+        manager.providers.insert(provider.id.clone(), provider);
+        // 设置当前供应商为默认项
+        manager.current = "default".to_string();
+
+        Ok(true)
+    }
+
+    /// 从 live 配置回填当前供应商（Claude / Codex；Droid 无需回填）
+    pub fn sync_current_provider_config(&mut self, app_type: &AppType) -> Result<(), String> {
+        self.ensure_writable()?;
+        match app_type {
+            AppType::Claude => {
+                let settings_path = get_claude_settings_path();
+                if !settings_path.exists() {
+                    return Err("Claude Code 配置文件不存在".to_string());
+                }
+
+                // 读取 live settings.json
+                let live_config = read_json_file::<Value>(&settings_path)?;
+
+                let manager = self
+                    .config
+                    .get_manager_mut(app_type)
+                    .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+
+                if !manager.current.is_empty() {
+                    let keys = managed_env_keys(manager);
+                    if let Some(current_provider) = manager.providers.get_mut(&manager.current) {
+                        // 只同步 cc-switch 管理的 env 字段与声明的顶层字段，用户自有的配置不归入供应商
+                        backfill_claude(current_provider, &live_config, &keys);
+                        secrets::extract_secrets(
+                            app_type,
+                            &manager.current,
+                            &mut current_provider.settings_config,
+                        )?;
+                        log::info!("已同步当前供应商 '{}' 的配置", current_provider.name);
+                    }
+                }
+                Ok(())
+            }
+            AppType::Codex => {
+                if !codex_config::get_codex_auth_path().exists() {
+                    return Err("Codex 配置文件不存在".to_string());
+                }
+                let manager = self
+                    .config
+                    .get_manager_mut(app_type)
+                    .ok_or_else(|| format!("应用类型不存在: {:?}", app_type))?;
+                // 与切换前的回填相同
+                if backfill_codex(manager)? {
+                    log::info!("已同步当前供应商 '{}' 的配置", manager.current);
+                }
+                Ok(())
+            }
+            AppType::Droid => {
+                // Droid 不需要同步逻辑，配置存储在主配置文件中
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 校验供应商配置结构
+pub(crate) fn validate_provider_settings(
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<(), String> {
+    match app_type {
+        AppType::Claude => {
+            if !provider.settings_config.is_object() {
+                return Err("Claude 配置必须是 JSON 对象".to_string());
+            }
+            let env = provider
+                .settings_config
+                .get("env")
+                .cloned()
+                .unwrap_or(Value::Null);
+            if let Some(paths) = &provider.managed_paths {
+                if paths.iter().any(|p| p.trim() == "env") {
+                    return Err("managedPaths 不能包含 env（env 按合并策略单独处理）".to_string());
+                }
+            }
+            if ClaudeCredentialKind::ALL.iter().all(|k| k.is_set(&env)) {
+                return Err(
+                    "ANTHROPIC_AUTH_TOKEN 与 ANTHROPIC_API_KEY 只能填写其中一个".to_string()
+                );
+            }
+            if let (Some(expected), Some(found)) = (
+                provider.credential_kind,
+                ClaudeCredentialKind::detect(&provider.settings_config),
+            ) {
+                if expected != found {
+                    return Err(format!(
+                        "凭据类型为 {}，但配置中填写的是 {}",
+                        expected.env_key(),
+                        found.env_key()
+                    ));
+                }
+            }
+        }
+        AppType::Codex => {
+            let settings = provider
+                .settings_config
+                .as_object()
+                .ok_or_else(|| "Codex 配置必须是 JSON 对象".to_string())?;
+            let auth = settings
+                .get("auth")
+                .ok_or_else(|| "Codex 配置缺少 auth 字段".to_string())?;
+            if !auth.is_object() {
+                return Err("Codex auth 配置必须是 JSON 对象".to_string());
+            }
+            if let Some(config_value) = settings.get("config") {
+                if !(config_value.is_string() || config_value.is_null()) {
+                    return Err("Codex config 字段必须是字符串".to_string());
+                }
+                if let Some(cfg_text) = config_value.as_str() {
+                    match provider.codex_config_mode() {
+                        CodexConfigMode::Full => codex_config::validate_config_toml(cfg_text)?,
+                        CodexConfigMode::Section => {
+                            codex_config::validate_section_config(cfg_text)?
+                        }
+                    }
+                }
+            }
+        }
+        AppType::Droid => {
+            let settings = provider
+                .settings_config
+                .as_object()
+                .ok_or_else(|| "Droid 配置必须是 JSON 对象".to_string())?;
+            let api_key = settings
+                .get("apiKey")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "Droid 配置缺少 apiKey 字段".to_string())?;
+            if !api_key.starts_with("fk-") {
+                return Err("Droid API Key 格式错误，应以 fk- 开头".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Codex 供应商 `config` 字段的文本（缺省为空）
+fn codex_config_text(provider: &Provider) -> &str {
+    provider
+        .settings_config
+        .get("config")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+/// Codex 写入 live 的 config.toml 全文：完整模式替换为供应商的配置，分段模式合并进现有文件
+///
+/// `previous` 为切换前（或编辑前）的供应商，分段模式下移除其定义的 `[model_providers.*]`
+fn render_codex_live(provider: &Provider, previous: Option<&Provider>) -> Result<String, String> {
+    match provider.codex_config_mode() {
+        CodexConfigMode::Full => codex_config::render_codex_config(codex_config_text(provider)),
+        CodexConfigMode::Section => {
+            let previous_section = previous
+                .filter(|p| p.codex_config_mode() == CodexConfigMode::Section)
+                .map(codex_config_text);
+            codex_config::render_codex_section_config(codex_config_text(provider), previous_section)
+        }
+    }
+}
+
+/// profile 模式：在要写入的 config.toml 中生成全部 Codex 供应商的 profile，并记录生成的 id
+///
+/// `overrides` 为正在写入、尚未保存到 `manager` 的供应商；模式关闭时只清理之前生成的表
+fn apply_codex_profiles(
+    cfg_text: String,
+    manager: &mut ProviderManager,
+    overrides: Option<&Provider>,
+) -> Result<String, String> {
+    let enabled = crate::settings::AppSettings::load().codex_profiles;
+    if !enabled && manager.codex_profile_ids.is_empty() {
+        return Ok(cfg_text);
+    }
+    let mut providers = Vec::new();
+    if enabled {
+        for provider in manager.providers.values() {
+            if overrides.is_none_or(|o| o.id != provider.id) {
+                // 只有明文写入 token 的供应商才需要解析密钥
+                providers.push(if provider.codex_inline_token() {
+                    resolve_provider(provider)?
+                } else {
+                    provider.clone()
+                });
+            }
+        }
+        providers.extend(overrides.cloned());
+        providers.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+    }
+    let (text, ids) =
+        codex_config::render_codex_profiles(&cfg_text, &providers, &manager.codex_profile_ids)?;
+    manager.codex_profile_ids = ids;
+    Ok(text)
+}
+
+/// 只更新 live config.toml 中的 profile（非当前供应商增删改或切换 profile 模式后调用）
+fn sync_codex_profiles(manager: &mut ProviderManager) -> Result<(), String> {
+    let live = codex_config::read_codex_config_text()?;
+    let text = apply_codex_profiles(live.clone(), manager, None)?;
+    if text != live {
+        crate::config::write_text_file(&codex_config::get_codex_config_path(), &text)?;
+    }
+    Ok(())
+}
+
+/// Claude：只更新 live 与当前供应商的 `env.ANTHROPIC_BASE_URL`
+fn switch_claude_url(manager: &mut ProviderManager, url: &str) -> Result<(), String> {
+    let settings_path = get_claude_settings_path();
+
+    // 读取现有配置
+    let mut final_config = read_claude_live_or_empty();
+
+    // 只更新 env.ANTHROPIC_BASE_URL
+    if let Some(config_obj) = final_config.as_object_mut() {
+        let env = config_obj.entry("env").or_insert(json!({}));
+        if let Some(env_obj) = env.as_object_mut() {
+            env_obj.insert(
+                "ANTHROPIC_BASE_URL".to_string(),
+                Value::String(url.to_string()),
+            );
+        }
+    }
+
+    // 写入配置
+    write_json_file(&settings_path, &final_config)?;
+
+    // 切换后的地址同样属于供应商写入的键
+    let mut keys = managed_env_keys(manager);
+    if !keys.iter().any(|k| k == "ANTHROPIC_BASE_URL") {
+        keys.push("ANTHROPIC_BASE_URL".to_string());
+        keys.sort();
+    }
+    manager.managed_env_keys = keys;
+
+    // 同时更新内存中的供应商配置
+    if let Some(cur) = manager.providers.get_mut(&manager.current) {
+        if let Some(provider_env) = cur.settings_config.get_mut("env") {
+            if let Some(env_obj) = provider_env.as_object_mut() {
+                env_obj.insert(
+                    "ANTHROPIC_BASE_URL".to_string(),
+                    Value::String(url.to_string()),
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Codex：只更新 live config.toml 与当前供应商配置中的 `base_url`，auth.json 不变
+fn switch_codex_url(manager: &mut ProviderManager, url: &str) -> Result<(), String> {
+    let live = codex_config::read_codex_config_text()?;
+    let text = codex_config::set_provider_base_url(&live, url)?;
+
+    // 供应商配置中 model_provider 的名称可能与 live 不同（如手动修改过），两边各自定位
+    if let Some(cur) = manager.providers.get_mut(&manager.current) {
+        let updated = codex_config::set_provider_base_url(codex_config_text(cur), url)?;
+        if let Some(obj) = cur.settings_config.as_object_mut() {
+            obj.insert("config".to_string(), Value::String(updated));
+        }
+    }
+    // profile 模式下当前供应商的 profile 同样指向新地址
+    let text = apply_codex_profiles(text, manager, None)?;
+    crate::config::write_text_file(&codex_config::get_codex_config_path(), &text)
+}
+
+/// 解析供应商中的密钥引用，返回可写入 live 文件的副本
+fn resolve_provider(provider: &Provider) -> Result<Provider, String> {
+    let mut resolved = provider.clone();
+    resolved.settings_config = secrets::resolve_secrets(&provider.settings_config)?;
+    Ok(resolved)
+}
+
+/// 当前由 cc-switch 管理的 Claude env 键
+///
+/// 旧配置没有记录时，以当前供应商自身 env 中的键为准
+fn managed_env_keys(manager: &ProviderManager) -> Vec<String> {
+    if !manager.managed_env_keys.is_empty() {
+        return manager.managed_env_keys.clone();
+    }
+    let mut keys: Vec<String> = manager
+        .providers
+        .get(&manager.current)
+        .and_then(|p| p.settings_config.get("env"))
+        .and_then(|env| env.as_object())
+        .map(|env| env.keys().cloned().collect())
+        .unwrap_or_default();
+    keys.sort();
+    keys
+}
+
+/// 将 live settings.json 回填到供应商：env 取受管键与两种凭据字段，并据此更新凭据类型；
+/// 供应商声明的顶层字段取 live 中的值（live 中不存在则从供应商移除），其余字段保持不变
+///
+/// live 中同时存在两种凭据时只保留供应商原有的类型
+fn backfill_claude(provider: &mut Provider, live: &Value, managed_keys: &[String]) {
+    let kind = provider.claude_credential_kind();
+    let mut keys = managed_keys.to_vec();
+    for credential in ClaudeCredentialKind::ALL {
+        if !keys.iter().any(|k| k == credential.env_key()) {
+            keys.push(credential.env_key().to_string());
+        }
+    }
+    let mut env = pick_env_keys(live.get("env").unwrap_or(&Value::Null), &keys);
+    if ClaudeCredentialKind::ALL.iter().all(|k| k.is_set(&env)) {
+        let keep = kind.unwrap_or(ClaudeCredentialKind::AuthToken);
+        if let Some(env_obj) = env.as_object_mut() {
+            env_obj.remove(keep.other().env_key());
+        }
+    }
+
+    let paths = provider.claude_managed_paths();
+    if !provider.settings_config.is_object() {
+        provider.settings_config = json!({});
+    }
+    if let Some(settings) = provider.settings_config.as_object_mut() {
+        settings.insert("env".to_string(), env);
+        for path in paths {
+            match live.get(&path) {
+                Some(value) => settings.insert(path, value.clone()),
+                None => settings.remove(&path),
+            };
+        }
+    }
+    provider.credential_kind = ClaudeCredentialKind::detect(&provider.settings_config).or(kind);
+}
+
+/// 从 live env 中取出指定键（用于回填，只保留 live 中仍存在的键）
+fn pick_env_keys(env: &Value, keys: &[String]) -> Value {
+    let picked: serde_json::Map<String, Value> = keys
+        .iter()
+        .filter_map(|k| env.get(k).map(|v| (k.clone(), v.clone())))
+        .collect();
+    Value::Object(picked)
+}
+
+/// env 合并策略：移除上一个供应商写入的键，再写入 `provider_env` 的全部键，其余键保持不变
+///
+/// 返回新的受管键列表
+fn merge_managed_env(
+    live: &mut Value,
+    provider_env: &Value,
+    previous_keys: &[String],
+) -> Vec<String> {
+    if !live.is_object() {
+        *live = json!({});
+    }
+    if let Some(config_obj) = live.as_object_mut() {
+        let env = config_obj.entry("env").or_insert_with(|| json!({}));
+        if !env.is_object() {
+            *env = json!({});
+        }
+        if let Some(env_obj) = env.as_object_mut() {
+            for key in previous_keys {
+                env_obj.remove(key);
+            }
+            if let Some(provider_env) = provider_env.as_object() {
+                for (key, value) in provider_env {
+                    env_obj.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    let mut managed: Vec<String> = provider_env
+        .as_object()
+        .map(|env| env.keys().cloned().collect())
+        .unwrap_or_default();
+    managed.sort();
+    managed
+}
+
+/// Claude live settings.json 中由 cc-switch 写入的部分
+struct ClaudeManaged {
+    env_keys: Vec<String>,
+    paths: Vec<String>,
+}
+
+impl ClaudeManaged {
+    /// 当前记录的受管部分（旧配置未记录时以当前供应商自身的声明为准）
+    fn of(manager: &ProviderManager) -> Self {
+        let paths = if manager.managed_settings_paths.is_empty() {
+            manager
+                .providers
+                .get(&manager.current)
+                .map(|p| p.claude_managed_paths())
+                .unwrap_or_default()
+        } else {
+            manager.managed_settings_paths.clone()
+        };
+        Self {
+            env_keys: managed_env_keys(manager),
+            paths,
+        }
+    }
+
+    fn store(self, manager: &mut ProviderManager) {
+        manager.managed_env_keys = self.env_keys;
+        manager.managed_settings_paths = self.paths;
+    }
+}
+
+/// 回填当前供应商并把目标供应商写入 live 配置，最后更新当前供应商
+fn write_switch_live(
+    app_type: &AppType,
+    manager: &mut ProviderManager,
+    resolved: &Provider,
+) -> Result<(), String> {
+    // SSOT 切换：先回填 live 配置到当前供应商，然后从内存写入目标主配置
+    match app_type {
+        AppType::Codex => {
+            // 回填：读取 live（auth.json + config.toml）写回当前供应商 settings_config
+            backfill_codex(manager)?;
+
+            // 切换：从目标供应商 settings_config 写入主配置（Codex 双文件原子+回滚）
+            let auth = resolved
+                .settings_config
+                .get("auth")
+                .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
+            let cfg_text = render_codex_live(resolved, manager.providers.get(&manager.current))?;
+            let cfg_text = apply_codex_profiles(cfg_text, manager, Some(resolved))?;
+            codex_config::write_codex_live_atomic(auth, &cfg_text)?;
+        }
+        AppType::Claude => {
+            let settings_path = get_claude_settings_path();
+            let previous = ClaudeManaged::of(manager);
+
+            // 回填：只回填 cc-switch 管理的 env 键（包含模型配置）与供应商声明的顶层字段，
+            // 用户自行添加的键不归入供应商
+            if settings_path.exists() && !manager.current.is_empty() {
+                if let Ok(live) = read_json_file::<Value>(&settings_path) {
+                    if let Some(cur) = manager.providers.get_mut(&manager.current) {
+                        backfill_claude(cur, &live, &previous.env_keys);
+                        // 回填的密钥写回密钥库，不内联到配置
+                        secrets::extract_secrets(
+                            app_type,
+                            &manager.current,
+                            &mut cur.settings_config,
+                        )?;
+                    }
+                }
+            }
+
+            // 切换：写入目标供应商的全部 env 键与受管字段，只移除上一个供应商写入的部分，保留其他用户配置
+            write_claude_live(Some(resolved), &previous)?.store(manager);
+        }
+        AppType::Droid => {
+            // Droid: 设置系统环境变量
+            let api_key = resolved
+                .settings_config
+                .get("apiKey")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "目标供应商缺少 apiKey 配置".to_string())?;
+
+            crate::droid_config::set_factory_api_key_env(api_key)?;
+        }
+    }
+
+    // 更新当前供应商
+    manager.current = resolved.id.clone();
+    Ok(())
+}
+
+/// 回滚切换写入的 live 文件并返回原始错误；回滚本身失败时记录日志并附在原始错误之后
+fn rollback_journal(journal: SwitchJournal, error: String) -> String {
+    match journal.rollback() {
+        Ok(()) => error,
+        Err(rollback_error) => {
+            log::error!("回滚切换失败: {}", rollback_error);
+            format!("{}（回滚 live 文件失败: {}）", error, rollback_error)
+        }
+    }
+}
+
+/// 回填 Codex：读取 live auth.json 与 config.toml 写回当前供应商，返回是否回填
+///
+/// 回填的密钥写回密钥库，不内联到配置
+fn backfill_codex(manager: &mut ProviderManager) -> Result<bool, String> {
+    if !backfill_codex_plain(manager)? {
+        return Ok(false);
+    }
+    let Some(cur) = manager.providers.get_mut(&manager.current) else {
+        return Ok(false);
+    };
+    secrets::extract_secrets(&AppType::Codex, &manager.current, &mut cur.settings_config)?;
+    Ok(true)
+}
+
+/// 回填 Codex 但保留明文密钥、不写入任何文件，供预演在配置副本上复现切换的回填步骤
+///
+/// 分段模式只回填片段中的几项，profile 模式生成的表不归入供应商；auth.json 不存在时不回填
+fn backfill_codex_plain(manager: &mut ProviderManager) -> Result<bool, String> {
+    let auth_path = codex_config::get_codex_auth_path();
+    if manager.current.is_empty() || !auth_path.exists() {
+        return Ok(false);
+    }
+    let auth: Value = read_json_file(&auth_path)?;
+    let config_str = codex_config::read_codex_config_text()?;
+
+    let Some(cur) = manager.providers.get_mut(&manager.current) else {
+        return Ok(false);
+    };
+    let config_str = match cur.codex_config_mode() {
+        // profile 模式生成的表不属于当前供应商
+        CodexConfigMode::Full => {
+            codex_config::strip_codex_profiles(&config_str, &manager.codex_profile_ids)
+        }
+        // 分段模式只回填片段中的几项，其余内容属于 live 文件本身
+        CodexConfigMode::Section => {
+            codex_config::extract_section_config(&config_str, codex_config_text(cur))?
+        }
+    };
+    cur.settings_config = json!({
+        "auth": auth,
+        "config": config_str,
+    });
+    Ok(true)
+}
+
+/// 停用 Codex：先把 live auth.json 与 config.toml 归档到 `~/.cc-switch/archive/<ts>/codex/`，
+/// 再清空 `OPENAI_API_KEY` 并移除顶层 `model_provider`（其余内容保持不变）
+///
+/// 之后切换到任意供应商即恢复
+fn disable_codex_live() -> Result<(), String> {
+    let auth_path = codex_config::get_codex_auth_path();
+    let config_path = codex_config::get_codex_config_path();
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    for path in [&auth_path, &config_path] {
+        if let Some(dest) = crate::config::archive_file(ts, "codex", path)? {
+            log::info!("已归档 {} -> {}", path.display(), dest.display());
+        }
+    }
+
+    let mut auth: Value = if auth_path.exists() {
+        read_json_file(&auth_path)?
+    } else {
+        json!({})
+    };
+    if let Some(auth_obj) = auth.as_object_mut() {
+        auth_obj.insert("OPENAI_API_KEY".to_string(), Value::Null);
+    }
+    let cfg_text = codex_config::remove_model_provider(&codex_config::read_codex_config_text()?)?;
+    codex_config::write_codex_live_atomic(&auth, &cfg_text)
+}
+
+/// 写入 Claude live settings.json（内容见 [`render_claude_live`]），返回新的受管部分
+fn write_claude_live(
+    target: Option<&Provider>,
+    previous: &ClaudeManaged,
+) -> Result<ClaudeManaged, String> {
+    let (final_config, managed) = render_claude_live(read_claude_live_or_empty(), target, previous);
+    write_json_file(&get_claude_settings_path(), &final_config)?;
+    Ok(managed)
+}
+
+/// 计算写入后的 Claude live settings.json：移除 `previous` 记录的 env 键与顶层字段，
+/// 再写入 `target` 的全部 env 键与声明的顶层字段（`None` 表示停用）
+///
+/// 目标供应商的另一种凭据字段无论是否受管都会被移除，避免两种请求头同时发送
+fn render_claude_live(
+    mut final_config: Value,
+    target: Option<&Provider>,
+    previous: &ClaudeManaged,
+) -> (Value, ClaudeManaged) {
+    let provider_env = target
+        .and_then(|p| p.settings_config.get("env"))
+        .cloned()
+        .unwrap_or_else(|| json!({}));
+    let env_keys = merge_managed_env(&mut final_config, &provider_env, &previous.env_keys);
+    if let Some(kind) = target.and_then(|p| p.claude_credential_kind()) {
+        if let Some(env_obj) = final_config.get_mut("env").and_then(|v| v.as_object_mut()) {
+            env_obj.remove(kind.other().env_key());
+        }
+    }
+
+    let paths = target.map(|p| p.claude_managed_paths()).unwrap_or_default();
+    if let Some(config_obj) = final_config.as_object_mut() {
+        for path in &previous.paths {
+            config_obj.remove(path);
+        }
+        if let Some(target) = target {
+            for path in &paths {
+                if let Some(value) = target.settings_config.get(path) {
+                    config_obj.insert(path.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    (final_config, ClaudeManaged { env_keys, paths })
+}
+
+/// 读取 Claude live settings.json，不存在或解析失败时返回空对象
+fn read_claude_live_or_empty() -> Value {
+    let settings_path = get_claude_settings_path();
+    if settings_path.exists() {
+        read_json_file::<Value>(&settings_path).unwrap_or(json!({}))
+    } else {
+        json!({})
+    }
+}
+
+/// 编辑当前供应商时写入 live 配置（Claude 写入全部 env 键与受管字段）
+fn write_live_for_edit(
+    app_type: &AppType,
+    provider: &Provider,
+    manager: &mut ProviderManager,
+) -> Result<(), String> {
+    match app_type {
+        AppType::Claude => {
+            let previous = ClaudeManaged::of(manager);
+            write_claude_live(Some(provider), &previous)?.store(manager);
+        }
+        AppType::Codex => {
+            let auth = provider
+                .settings_config
+                .get("auth")
+                .ok_or_else(|| "目标供应商缺少 auth 配置".to_string())?;
+            let cfg_text = render_codex_live(provider, manager.providers.get(&provider.id))?;
+            let cfg_text = apply_codex_profiles(cfg_text, manager, Some(provider))?;
+            codex_config::write_codex_live_atomic(auth, &cfg_text)?;
+        }
+        AppType::Droid => {
+            // Droid: 设置环境变量
+            let api_key = provider
+                .settings_config
+                .get("apiKey")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| "Droid 配置缺少 apiKey 字段".to_string())?;
+            crate::droid_config::set_factory_api_key_env(api_key)?;
+        }
+    }
+    Ok(())
+}
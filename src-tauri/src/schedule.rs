@@ -0,0 +1,271 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+
+/// 查找下一次计划切换时最多向后查看的天数（覆盖按月规则的一个完整周期）
+const LOOKAHEAD_DAYS: i64 = 62;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_at() -> String {
+    "00:00".to_string()
+}
+
+/// 星期（`mon` … `sun`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Day {
+    fn weekday(&self) -> Weekday {
+        match self {
+            Day::Mon => Weekday::Mon,
+            Day::Tue => Weekday::Tue,
+            Day::Wed => Weekday::Wed,
+            Day::Thu => Weekday::Thu,
+            Day::Fri => Weekday::Fri,
+            Day::Sat => Weekday::Sat,
+            Day::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// 规则的触发时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RuleSchedule {
+    /// 每天（或 `days` 指定的星期）`start` 切换到规则的供应商，`end` 切换到 `otherwise`（可选）
+    ///
+    /// `end` 早于 `start` 时表示跨越午夜（如 22:00–06:00），`days` 指开始那一天
+    #[serde(rename_all = "camelCase")]
+    TimeWindow {
+        /// 为空表示每天
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        days: Vec<Day>,
+        start: String,
+        end: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        otherwise: Option<String>,
+    },
+    /// 每月第 `day` 天的 `at`（默认 00:00）切换；当月天数不足时取最后一天
+    #[serde(rename_all = "camelCase")]
+    MonthDay {
+        day: u32,
+        #[serde(default = "default_at")]
+        at: String,
+    },
+}
+
+/// 一条自动切换规则（`settings.json` 的 `switchRules`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchRule {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub app_type: AppType,
+    pub provider_id: String,
+    #[serde(flatten)]
+    pub schedule: RuleSchedule,
+}
+
+/// 一次计划中的切换
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledSwitch {
+    pub app_type: AppType,
+    pub provider_id: String,
+    /// 供应商不存在时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_name: Option<String>,
+    /// 触发时间（Unix 毫秒）
+    pub at: i64,
+    /// 触发时间（本地时间，`YYYY-MM-DD HH:MM`）
+    pub at_local: String,
+    /// 规则在 `switchRules` 中的下标
+    pub rule_index: usize,
+}
+
+fn parse_time(raw: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(raw.trim(), "%H:%M").ok()
+}
+
+/// `date` 所在月份的第 `day` 天（超出当月天数时取最后一天）
+fn month_day(date: NaiveDate, day: u32) -> Option<NaiveDate> {
+    let first = date.with_day(1)?;
+    let next_month = first.checked_add_months(chrono::Months::new(1))?;
+    let last = next_month.pred_opt()?.day();
+    first.with_day(day.clamp(1, last))
+}
+
+impl SwitchRule {
+    /// 以 `date` 为起点的切换时刻及目标供应商；时间格式无效时返回 None
+    fn transitions_on(&self, date: NaiveDate) -> Option<Vec<(NaiveDateTime, &str)>> {
+        match &self.schedule {
+            RuleSchedule::TimeWindow {
+                days,
+                start,
+                end,
+                otherwise,
+            } => {
+                let (start, end) = (parse_time(start)?, parse_time(end)?);
+                if !days.is_empty() && !days.iter().any(|d| d.weekday() == date.weekday()) {
+                    return Some(Vec::new());
+                }
+                let mut out = vec![(date.and_time(start), self.provider_id.as_str())];
+                if let Some(otherwise) = otherwise {
+                    let end_date = if end > start { date } else { date.succ_opt()? };
+                    out.push((end_date.and_time(end), otherwise.as_str()));
+                }
+                Some(out)
+            }
+            RuleSchedule::MonthDay { day, at } => {
+                let at = parse_time(at)?;
+                if month_day(date, *day)? != date {
+                    return Some(Vec::new());
+                }
+                Some(vec![(date.and_time(at), self.provider_id.as_str())])
+            }
+        }
+    }
+
+    /// `(from, to]` 内的全部切换时刻
+    fn transitions_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Vec<(NaiveDateTime, &str)> {
+        let mut out = Vec::new();
+        // 前一天开始的跨午夜时段可能在 `from` 之后结束
+        let mut date = from.date() - Duration::days(1);
+        while date <= to.date() {
+            let Some(items) = self.transitions_on(date) else {
+                log::warn!("自动切换规则的时间格式无效（应为 HH:MM）: {:?}", self);
+                return Vec::new();
+            };
+            out.extend(items.into_iter().filter(|(t, _)| *t > from && *t <= to));
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        out
+    }
+
+    /// 当前时刻规则期望的供应商：时段规则在时段内为规则的供应商，时段外为 `otherwise`
+    ///
+    /// 按月规则只在触发时刻生效，没有持续状态
+    fn desired_at(&self, now: NaiveDateTime) -> Option<&str> {
+        let RuleSchedule::TimeWindow {
+            days,
+            start,
+            end,
+            otherwise,
+        } = &self.schedule
+        else {
+            return None;
+        };
+        let (start, end) = (parse_time(start)?, parse_time(end)?);
+        for date in [now.date() - Duration::days(1), now.date()] {
+            if !days.is_empty() && !days.iter().any(|d| d.weekday() == date.weekday()) {
+                continue;
+            }
+            let end_date = if end > start { date } else { date.succ_opt()? };
+            if date.and_time(start) <= now && now < end_date.and_time(end) {
+                return Some(self.provider_id.as_str());
+            }
+        }
+        otherwise.as_deref()
+    }
+}
+
+/// 到达的切换：应用、时刻、规则下标与目标供应商
+type Due = (AppType, NaiveDateTime, usize, String);
+
+/// 每个应用只保留最晚（同一时刻取下标最大）的切换
+fn keep_latest(due: &mut Vec<Due>, item: Due) {
+    match due.iter_mut().find(|d| d.0 == item.0) {
+        Some(entry) if (item.1, item.2) >= (entry.1, entry.2) => *entry = item,
+        Some(_) => {}
+        None => due.push(item),
+    }
+}
+
+/// 评估规则的状态：记录上次检查的时刻，找出期间到达的切换
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    last_check: Option<NaiveDateTime>,
+}
+
+impl Scheduler {
+    /// 返回 `now` 时应切换到的供应商（每个应用至多一个，同一时刻多条规则以靠后的为准）
+    ///
+    /// 首次调用时按时段规则的当前状态返回（启动时即生效）；之后只在到达切换时刻时返回，
+    /// 因此时段内的手动切换会保留到下一个切换时刻
+    pub fn tick(&mut self, rules: &[SwitchRule], now: NaiveDateTime) -> Vec<(AppType, String)> {
+        let mut due: Vec<Due> = Vec::new();
+        for (index, rule) in rules.iter().enumerate().filter(|(_, r)| r.enabled) {
+            match self.last_check {
+                None => {
+                    if let Some(id) = rule.desired_at(now) {
+                        keep_latest(&mut due, (rule.app_type, now, index, id.to_string()));
+                    }
+                }
+                Some(last) => {
+                    for (at, id) in rule.transitions_between(last, now) {
+                        keep_latest(&mut due, (rule.app_type, at, index, id.to_string()));
+                    }
+                }
+            }
+        }
+        self.last_check = Some(now);
+
+        due.into_iter().map(|(app, _, _, id)| (app, id)).collect()
+    }
+}
+
+/// 当前本地时间
+pub fn local_now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+/// `now` 之后最近的一次计划切换（`app_type` 为 None 时不限应用）
+pub fn next_switch(
+    rules: &[SwitchRule],
+    app_type: Option<&AppType>,
+    now: NaiveDateTime,
+) -> Option<ScheduledSwitch> {
+    let until = now + Duration::days(LOOKAHEAD_DAYS);
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| r.enabled && app_type.is_none_or(|a| *a == r.app_type))
+        .flat_map(|(index, rule)| {
+            rule.transitions_between(now, until)
+                .into_iter()
+                .map(move |(at, id)| (at, index, rule.app_type, id.to_string()))
+        })
+        .min_by_key(|(at, index, _, _)| (*at, *index))
+        .map(|(at, rule_index, app_type, provider_id)| ScheduledSwitch {
+            app_type,
+            provider_id,
+            provider_name: None,
+            at: Local
+                .from_local_datetime(&at)
+                .earliest()
+                .map(|t| t.timestamp_millis())
+                .unwrap_or_default(),
+            at_local: at.format("%Y-%m-%d %H:%M").to_string(),
+            rule_index,
+        })
+}
@@ -0,0 +1,398 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use crate::config::get_migration_log_path;
+use crate::provider::ClaudeCredentialKind;
+
+/// 当前配置结构版本；新增迁移步骤时同步递增
+pub const CURRENT_VERSION: u32 = 3;
+
+/// 单个迁移步骤：把 `from` 版本的配置 JSON 转换为 `from + 1` 版本
+///
+/// `migrate` 必须是纯函数（不读写文件、不依赖全局状态），
+/// 每个步骤在 `src-tauri/fixtures/schema/` 下有一对 `v<from>_to_v<from+1>.input.json` /
+/// `.expected.json`，由本模块的测试逐个校验
+struct MigrationStep {
+    from: u32,
+    name: &'static str,
+    migrate: fn(Value) -> Result<Value, String>,
+}
+
+/// 迁移注册表：按 `from` 升序排列，首尾相接
+const MIGRATIONS: &[MigrationStep] = &[
+    MigrationStep {
+        from: 1,
+        name: "wrap-claude-provider-manager",
+        migrate: v1_to_v2,
+    },
+    MigrationStep {
+        from: 2,
+        name: "single-claude-credential",
+        migrate: v2_to_v3,
+    },
+];
+
+/// 迁移日志条目（追加写入 `~/.cc-switch/migrations.log`，每行一个 JSON）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationLogEntry {
+    /// Unix 秒
+    pub timestamp: u64,
+    pub from: u32,
+    pub to: u32,
+    pub name: String,
+    pub status: MigrationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationStatus {
+    /// 已执行并随配置一起保存
+    Applied,
+    /// 本步骤或后续步骤失败，整个迁移已回滚
+    RolledBack,
+    /// 执行失败的步骤
+    Failed,
+}
+
+/// 一次迁移的执行结果：`result` 为迁移后的 JSON，失败时原始配置保持不变
+pub struct MigrationRun {
+    pub result: Result<Value, String>,
+    pub log: Vec<MigrationLogEntry>,
+}
+
+impl MigrationRun {
+    /// 是否执行了至少一个迁移步骤
+    pub fn migrated(&self) -> bool {
+        !self.log.is_empty()
+    }
+
+    /// 迁移成功但保存失败时，将已执行的步骤标记为回滚
+    pub fn mark_rolled_back(&mut self, error: &str) {
+        for entry in &mut self.log {
+            entry.status = MigrationStatus::RolledBack;
+            entry.error = Some(error.to_string());
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 识别配置 JSON 的版本：显式的 `version` 字段优先；
+/// 缺省时顶层带 `providers` 的是 v1（单个 ProviderManager），否则按 v2 处理
+pub fn detect_version(value: &Value) -> Result<u32, String> {
+    if let Some(v) = value.get("version").and_then(|v| v.as_u64()) {
+        return u32::try_from(v).map_err(|_| format!("配置版本 v{} 无效", v));
+    }
+    if value.get("providers").is_some() {
+        Ok(1)
+    } else {
+        Ok(2)
+    }
+}
+
+/// 依次执行从当前版本到 [`CURRENT_VERSION`] 的所有步骤
+///
+/// 步骤在工作副本上执行，任一步失败即整体放弃（不产生部分迁移的配置），
+/// 已执行的步骤在日志中标记为回滚
+pub fn run_migrations(original: &Value) -> MigrationRun {
+    run_steps(original, MIGRATIONS, CURRENT_VERSION)
+}
+
+/// 按注册表 `steps` 把配置迁移到 `target` 版本（见 [`run_migrations`]）
+fn run_steps(original: &Value, steps: &[MigrationStep], target: u32) -> MigrationRun {
+    let mut log = Vec::new();
+    let mut version = match detect_version(original) {
+        Ok(version) => version,
+        Err(e) => {
+            return MigrationRun {
+                result: Err(e),
+                log,
+            }
+        }
+    };
+
+    if version > target {
+        return MigrationRun {
+            result: Err(format!(
+                "配置版本 v{} 高于当前支持的 v{}，请升级 CC Switch",
+                version, target
+            )),
+            log,
+        };
+    }
+
+    let mut working = original.clone();
+    while version < target {
+        let Some(step) = steps.iter().find(|s| s.from == version) else {
+            return MigrationRun {
+                result: Err(format!("缺少 v{} 的迁移步骤", version)),
+                log,
+            };
+        };
+        let mut entry = MigrationLogEntry {
+            timestamp: now_secs(),
+            from: step.from,
+            to: step.from + 1,
+            name: step.name.to_string(),
+            status: MigrationStatus::Applied,
+            error: None,
+        };
+        match (step.migrate)(working).map(|v| with_version(v, step.from + 1)) {
+            Ok(next) => {
+                log.push(entry);
+                working = next;
+                version += 1;
+            }
+            Err(e) => {
+                let message = format!("迁移 v{} → v{} 失败: {}", entry.from, entry.to, e);
+                for applied in &mut log {
+                    applied.status = MigrationStatus::RolledBack;
+                    applied.error = Some(message.clone());
+                }
+                entry.status = MigrationStatus::Failed;
+                entry.error = Some(e);
+                log.push(entry);
+                return MigrationRun {
+                    result: Err(message),
+                    log,
+                };
+            }
+        }
+    }
+
+    MigrationRun {
+        result: Ok(working),
+        log,
+    }
+}
+
+fn with_version(mut value: Value, version: u32) -> Value {
+    if let Some(obj) = value.as_object_mut() {
+        obj.insert("version".to_string(), json!(version));
+    }
+    value
+}
+
+/// 追加迁移日志（失败仅记录到应用日志，不影响加载）
+pub fn append_log(entries: &[MigrationLogEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    let path = get_migration_log_path();
+    let result = (|| -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("打开迁移日志失败: {}", e))?;
+        for entry in entries {
+            let line =
+                serde_json::to_string(entry).map_err(|e| format!("序列化迁移日志失败: {}", e))?;
+            writeln!(file, "{}", line).map_err(|e| format!("写入迁移日志失败: {}", e))?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::warn!("{}", e);
+    }
+}
+
+/// 读取迁移日志（按写入顺序）
+pub fn read_log() -> Result<Vec<MigrationLogEntry>, String> {
+    let path = get_migration_log_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("读取迁移日志失败: {}", e))?;
+    Ok(content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+// ---------------- 迁移步骤 ----------------
+
+/// v1 → v2：单个 Claude ProviderManager（`{providers, current}`）放入多应用结构
+fn v1_to_v2(v1: Value) -> Result<Value, String> {
+    let Value::Object(manager) = v1 else {
+        return Err("v1 配置不是 JSON 对象".to_string());
+    };
+    if !manager.get("providers").is_some_and(|p| p.is_object()) {
+        return Err("v1 配置缺少 providers 对象".to_string());
+    }
+    let empty_manager = || json!({ "providers": {}, "current": "" });
+
+    let mut v2 = Map::new();
+    v2.insert("claude".to_string(), Value::Object(manager));
+    v2.insert("codex".to_string(), empty_manager());
+    v2.insert("droid".to_string(), empty_manager());
+    Ok(Value::Object(v2))
+}
+
+/// v2 → v3：同时填写了 `ANTHROPIC_AUTH_TOKEN` 与 `ANTHROPIC_API_KEY` 的 Claude 供应商
+/// 只保留 `credentialKind` 指定的一种（未指定时保留 AUTH_TOKEN），否则无法再编辑或切换
+fn v2_to_v3(mut v2: Value) -> Result<Value, String> {
+    let Some(providers) = v2
+        .pointer_mut("/claude/providers")
+        .and_then(|p| p.as_object_mut())
+    else {
+        return Ok(v2);
+    };
+    for provider in providers.values_mut() {
+        let preferred = provider
+            .get("credentialKind")
+            .and_then(|k| serde_json::from_value::<ClaudeCredentialKind>(k.clone()).ok());
+        if let Some(env) = provider.pointer_mut("/settingsConfig/env") {
+            ClaudeCredentialKind::retain_one(env, preferred);
+        }
+    }
+    Ok(v2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(step: &MigrationStep, kind: &str) -> Value {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!(
+            "fixtures/schema/v{}_to_v{}.{}.json",
+            step.from,
+            step.from + 1,
+            kind
+        ));
+        let text = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("{}: 缺少 fixture {}: {}", step.name, path.display(), e));
+        serde_json::from_str(&text).unwrap_or_else(|e| {
+            panic!("{}: 解析 fixture {} 失败: {}", step.name, path.display(), e)
+        })
+    }
+
+    #[test]
+    fn each_step_matches_its_fixture() {
+        for step in MIGRATIONS {
+            let input = fixture(step, "input");
+            let expected = fixture(step, "expected");
+            assert_eq!(
+                detect_version(&input),
+                Ok(step.from),
+                "{}: 输入 fixture 的版本",
+                step.name
+            );
+            let actual = (step.migrate)(input)
+                .map(|v| with_version(v, step.from + 1))
+                .unwrap_or_else(|e| panic!("{}: {}", step.name, e));
+            assert_eq!(actual, expected, "{}: 迁移结果与期望不一致", step.name);
+        }
+    }
+
+    #[test]
+    fn registry_covers_every_version() {
+        let froms: Vec<u32> = MIGRATIONS.iter().map(|s| s.from).collect();
+        let expected: Vec<u32> = (1..CURRENT_VERSION).collect();
+        assert_eq!(froms, expected);
+    }
+
+    #[test]
+    fn migrates_v1_to_current() {
+        let input = fixture(&MIGRATIONS[0], "input");
+        let run = run_migrations(&input);
+        let result = run.result.unwrap();
+        assert_eq!(detect_version(&result), Ok(CURRENT_VERSION));
+        assert_eq!(run.log.len(), (CURRENT_VERSION - 1) as usize);
+        assert!(run
+            .log
+            .iter()
+            .all(|e| e.status == MigrationStatus::Applied && e.error.is_none()));
+    }
+
+    #[test]
+    fn current_version_is_untouched() {
+        let input =
+            json!({ "version": CURRENT_VERSION, "claude": { "providers": {}, "current": "" } });
+        let run = run_migrations(&input);
+        assert!(!run.migrated());
+        assert_eq!(run.result.unwrap(), input);
+    }
+
+    fn fail_step(_: Value) -> Result<Value, String> {
+        Err("boom".to_string())
+    }
+
+    #[test]
+    fn failing_step_rolls_back_applied_steps() {
+        let steps = [
+            MigrationStep {
+                from: 1,
+                name: "ok",
+                migrate: v1_to_v2,
+            },
+            MigrationStep {
+                from: 2,
+                name: "fail",
+                migrate: fail_step,
+            },
+        ];
+        let input = json!({ "providers": {}, "current": "" });
+        let run = run_steps(&input, &steps, 3);
+        let error = run.result.unwrap_err();
+        assert!(error.contains("v2 → v3"));
+        assert_eq!(run.log.len(), 2);
+        assert_eq!(run.log[0].status, MigrationStatus::RolledBack);
+        assert_eq!(run.log[0].error.as_deref(), Some(error.as_str()));
+        assert_eq!(run.log[1].status, MigrationStatus::Failed);
+        assert_eq!(run.log[1].error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn missing_step_is_an_error() {
+        let input = json!({ "providers": {}, "current": "" });
+        let run = run_steps(&input, &[], 2);
+        assert!(run.result.is_err());
+        assert!(run.log.is_empty());
+    }
+
+    #[test]
+    fn mark_rolled_back_after_save_failure() {
+        let input = json!({ "providers": {}, "current": "" });
+        let mut run = run_migrations(&input);
+        run.mark_rolled_back("保存失败");
+        assert!(run
+            .log
+            .iter()
+            .all(|e| e.status == MigrationStatus::RolledBack
+                && e.error.as_deref() == Some("保存失败")));
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let input = json!({ "version": CURRENT_VERSION + 1 });
+        let run = run_migrations(&input);
+        assert!(run.result.unwrap_err().contains("高于当前支持"));
+        assert!(run.log.is_empty());
+    }
+
+    #[test]
+    fn out_of_range_version_is_rejected() {
+        // 截断为 u32 后会变成 2
+        let huge = u64::from(u32::MAX) + 3;
+        assert!(detect_version(&json!({ "version": huge })).is_err());
+        let run = run_migrations(&json!({ "version": huge }));
+        assert!(run.result.is_err());
+        assert!(run.log.is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use crate::app_config::{AppType, MultiAppConfig};
+use crate::config::get_secrets_path;
+
+/// 引用占位符的键名：`{"$secret": "<id>"}`
+pub const SECRET_REF_KEY: &str = "$secret";
+
+/// 密钥库 `~/.cc-switch/secrets.json`（0600），以密钥 ID 为键
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SecretStore {
+    #[serde(default)]
+    secrets: HashMap<String, String>,
+}
+
+impl SecretStore {
+    /// 读取密钥库（文件不存在时返回空库；解析失败时报错，避免覆盖已有密钥）
+    fn load() -> Result<Self, String> {
+        let path = get_secrets_path();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path).map_err(|e| format!("读取密钥库失败: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("解析密钥库失败: {}", e))
+    }
+
+    /// 以 0600 权限写入密钥库
+    fn save(&self) -> Result<(), String> {
+        let json =
+            serde_json::to_string_pretty(self).map_err(|e| format!("序列化密钥库失败: {}", e))?;
+        crate::secure_store::write_private_file(&get_secrets_path(), json.as_bytes())
+    }
+}
+
+/// 各应用 settings_config 中需要托管到密钥库的字段（JSON Pointer）
+fn secret_pointers(app_type: &AppType) -> &'static [&'static str] {
+    match app_type {
+        AppType::Claude => &["/env/ANTHROPIC_AUTH_TOKEN", "/env/ANTHROPIC_API_KEY"],
+        AppType::Codex => &["/auth/OPENAI_API_KEY"],
+        AppType::Droid => &["/apiKey"],
+    }
+}
+
+/// 生成密钥 ID：`<app>/<provider_id>/<field>`
+pub fn secret_id(app_type: &AppType, provider_id: &str, field: &str) -> String {
+    format!("{}/{}/{}", app_type.as_str(), provider_id, field)
+}
+
+/// 若值为引用占位符，返回其中的密钥 ID
+pub fn secret_ref_id(value: &Value) -> Option<&str> {
+    let obj = value.as_object()?;
+    if obj.len() != 1 {
+        return None;
+    }
+    obj.get(SECRET_REF_KEY).and_then(|v| v.as_str())
+}
+
+/// 将 settings_config 中的明文密钥移入密钥库，并替换为引用占位符
+///
+/// 空字符串（如官方登录预设）保持原样；返回是否有字段被替换
+pub fn extract_secrets(
+    app_type: &AppType,
+    provider_id: &str,
+    settings: &mut Value,
+) -> Result<bool, String> {
+    let mut store = SecretStore::load()?;
+    let changed = extract_into(&mut store, app_type, provider_id, settings);
+    if changed {
+        store.save()?;
+    }
+    Ok(changed)
+}
+
+fn extract_into(
+    store: &mut SecretStore,
+    app_type: &AppType,
+    provider_id: &str,
+    settings: &mut Value,
+) -> bool {
+    let mut changed = false;
+    for pointer in secret_pointers(app_type) {
+        let Some(slot) = settings.pointer_mut(pointer) else {
+            continue;
+        };
+        let Some(raw) = slot.as_str().filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        let field = pointer.rsplit('/').next().unwrap_or(pointer);
+        let id = secret_id(app_type, provider_id, field);
+        store.secrets.insert(id.clone(), raw.to_string());
+        *slot = json!({ SECRET_REF_KEY: id });
+        changed = true;
+    }
+    changed
+}
+
+/// 将整份配置中的明文密钥移入密钥库，返回替换的字段数量
+pub fn extract_config_secrets(config: &mut MultiAppConfig) -> Result<usize, String> {
+    let mut store = SecretStore::load()?;
+    let mut count = 0;
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Droid] {
+        if let Some(manager) = config.get_manager_mut(&app_type) {
+            for (id, provider) in manager.providers.iter_mut() {
+                if extract_into(&mut store, &app_type, id, &mut provider.settings_config) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    if count > 0 {
+        store.save()?;
+    }
+    Ok(count)
+}
+
+/// 解析 settings_config 中的所有引用占位符，返回可直接写入 live 文件的副本
+pub fn resolve_secrets(settings: &Value) -> Result<Value, String> {
+    let store = SecretStore::load()?;
+    resolve_with(&store, settings)
+}
+
+fn resolve_with(store: &SecretStore, value: &Value) -> Result<Value, String> {
+    if let Some(id) = secret_ref_id(value) {
+        return store
+            .secrets
+            .get(id)
+            .map(|s| Value::String(s.clone()))
+            .ok_or_else(|| format!("密钥库中缺少密钥: {}", id));
+    }
+    match value {
+        Value::Object(map) => {
+            let mut out = serde_json::Map::with_capacity(map.len());
+            for (k, v) in map {
+                out.insert(k.clone(), resolve_with(store, v)?);
+            }
+            Ok(Value::Object(out))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|v| resolve_with(store, v))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// 配置中全部供应商引用的密钥 ID
+fn config_secret_refs(config: &MultiAppConfig) -> Vec<&str> {
+    fn collect<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        if let Some(id) = secret_ref_id(value) {
+            out.push(id);
+            return;
+        }
+        match value {
+            Value::Object(map) => map.values().for_each(|v| collect(v, out)),
+            Value::Array(items) => items.iter().for_each(|v| collect(v, out)),
+            _ => {}
+        }
+    }
+
+    let mut refs = Vec::new();
+    for manager in config.apps.values() {
+        for provider in manager.providers.values() {
+            collect(&provider.settings_config, &mut refs);
+        }
+    }
+    refs
+}
+
+/// 列出配置中在密钥库里找不到的引用（按密钥 ID 排序去重），用于恢复快照前校验
+pub fn missing_secret_refs(config: &MultiAppConfig) -> Result<Vec<String>, String> {
+    let store = SecretStore::load()?;
+    let mut missing: Vec<String> = config_secret_refs(config)
+        .into_iter()
+        .filter(|id| !store.secrets.contains_key(*id))
+        .map(str::to_string)
+        .collect();
+    missing.sort();
+    missing.dedup();
+    Ok(missing)
+}
+
+/// 取字符串值：明文直接返回，引用占位符从密钥库解析（失败返回 None）
+pub fn resolve_secret_str(value: &Value) -> Option<String> {
+    if let Some(s) = value.as_str() {
+        return Some(s.to_string());
+    }
+    let id = secret_ref_id(value)?;
+    SecretStore::load().ok()?.secrets.get(id).cloned()
+}
+
+/// 移除现存供应商不再引用的 `<app>/<id>/<field>` 密钥（如更换凭据类型后旧字段的密钥），返回移除数量
+///
+/// 在配置保存成功后调用：修改失败回滚时，旧配置引用的密钥仍然可用
+pub fn prune_unreferenced_secrets(config: &MultiAppConfig) -> Result<usize, String> {
+    let referenced: HashSet<&str> = config_secret_refs(config).into_iter().collect();
+    let mut store = SecretStore::load()?;
+    let before = store.secrets.len();
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Droid] {
+        let Some(manager) = config.get_manager(&app_type) else {
+            continue;
+        };
+        for provider_id in manager.providers.keys() {
+            for pointer in secret_pointers(&app_type) {
+                let field = pointer.rsplit('/').next().unwrap_or(pointer);
+                let id = secret_id(&app_type, provider_id, field);
+                if !referenced.contains(id.as_str()) {
+                    store.secrets.remove(&id);
+                }
+            }
+        }
+    }
+    let removed = before - store.secrets.len();
+    if removed > 0 {
+        store.save()?;
+    }
+    Ok(removed)
+}
+
+/// 删除某个供应商在密钥库中的全部密钥
+pub fn remove_provider_secrets(app_type: &AppType, provider_id: &str) -> Result<(), String> {
+    let mut store = SecretStore::load()?;
+    let prefix = format!("{}/{}/", app_type.as_str(), provider_id);
+    let before = store.secrets.len();
+    store.secrets.retain(|id, _| !id.starts_with(&prefix));
+    if store.secrets.len() != before {
+        store.save()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,600 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::app_config::{LoadError, MultiAppConfig};
+use crate::config::{
+    atomic_write, get_app_config_dir, get_app_config_enc_path, get_config_key_path,
+};
+
+/// 加密封装格式版本
+const ENVELOPE_VERSION: u32 = 1;
+/// 封装使用的 AEAD 算法标识
+const ENVELOPE_ALG: &str = "AES-256-GCM";
+/// 口令派生密钥时使用的 KDF 标识
+const KDF_ALG: &str = "argon2id";
+/// 通过环境变量提供口令（无界面 / 无 keyring 的 Linux 场景）
+pub const PASSPHRASE_ENV: &str = "CC_SWITCH_PASSPHRASE";
+
+/// 口令派生参数（Argon2id），写入密钥文件与加密封装，便于丢失密钥文件后由口令重新派生
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KdfParams {
+    alg: String,
+    salt: String,
+    #[serde(rename = "mCost")]
+    m_cost: u32,
+    #[serde(rename = "tCost")]
+    t_cost: u32,
+    #[serde(rename = "pCost")]
+    p_cost: u32,
+}
+
+/// 密钥文件 `~/.cc-switch/config.key`（0600）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    v: u32,
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+    /// 更换密钥尚未完成时保留的旧密钥
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    previous: Vec<String>,
+}
+
+/// 加密配置封装 `~/.cc-switch/config.enc.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope {
+    v: u32,
+    alg: String,
+    nonce: String,
+    ct: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<KdfParams>,
+}
+
+/// 已解锁的密钥
+struct ConfigKey {
+    key: [u8; 32],
+    kdf: Option<KdfParams>,
+    /// 旧密钥：更换密钥时在全部副本重新加密之前保留，旧封装仍可解密
+    previous: Vec<[u8; 32]>,
+}
+
+impl KdfParams {
+    /// 生成新的随机盐与默认 Argon2id 参数
+    fn generate() -> Self {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            alg: KDF_ALG.to_string(),
+            salt: STANDARD.encode(salt),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+
+    /// 由口令派生 32 字节密钥
+    fn derive(&self, passphrase: &str) -> Result<[u8; 32], String> {
+        if self.alg != KDF_ALG {
+            return Err(format!("不支持的密钥派生算法: {}", self.alg));
+        }
+        let salt = STANDARD
+            .decode(&self.salt)
+            .map_err(|e| format!("解析 KDF 盐失败: {}", e))?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| format!("KDF 参数无效: {}", e))?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("口令派生密钥失败: {}", e))?;
+        Ok(key)
+    }
+}
+
+/// 加密：返回 (nonce, ciphertext)，每次调用生成新的 12 字节随机 nonce
+pub fn encrypt_bytes(key: &[u8; 32], plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ct = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "加密配置失败".to_string())?;
+    Ok((nonce.to_vec(), ct))
+}
+
+/// 解密：认证失败（密钥错误或密文被篡改）时返回错误
+pub fn decrypt_bytes(key: &[u8; 32], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if nonce.len() != 12 {
+        return Err("加密配置 nonce 长度无效".to_string());
+    }
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "解密配置失败：密钥不匹配或文件已损坏".to_string())
+}
+
+/// 读取并解析加密封装
+///
+/// 无法解析为封装时为 [`LoadError::Corrupt`]；读取失败或封装版本 / 算法由新版本写入时为 [`LoadError::Other`]
+fn read_envelope(path: &Path) -> Result<Envelope, LoadError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| LoadError::Other(format!("读取加密配置失败: {}", e)))?;
+    let envelope: Envelope = serde_json::from_str(&content)
+        .map_err(|e| LoadError::Corrupt(format!("解析加密配置封装失败: {}", e)))?;
+    if envelope.v > ENVELOPE_VERSION {
+        return Err(LoadError::Other(format!(
+            "加密配置版本 v{} 高于当前支持的 v{}，请升级 CC Switch",
+            envelope.v, ENVELOPE_VERSION
+        )));
+    }
+    if envelope.v != ENVELOPE_VERSION {
+        return Err(LoadError::Corrupt(format!(
+            "不支持的加密配置版本: {}",
+            envelope.v
+        )));
+    }
+    if envelope.alg != ENVELOPE_ALG {
+        return Err(LoadError::Other(format!(
+            "不支持的加密算法: {}",
+            envelope.alg
+        )));
+    }
+    Ok(envelope)
+}
+
+/// 判断文本是否为加密封装（而非明文配置）
+pub fn is_envelope(content: &str) -> bool {
+    serde_json::from_str::<Value>(content)
+        .map(|v| v.get("ct").is_some() && v.get("nonce").is_some())
+        .unwrap_or(false)
+}
+
+/// 解密封装内容
+fn open_envelope(key: &[u8; 32], envelope: &Envelope) -> Result<Vec<u8>, String> {
+    let nonce = STANDARD
+        .decode(&envelope.nonce)
+        .map_err(|e| format!("解析 nonce 失败: {}", e))?;
+    let ct = STANDARD
+        .decode(&envelope.ct)
+        .map_err(|e| format!("解析密文失败: {}", e))?;
+    decrypt_bytes(key, &nonce, &ct)
+}
+
+/// 依次以当前密钥与保留的旧密钥解密封装内容
+fn open_envelope_with(key: &ConfigKey, envelope: &Envelope) -> Result<Vec<u8>, String> {
+    let error = match open_envelope(&key.key, envelope) {
+        Ok(plaintext) => return Ok(plaintext),
+        Err(e) => e,
+    };
+    key.previous
+        .iter()
+        .find_map(|old| open_envelope(old, envelope).ok())
+        .ok_or(error)
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("解析密钥失败: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| "密钥长度无效，应为 32 字节".to_string())
+}
+
+/// 读取密钥文件（不存在时返回 None）
+fn read_key_file() -> Result<Option<ConfigKey>, String> {
+    let path = get_config_key_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+    let file: KeyFile =
+        serde_json::from_str(&content).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+    let previous = file
+        .previous
+        .iter()
+        .map(|k| decode_key(k))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(ConfigKey {
+        key: decode_key(&file.key)?,
+        kdf: file.kdf,
+        previous,
+    }))
+}
+
+/// 写入密钥文件（仅当前用户可读写）
+fn write_key_file(key: &ConfigKey) -> Result<(), String> {
+    let file = KeyFile {
+        v: ENVELOPE_VERSION,
+        key: STANDARD.encode(key.key),
+        kdf: key.kdf.clone(),
+        previous: key.previous.iter().map(|k| STANDARD.encode(k)).collect(),
+    };
+    let json =
+        serde_json::to_string_pretty(&file).map_err(|e| format!("序列化密钥文件失败: {}", e))?;
+    write_private_file(&get_config_key_path(), json.as_bytes())
+}
+
+/// 以 0600 权限原子写入文件：临时文件创建时即限制权限，再 rename 替换
+pub(crate) fn write_private_file(path: &Path, data: &[u8]) -> Result<(), String> {
+    let parent = path.parent().ok_or_else(|| "无效的路径".to_string())?;
+    fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| "无效的文件名".to_string())?
+        .to_string_lossy()
+        .to_string();
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let tmp = parent.join(format!("{}.tmp.{}", file_name, ts));
+
+    {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut f = options
+            .open(&tmp)
+            .map_err(|e| format!("创建临时文件失败: {}", e))?;
+        f.write_all(data)
+            .map_err(|e| format!("写入临时文件失败: {}", e))?;
+        f.flush().map_err(|e| format!("刷新临时文件失败: {}", e))?;
+    }
+
+    #[cfg(windows)]
+    {
+        if path.exists() {
+            let _ = fs::remove_file(path);
+        }
+    }
+    fs::rename(&tmp, path).map_err(|e| format!("原子替换失败: {}", e))
+}
+
+/// 获取密钥：优先密钥文件；其次由环境变量口令派生；都没有且尚无加密配置时生成随机密钥
+fn get_or_create_key() -> Result<ConfigKey, String> {
+    if let Some(key) = read_key_file()? {
+        return Ok(key);
+    }
+
+    let enc_path = get_app_config_enc_path();
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if !passphrase.is_empty() {
+            let key = derive_key_for_passphrase(&passphrase)?;
+            write_key_file(&key)?;
+            log::info!("已由 {} 派生密钥并写入密钥文件", PASSPHRASE_ENV);
+            return Ok(key);
+        }
+    }
+
+    if enc_path.exists() {
+        // 已有加密配置却缺少密钥：绝不生成新密钥，避免覆盖后无法恢复
+        return Err(format!(
+            "缺少密钥文件 {}，无法解密配置；请设置环境变量 {} 或运行 `cc-switch-cli unlock`",
+            get_config_key_path().display(),
+            PASSPHRASE_ENV
+        ));
+    }
+
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    let key = ConfigKey {
+        key,
+        kdf: None,
+        previous: Vec::new(),
+    };
+    write_key_file(&key)?;
+    // 密钥与密文在同一目录：只防配置文件单独外泄（同步、备份），不防能读取该目录的人
+    log::warn!(
+        "已生成随机配置加密密钥并保存在 {}（与加密配置位于同一目录）；密钥文件丢失将无法解密，可设置 {} 或运行 `cc-switch-cli unlock` 改用口令",
+        get_config_key_path().display(),
+        PASSPHRASE_ENV
+    );
+    Ok(key)
+}
+
+/// 由口令派生密钥：已有加密配置时沿用其中的 KDF 参数并校验能否解密，否则生成新参数
+fn derive_key_for_passphrase(passphrase: &str) -> Result<ConfigKey, String> {
+    let enc_path = get_app_config_enc_path();
+    if enc_path.exists() {
+        let envelope = read_envelope(&enc_path)?;
+        let kdf = envelope
+            .kdf
+            .clone()
+            .ok_or_else(|| "现有加密配置并非由口令派生的密钥加密，无法通过口令解锁".to_string())?;
+        let key = kdf.derive(passphrase)?;
+        open_envelope(&key, &envelope).map_err(|_| "口令错误，无法解密现有配置".to_string())?;
+        return Ok(ConfigKey {
+            key,
+            kdf: Some(kdf),
+            previous: Vec::new(),
+        });
+    }
+
+    let kdf = KdfParams::generate();
+    let key = kdf.derive(passphrase)?;
+    Ok(ConfigKey {
+        key,
+        kdf: Some(kdf),
+        previous: Vec::new(),
+    })
+}
+
+/// 由口令解锁并写入密钥文件（供 CLI 在无界面环境下使用）
+///
+/// - 现有配置由口令派生密钥加密：校验口令能解密后写入密钥文件
+/// - 现有配置由随机密钥加密：用现有密钥解密后改由口令派生的新密钥重新加密
+/// - 尚无加密配置：以该口令派生的新密钥替换现有密钥文件
+///
+/// 密钥变化时，`.bak`、快照、归档与迁移备份等以旧密钥加密的副本一并重新加密（见 [`rekey_envelopes`]）；
+/// 整个过程持有配置锁
+pub fn unlock_with_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.is_empty() {
+        return Err("口令不能为空".to_string());
+    }
+
+    crate::config_sync::with_config_lock(|| {
+        let existing = read_key_file()?;
+        let enc_path = get_app_config_enc_path();
+        let (mut key, config) = if enc_path.exists() && read_envelope(&enc_path)?.kdf.is_none() {
+            let config = read_encrypted_config()?;
+            let kdf = KdfParams::generate();
+            let key = ConfigKey {
+                key: kdf.derive(passphrase)?,
+                kdf: Some(kdf),
+                previous: Vec::new(),
+            };
+            (key, Some(config))
+        } else {
+            (derive_key_for_passphrase(passphrase)?, None)
+        };
+
+        // 先写入新密钥并保留旧密钥（包括上次未完成时留下的），中途失败时新旧封装都能解密
+        if let Some(old) = existing {
+            if old.key != key.key {
+                key.previous.push(old.key);
+            }
+            key.previous
+                .extend(old.previous.into_iter().filter(|k| *k != key.key));
+        }
+        write_key_file(&key)?;
+        if let Some(config) = config {
+            write_envelope(&key, &config)?;
+            log::info!("已改用口令派生的密钥重新加密配置");
+        }
+
+        if !key.previous.is_empty() {
+            let failed = rekey_envelopes(&key)?;
+            if failed.is_empty() {
+                key.previous.clear();
+                write_key_file(&key)?;
+            } else {
+                log::warn!(
+                    "{} 个文件无法以新旧密钥解密，旧密钥保留在密钥文件中: {:?}",
+                    failed.len(),
+                    failed
+                );
+            }
+        }
+        log::info!(
+            "已写入口令派生的密钥文件: {}",
+            get_config_key_path().display()
+        );
+        Ok(())
+    })
+}
+
+/// 把 `~/.cc-switch/` 下仍以旧密钥（`key.previous`）加密的封装改用当前密钥重新加密，
+/// 返回无法解密的文件
+fn rekey_envelopes(key: &ConfigKey) -> Result<Vec<PathBuf>, String> {
+    let current = ConfigKey {
+        key: key.key,
+        kdf: key.kdf.clone(),
+        previous: Vec::new(),
+    };
+    let mut failed = Vec::new();
+    for path in envelope_files(&get_app_config_dir()) {
+        let envelope = match read_envelope(&path) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                log::warn!("读取加密文件失败 {}: {}", path.display(), e.message());
+                failed.push(path);
+                continue;
+            }
+        };
+        if open_envelope(&current.key, &envelope).is_ok() {
+            continue;
+        }
+        match open_envelope_with(key, &envelope) {
+            Ok(plaintext) => {
+                write_private_file(&path, &seal_bytes(&current, &plaintext)?)?;
+                log::info!("已用新密钥重新加密: {}", path.display());
+            }
+            Err(e) => {
+                log::warn!("无法解密 {}: {}", path.display(), e);
+                failed.push(path);
+            }
+        }
+    }
+    Ok(failed)
+}
+
+/// 递归列出目录下内容为加密封装的文件
+fn envelope_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => files.extend(envelope_files(&path)),
+            Ok(t)
+                if t.is_file()
+                    && fs::read_to_string(&path).is_ok_and(|content| is_envelope(&content)) =>
+            {
+                files.push(path)
+            }
+            _ => {}
+        }
+    }
+    files
+}
+
+/// 读取 `config.enc.json`：解析封装、解密、反序列化
+pub fn read_encrypted_config() -> Result<MultiAppConfig, String> {
+    read_encrypted_config_from(&get_app_config_enc_path())
+}
+
+/// 读取指定路径的加密配置（如 `.bak` 或归档副本），按需执行结构迁移
+pub fn read_encrypted_config_from(path: &Path) -> Result<MultiAppConfig, String> {
+    MultiAppConfig::from_json_value(&read_encrypted_json_from(path)?)
+}
+
+/// 解密指定路径的加密配置，返回迁移前的原始 JSON
+pub fn read_encrypted_json_from(path: &Path) -> Result<serde_json::Value, String> {
+    load_encrypted_json(path).map_err(String::from)
+}
+
+/// 同 [`read_encrypted_json_from`]，区分文件损坏（解析或解密失败）与密钥不可用等其他错误
+pub fn load_encrypted_json(path: &Path) -> Result<serde_json::Value, LoadError> {
+    let envelope = read_envelope(path)?;
+    let key = get_or_create_key().map_err(LoadError::Other)?;
+    let plaintext = open_envelope_with(&key, &envelope).map_err(LoadError::Corrupt)?;
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| LoadError::Corrupt(format!("解析解密后的配置失败: {}", e)))
+}
+
+/// 写入 `config.enc.json`：序列化 → 加密 → 原子写入
+pub fn write_encrypted_config(config: &MultiAppConfig) -> Result<(), String> {
+    let key = get_or_create_key()?;
+    write_envelope(&key, config)
+}
+
+/// 以当前密钥加密配置，返回封装后的文件内容（不落盘）
+pub fn encrypt_config(config: &MultiAppConfig) -> Result<Vec<u8>, String> {
+    let key = get_or_create_key()?;
+    seal_config(&key, config)
+}
+
+fn write_envelope(key: &ConfigKey, config: &MultiAppConfig) -> Result<(), String> {
+    let data = seal_config(key, config)?;
+    atomic_write(&get_app_config_enc_path(), &data)
+}
+
+/// 以当前密钥加密 JSON 文本（如旧明文配置的归档副本），返回封装后的文件内容（不落盘）
+///
+/// 结果可由 [`read_encrypted_json_from`] 读取
+pub fn encrypt_json_bytes(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    serde_json::from_slice::<serde_json::Value>(plaintext)
+        .map_err(|e| format!("解析待加密的 JSON 失败: {}", e))?;
+    let key = get_or_create_key()?;
+    seal_bytes(&key, plaintext)
+}
+
+fn seal_config(key: &ConfigKey, config: &MultiAppConfig) -> Result<Vec<u8>, String> {
+    let plaintext = serde_json::to_vec(config).map_err(|e| format!("序列化配置失败: {}", e))?;
+    seal_bytes(key, &plaintext)
+}
+
+fn seal_bytes(key: &ConfigKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let (nonce, ct) = encrypt_bytes(&key.key, plaintext)?;
+    let envelope = Envelope {
+        v: ENVELOPE_VERSION,
+        alg: ENVELOPE_ALG.to_string(),
+        nonce: STANDARD.encode(nonce),
+        ct: STANDARD.encode(ct),
+        kdf: key.kdf.clone(),
+    };
+    let json = serde_json::to_string_pretty(&envelope)
+        .map_err(|e| format!("序列化加密配置失败: {}", e))?;
+    Ok(json.into_bytes())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::app_config::AppType;
+    use crate::provider::Provider;
+    use crate::test_support::TempHome;
+    use serde_json::json;
+
+    fn config_with(id: &str) -> MultiAppConfig {
+        let mut config = MultiAppConfig::default();
+        let provider = Provider::with_id(
+            id.to_string(),
+            id.to_string(),
+            json!({ "env": { "ANTHROPIC_AUTH_TOKEN": format!("sk-{}", id) } }),
+            None,
+        );
+        config
+            .get_manager_mut(&AppType::Claude)
+            .unwrap()
+            .providers
+            .insert(id.to_string(), provider);
+        config
+    }
+
+    #[test]
+    fn passphrase_rekeys_snapshots_and_backups() {
+        let _home = TempHome::new();
+        // 随机密钥下保存两次：第二次保存前为第一版创建快照与 .bak
+        config_with("a").save().unwrap();
+        config_with("b").save().unwrap();
+        let snapshot = crate::snapshot::list_snapshots().unwrap().remove(0);
+        let backup = get_app_config_dir().join("config.v1.backup.1.json");
+        let sealed = encrypt_json_bytes(br#"{"providers": {}, "current": ""}"#).unwrap();
+        write_private_file(&backup, &sealed).unwrap();
+        let random_key = read_key_file().unwrap().unwrap().key;
+
+        unlock_with_passphrase("correct horse").unwrap();
+
+        let key = read_key_file().unwrap().unwrap();
+        assert!(key.kdf.is_some());
+        assert_ne!(key.key, random_key);
+        assert!(key.previous.is_empty(), "全部副本重新加密后不再保留旧密钥");
+
+        let restored = crate::snapshot::restore_snapshot(&snapshot.id).unwrap();
+        let providers = &restored
+            .config
+            .get_manager(&AppType::Claude)
+            .unwrap()
+            .providers;
+        assert!(providers.contains_key("a"));
+        read_encrypted_config_from(&get_app_config_dir().join("config.enc.json.bak")).unwrap();
+        read_encrypted_json_from(&backup).unwrap();
+    }
+
+    #[test]
+    fn unreadable_copy_keeps_previous_key() {
+        let _home = TempHome::new();
+        config_with("a").save().unwrap();
+        // 以其他密钥加密的副本：新旧密钥都无法解密
+        let stranger = ConfigKey {
+            key: [7u8; 32],
+            kdf: None,
+            previous: Vec::new(),
+        };
+        let stray = get_app_config_dir().join("stray.enc.json");
+        write_private_file(&stray, &seal_bytes(&stranger, b"{}").unwrap()).unwrap();
+        let random_key = read_key_file().unwrap().unwrap().key;
+
+        unlock_with_passphrase("correct horse").unwrap();
+
+        let key = read_key_file().unwrap().unwrap();
+        assert_eq!(key.previous, vec![random_key]);
+        read_encrypted_config().unwrap();
+    }
+}
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{OnceLock, RwLock};
 
+use crate::preflight::PreflightMode;
+use crate::schedule::SwitchRule;
+use crate::switch_hooks::AppSwitchHooks;
+
 /// 应用设置结构，允许覆盖默认配置目录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -23,6 +28,27 @@ pub struct AppSettings {
     pub auto_start: bool,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub global_shortcut: Option<String>,
+    /// 最多保留的配置快照数量（0 表示不限）
+    #[serde(default = "default_snapshot_retention_count")]
+    pub snapshot_retention_count: u32,
+    /// 配置快照保留天数（未设置表示不按时间清理）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_retention_days: Option<u32>,
+    /// live 配置文件被外部修改后自动回填到当前供应商
+    #[serde(default)]
+    pub auto_sync_live_config: bool,
+    /// 切换前后执行的命令，以应用类型（claude / codex / droid）为键
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub switch_hooks: HashMap<String, AppSwitchHooks>,
+    /// 切换前连通性检查方式（off / warn / block），以应用类型为键，未配置表示不检查
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub switch_preflight: HashMap<String, PreflightMode>,
+    /// 按时间自动切换供应商的规则（靠后的规则优先）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub switch_rules: Vec<SwitchRule>,
+    /// 为每个 Codex 供应商生成 `[profiles.<id>]`，可通过 `codex --profile <id>` 同时使用多个供应商
+    #[serde(default)]
+    pub codex_profiles: bool,
 }
 
 fn default_show_in_tray() -> bool {
@@ -33,6 +59,10 @@ fn default_minimize_to_tray_on_close() -> bool {
     true
 }
 
+fn default_snapshot_retention_count() -> u32 {
+    20
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
@@ -44,6 +74,13 @@ impl Default for AppSettings {
             language: None,
             auto_start: false,
             global_shortcut: None,
+            snapshot_retention_count: default_snapshot_retention_count(),
+            snapshot_retention_days: None,
+            auto_sync_live_config: false,
+            switch_hooks: HashMap::new(),
+            switch_preflight: HashMap::new(),
+            switch_rules: Vec::new(),
+            codex_profiles: false,
         }
     }
 }
@@ -88,6 +125,8 @@ impl AppSettings {
             .map(|s| s.trim())
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string());
+
+        self.snapshot_retention_days = self.snapshot_retention_days.filter(|days| *days > 0);
     }
 
     pub fn load() -> Self {
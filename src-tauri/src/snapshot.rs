@@ -0,0 +1,273 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_config::MultiAppConfig;
+use crate::config::{atomic_write, get_app_config_enc_path, get_snapshots_dir};
+use crate::provider::ProviderManager;
+use crate::secure_store;
+
+const SNAPSHOT_PREFIX: &str = "config.";
+const SNAPSHOT_SUFFIX: &str = ".enc.json";
+
+/// 配置快照信息（快照 ID 为创建时的毫秒时间戳）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    /// 创建时间（Unix 毫秒）
+    pub created_at: u64,
+    pub size: u64,
+    pub path: String,
+}
+
+/// 供应商标识（用于差异列表）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRef {
+    pub id: String,
+    pub name: String,
+}
+
+/// 单个应用的差异：以快照为基准，描述当前配置相对快照的变化
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSnapshotDiff {
+    pub app: String,
+    /// 快照之后新增的供应商
+    pub added: Vec<ProviderRef>,
+    /// 快照之后删除的供应商
+    pub removed: Vec<ProviderRef>,
+    /// 两边都存在但配置不同的供应商
+    pub changed: Vec<ProviderRef>,
+    pub snapshot_current: String,
+    pub current: String,
+}
+
+/// 快照与当前配置的差异（仅包含有变化的应用）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub snapshot_id: String,
+    pub apps: Vec<AppSnapshotDiff>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn snapshot_path(id: &str) -> PathBuf {
+    get_snapshots_dir().join(format!("{}{}{}", SNAPSHOT_PREFIX, id, SNAPSHOT_SUFFIX))
+}
+
+/// 从文件名解析快照 ID（`config.<ms>.enc.json`）
+fn parse_snapshot_id(file_name: &str) -> Option<u64> {
+    file_name
+        .strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(SNAPSHOT_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// 列出所有快照，按时间从新到旧排列
+pub fn list_snapshots() -> Result<Vec<SnapshotInfo>, String> {
+    let dir = get_snapshots_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(&dir).map_err(|e| format!("读取快照目录失败: {}", e))?;
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let created_at = parse_snapshot_id(&entry.file_name().to_string_lossy())?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            Some(SnapshotInfo {
+                id: created_at.to_string(),
+                created_at,
+                size,
+                path: entry.path().to_string_lossy().to_string(),
+            })
+        })
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+    Ok(snapshots)
+}
+
+/// 在覆盖 `config.enc.json` 之前为其内容创建快照，并按设置清理过期快照
+///
+/// 内容与最新快照相同时跳过，避免重复保存产生无意义的快照
+pub fn snapshot_before_save(enc_path: &Path) -> Result<Option<SnapshotInfo>, String> {
+    if !enc_path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read(enc_path).map_err(|e| format!("读取配置文件失败: {}", e))?;
+
+    // 每次加密使用新 nonce，密文必然不同，需解密后比较内容
+    if let Some(latest) = list_snapshots()?.first() {
+        let same = match (
+            MultiAppConfig::read_from_path(enc_path),
+            MultiAppConfig::read_from_path(Path::new(&latest.path)),
+        ) {
+            (Ok(current), Ok(previous)) => {
+                serde_json::to_value(&current).ok() == serde_json::to_value(&previous).ok()
+            }
+            _ => false,
+        };
+        if same {
+            return Ok(None);
+        }
+    }
+
+    // 同一毫秒内多次保存时顺延，保证 ID 唯一
+    let mut created_at = now_millis();
+    while snapshot_path(&created_at.to_string()).exists() {
+        created_at += 1;
+    }
+    let id = created_at.to_string();
+    let path = snapshot_path(&id);
+    secure_store::write_private_file(&path, &data)?;
+
+    prune_snapshots();
+    Ok(Some(SnapshotInfo {
+        id,
+        created_at,
+        size: data.len() as u64,
+        path: path.to_string_lossy().to_string(),
+    }))
+}
+
+/// 按设置中的保留数量与保留天数删除旧快照（失败仅记录日志）
+fn prune_snapshots() {
+    let settings = crate::settings::get_settings();
+    let max_count = settings.snapshot_retention_count as usize;
+    let max_age_ms = settings
+        .snapshot_retention_days
+        .map(|days| u64::from(days) * 24 * 60 * 60 * 1000);
+    let now = now_millis();
+
+    let snapshots = match list_snapshots() {
+        Ok(list) => list,
+        Err(e) => {
+            log::warn!("清理快照失败: {}", e);
+            return;
+        }
+    };
+    for (index, snapshot) in snapshots.iter().enumerate() {
+        let over_count = max_count > 0 && index >= max_count;
+        let too_old = max_age_ms.is_some_and(|max| now.saturating_sub(snapshot.created_at) > max);
+        if over_count || too_old {
+            if let Err(e) = fs::remove_file(&snapshot.path) {
+                log::warn!("删除过期快照失败 {}: {}", snapshot.path, e);
+            }
+        }
+    }
+}
+
+/// 读取并解密指定快照
+pub fn read_snapshot(id: &str) -> Result<MultiAppConfig, String> {
+    let created_at: u64 = id.parse().map_err(|_| format!("无效的快照 ID: {}", id))?;
+    let path = snapshot_path(&created_at.to_string());
+    if !path.exists() {
+        return Err(format!("快照不存在: {}", id));
+    }
+    MultiAppConfig::read_from_path(&path)
+}
+
+/// 比较快照与当前配置：新增 / 删除 / 变更的供应商以及当前供应商的变化
+pub fn diff_snapshot(id: &str, current: &MultiAppConfig) -> Result<SnapshotDiff, String> {
+    let snapshot = read_snapshot(id)?;
+    let empty = ProviderManager::default();
+
+    let app_names: BTreeSet<&String> = snapshot.apps.keys().chain(current.apps.keys()).collect();
+    let mut apps = Vec::new();
+    for app in app_names {
+        let before = snapshot.apps.get(app).unwrap_or(&empty);
+        let after = current.apps.get(app).unwrap_or(&empty);
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for (pid, provider) in &after.providers {
+            let entry = ProviderRef {
+                id: pid.clone(),
+                name: provider.name.clone(),
+            };
+            match before.providers.get(pid) {
+                None => added.push(entry),
+                Some(old) => {
+                    if serde_json::to_value(old).ok() != serde_json::to_value(provider).ok() {
+                        changed.push(entry);
+                    }
+                }
+            }
+        }
+        let mut removed: Vec<ProviderRef> = before
+            .providers
+            .iter()
+            .filter(|(pid, _)| !after.providers.contains_key(*pid))
+            .map(|(pid, provider)| ProviderRef {
+                id: pid.clone(),
+                name: provider.name.clone(),
+            })
+            .collect();
+
+        if added.is_empty()
+            && removed.is_empty()
+            && changed.is_empty()
+            && before.current == after.current
+        {
+            continue;
+        }
+        for list in [&mut added, &mut removed, &mut changed] {
+            list.sort_by(|a, b| a.id.cmp(&b.id));
+        }
+        apps.push(AppSnapshotDiff {
+            app: app.clone(),
+            added,
+            removed,
+            changed,
+            snapshot_current: before.current.clone(),
+            current: after.current.clone(),
+        });
+    }
+
+    Ok(SnapshotDiff {
+        snapshot_id: id.to_string(),
+        apps,
+    })
+}
+
+/// 恢复结果：恢复后的配置与覆盖前的配置（原配置缺失或无法读取时为 None）
+pub struct Restored {
+    pub config: MultiAppConfig,
+    pub previous: Option<MultiAppConfig>,
+}
+
+/// 将快照恢复为 `config.enc.json`，返回恢复后的配置
+///
+/// 快照先完整解密校验，引用的密钥须仍在密钥库中，再以当前密钥重新加密后原子写入；
+/// 覆盖前的配置同样会留下快照，可再次恢复
+pub fn restore_snapshot(id: &str) -> Result<Restored, String> {
+    let config = read_snapshot(id)?;
+    // 快照之后删除或轮换过的密钥无法再解析，恢复后供应商将无法切换
+    let missing = crate::secrets::missing_secret_refs(&config)?;
+    if !missing.is_empty() {
+        return Err(format!(
+            "快照引用的密钥已不在密钥库中，无法恢复: {}",
+            missing.join(", ")
+        ));
+    }
+    let data = secure_store::encrypt_config(&config)?;
+
+    let enc_path = get_app_config_enc_path();
+    let previous = MultiAppConfig::read_from_path(&enc_path).ok();
+    if let Err(e) = snapshot_before_save(&enc_path) {
+        log::warn!("恢复前创建快照失败: {}", e);
+    }
+    atomic_write(&enc_path, &data)?;
+    log::info!("已从快照 {} 恢复配置", id);
+    Ok(Restored { config, previous })
+}
@@ -15,7 +15,7 @@ pub struct EndpointLatency {
   pub error: Option<String>,
 }
 
-fn build_client(timeout_secs: u64) -> Result<Client, String> {
+pub(crate) fn build_client(timeout_secs: u64) -> Result<Client, String> {
   Client::builder()
     .timeout(Duration::from_secs(timeout_secs))
     .redirect(reqwest::redirect::Policy::limited(5))
@@ -24,7 +24,7 @@ fn build_client(timeout_secs: u64) -> Result<Client, String> {
     .map_err(|e| format!("创建 HTTP 客户端失败: {e}"))
 }
 
-fn sanitize_timeout(timeout_secs: Option<u64>) -> u64 {
+pub(crate) fn sanitize_timeout(timeout_secs: Option<u64>) -> u64 {
   let secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
   secs.clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS)
 }
@@ -1,31 +1,111 @@
-use crate::app_config::MultiAppConfig;
+use crate::app_config::AppType;
+use crate::config_recovery::RecoveryReport;
+use crate::provider_service::ProviderService;
+use crate::switch_hooks::SwitchReport;
 use std::sync::Mutex;
+use std::time::Duration;
 
 /// 全局应用状态
 pub struct AppState {
-    pub config: Mutex<MultiAppConfig>,
+    pub service: Mutex<ProviderService>,
+    /// 启动时的配置恢复结果（配置正常加载时为 None）
+    pub recovery: Option<RecoveryReport>,
 }
 
 impl AppState {
-    /// 创建新的应用状态
+    /// 创建新的应用状态（配置损坏时自动从备份恢复，无法恢复则进入只读模式）
     pub fn new() -> Self {
-        let config = MultiAppConfig::load().unwrap_or_else(|e| {
-            log::warn!("加载配置失败: {}, 使用默认配置", e);
-            MultiAppConfig::default()
-        });
+        let (service, recovery) = ProviderService::load_or_recover();
 
         Self {
-            config: Mutex::new(config),
+            service: Mutex::new(service),
+            recovery,
         }
     }
 
-    /// 保存配置到文件
+    /// 保存配置到文件（先合并其他进程写入的改动）
     pub fn save(&self) -> Result<(), String> {
-        let config = self
-            .config
+        let mut service = self
+            .service
             .lock()
             .map_err(|e| format!("获取锁失败: {}", e))?;
 
-        config.save()
+        service.save()
+    }
+
+    /// 在跨进程配置锁内执行“同步磁盘 - 修改 - 保存”
+    pub fn modify<T>(
+        &self,
+        f: impl FnOnce(&mut ProviderService) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut service = self
+            .service
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?;
+
+        service.modify(f)
+    }
+
+    /// 切换供应商，并执行切换前 / 后钩子与连通性检查（界面与托盘共用）
+    ///
+    /// 钩子与检查期间不持有服务锁，避免长时间命令或网络请求阻塞其他界面操作
+    pub async fn switch_provider(
+        &self,
+        app_type: &AppType,
+        id: &str,
+    ) -> Result<SwitchReport, String> {
+        let plan = self
+            .service
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?
+            .prepare_switch(app_type, id)?;
+        plan.execute(|| self.modify(|service| service.switch_provider(app_type, id)))
+            .await
+    }
+
+    /// 切换到供应商 `id` 并使用其备选地址 `url`（托盘端点菜单），供应商与地址在同一次修改中保存
+    ///
+    /// 供应商已是当前时只切换地址，不执行钩子与连通性检查；否则同 [`Self::switch_provider`]
+    pub async fn switch_provider_endpoint(
+        &self,
+        app_type: &AppType,
+        id: &str,
+        url: &str,
+    ) -> Result<SwitchReport, String> {
+        let plan = {
+            let service = self
+                .service
+                .lock()
+                .map_err(|e| format!("获取锁失败: {}", e))?;
+            if service.current_provider_id(app_type)? == id {
+                None
+            } else {
+                Some(service.prepare_switch(app_type, id)?)
+            }
+        };
+        let switch = || self.modify(|service| service.switch_provider_with_url(app_type, id, url));
+        match plan {
+            Some(plan) => plan.execute(switch).await,
+            None => Ok(SwitchReport {
+                provider: switch()?,
+                preflight: None,
+            }),
+        }
+    }
+
+    /// 临时切换供应商，`duration` 后自动恢复（钩子与检查同 [`Self::switch_provider`]）
+    pub async fn switch_provider_for(
+        &self,
+        app_type: &AppType,
+        id: &str,
+        duration: Duration,
+    ) -> Result<SwitchReport, String> {
+        let plan = self
+            .service
+            .lock()
+            .map_err(|e| format!("获取锁失败: {}", e))?
+            .prepare_switch(app_type, id)?;
+        plan.execute(|| self.modify(|service| service.switch_provider_for(app_type, id, duration)))
+            .await
     }
 }
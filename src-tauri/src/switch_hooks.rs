@@ -0,0 +1,425 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::app_config::AppType;
+use crate::preflight::{Preflight, PreflightResult};
+use crate::provider::Provider;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// 命令退出后等待输出读取完成的最长时间
+const OUTPUT_GRACE: Duration = Duration::from_millis(500);
+/// 超时上限（1 小时）：`timeoutSecs` 来自用户设置，更大的值按上限处理
+const MAX_TIMEOUT_SECS: u64 = 60 * 60;
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// 单条钩子命令（Unix 下由 `sh -c` 执行，Windows 下由 `cmd /C` 执行）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    pub command: String,
+    /// 超时秒数，超时后终止命令及其子进程并视为失败（默认 30，最长 1 小时）
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// 切换前 / 后执行的命令列表
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchHooks {
+    /// 切换前执行，任一命令失败（非零退出或超时）则放弃切换
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_switch: Vec<HookCommand>,
+    /// 配置保存后执行，失败只记录日志
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_switch: Vec<HookCommand>,
+}
+
+/// 单个应用的钩子：应用级命令先执行，再执行目标供应商自己的命令
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSwitchHooks {
+    #[serde(flatten)]
+    pub hooks: SwitchHooks,
+    /// 以供应商 ID 为键
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, SwitchHooks>,
+}
+
+/// 一次切换要执行的钩子及传给命令的环境变量
+#[derive(Debug, Clone, Default)]
+pub struct SwitchHookRun {
+    pre: Vec<HookCommand>,
+    post: Vec<HookCommand>,
+    env: Vec<(String, String)>,
+}
+
+impl SwitchHookRun {
+    /// 按设置收集切换到 `provider` 时的钩子
+    ///
+    /// 每次切换都重新读取 `settings.json`，手动编辑后无需重启
+    pub fn prepare(app_type: &AppType, provider: &Provider, previous_id: &str) -> Self {
+        let settings = crate::settings::AppSettings::load();
+        let Some(app_hooks) = settings.switch_hooks.get(app_type.as_str()) else {
+            return Self::default();
+        };
+        let provider_hooks = app_hooks.providers.get(&provider.id);
+        let collect = |pick: fn(&SwitchHooks) -> &Vec<HookCommand>| -> Vec<HookCommand> {
+            pick(&app_hooks.hooks)
+                .iter()
+                .chain(provider_hooks.map(pick).into_iter().flatten())
+                .filter(|h| !h.command.trim().is_empty())
+                .cloned()
+                .collect()
+        };
+
+        let env = vec![
+            ("CC_SWITCH_APP_TYPE", app_type.as_str().to_string()),
+            ("CC_SWITCH_PROVIDER_ID", provider.id.clone()),
+            ("CC_SWITCH_PROVIDER_NAME", provider.name.clone()),
+            (
+                "CC_SWITCH_BASE_URL",
+                provider.base_url(app_type).unwrap_or_default(),
+            ),
+            ("CC_SWITCH_PREVIOUS_PROVIDER_ID", previous_id.to_string()),
+        ];
+        Self {
+            pre: collect(|h| &h.pre_switch),
+            post: collect(|h| &h.post_switch),
+            env: env.into_iter().map(|(k, v)| (k.to_string(), v)).collect(),
+        }
+    }
+
+    /// 依次执行切换前钩子，遇到失败立即返回错误
+    pub async fn run_pre(&self) -> Result<(), String> {
+        if self.pre.is_empty() {
+            return Ok(());
+        }
+        let (hooks, env) = (self.pre.clone(), self.env.clone());
+        run_blocking(move || {
+            for hook in &hooks {
+                run_hook(hook, &env, "pre")
+                    .map_err(|e| format!("切换前钩子失败，已取消切换: {}", e))?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// 依次执行切换后钩子（失败不影响已完成的切换）
+    pub async fn run_post(&self) {
+        if self.post.is_empty() {
+            return;
+        }
+        let (hooks, env) = (self.post.clone(), self.env.clone());
+        let result = run_blocking(move || {
+            for hook in &hooks {
+                if let Err(e) = run_hook(hook, &env, "post") {
+                    log::warn!("切换后钩子失败: {}", e);
+                }
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(e) = result {
+            log::warn!("切换后钩子失败: {}", e);
+        }
+    }
+}
+
+/// 在阻塞线程池中执行钩子：命令最长可运行 `timeoutSecs` 秒，不能占用异步运行时的工作线程
+async fn run_blocking(
+    f: impl FnOnce() -> Result<(), String> + Send + 'static,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("钩子线程异常退出: {}", e))?
+}
+
+/// 切换结果：目标供应商与切换前连通性检查的结果（未开启检查时为 None）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchReport {
+    pub provider: Provider,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preflight: Option<PreflightResult>,
+}
+
+/// 一次切换在写入前后要做的事：钩子与连通性检查
+#[derive(Debug, Clone, Default)]
+pub struct SwitchPlan {
+    pub hooks: SwitchHookRun,
+    pub preflight: Option<Preflight>,
+}
+
+impl SwitchPlan {
+    /// 依次执行切换前钩子、连通性检查、`switch`（应完成切换并保存配置）与切换后钩子
+    ///
+    /// 钩子与检查在配置锁之外执行，钩子命令中可以再调用 `cc-switch-cli`；
+    /// 切换前钩子失败或 `block` 模式下检查失败时不做任何修改
+    pub async fn execute(
+        &self,
+        switch: impl FnOnce() -> Result<Provider, String>,
+    ) -> Result<SwitchReport, String> {
+        self.hooks.run_pre().await?;
+        let preflight = match &self.preflight {
+            Some(check) => Some(check.run().await?),
+            None => None,
+        };
+        let provider = switch()?;
+        self.hooks.run_post().await;
+        Ok(SwitchReport {
+            provider,
+            preflight,
+        })
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C").arg(command).creation_flags(CREATE_NO_WINDOW);
+        cmd
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        use std::os::unix::process::CommandExt;
+        // 独立进程组：超时时连同命令启动的子进程一起终止
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command).process_group(0);
+        cmd
+    }
+}
+
+/// 终止超时的命令及其启动的全部子进程（Unix 下整个进程组，Windows 下整个进程树），
+/// 避免遗留进程继续运行并占用输出管道
+fn kill_tree(child: &mut Child) {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let _ = Command::new("taskkill")
+            .args(["/PID", &child.id().to_string(), "/T", "/F"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .status();
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        // 进程组 ID 即 sh 的 PID
+        if let Ok(pgid) = i32::try_from(child.id()) {
+            // SAFETY: 仅向该命令所在的进程组发送信号，不涉及内存
+            unsafe {
+                libc::kill(-pgid, libc::SIGKILL);
+            }
+        }
+    }
+
+    let _ = child.kill();
+}
+
+/// 在后台读取子进程输出，避免管道写满导致命令阻塞
+fn spawn_reader<R: Read + Send + 'static>(pipe: Option<R>) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut out = String::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_string(&mut out);
+        }
+        let _ = tx.send(out);
+    });
+    rx
+}
+
+/// 执行单条钩子命令：非零退出或超时视为失败，输出写入日志
+fn run_hook(hook: &HookCommand, env: &[(String, String)], stage: &str) -> Result<(), String> {
+    let mut child = shell_command(&hook.command)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .env("CC_SWITCH_HOOK", stage)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{}: 启动失败: {}", hook.command, e))?;
+
+    let stdout = spawn_reader(child.stdout.take());
+    let stderr = spawn_reader(child.stderr.take());
+
+    let timeout_secs = hook.timeout_secs.min(MAX_TIMEOUT_SECS);
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                kill_tree(&mut child);
+                let _ = child.wait();
+                return Err(format!("{}: 超过 {} 秒未结束", hook.command, timeout_secs));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("{}: 等待命令结束失败: {}", hook.command, e)),
+        }
+    };
+
+    // 命令在后台启动的进程（如 `gateway &`）会继承输出管道，不等待其结束
+    let grace = Instant::now() + OUTPUT_GRACE;
+    let collect = |rx: mpsc::Receiver<String>| {
+        rx.recv_timeout(grace.saturating_duration_since(Instant::now()))
+            .unwrap_or_default()
+    };
+    let stdout = collect(stdout);
+    let stderr = collect(stderr);
+    if !stdout.trim().is_empty() {
+        log::info!("[{} hook] {}: {}", stage, hook.command, stdout.trim_end());
+    }
+    if status.success() {
+        if !stderr.trim().is_empty() {
+            log::warn!("[{} hook] {}: {}", stage, hook.command, stderr.trim_end());
+        }
+        Ok(())
+    } else {
+        let code = status
+            .code()
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "信号终止".to_string());
+        Err(format!(
+            "{}: 退出码 {}{}",
+            hook.command,
+            code,
+            if stderr.trim().is_empty() {
+                String::new()
+            } else {
+                format!("，{}", stderr.trim())
+            }
+        ))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn hook_run(pre: &[&str], post: &[&str]) -> SwitchHookRun {
+        let hooks = |commands: &[&str]| {
+            commands
+                .iter()
+                .map(|c| HookCommand {
+                    command: c.to_string(),
+                    timeout_secs: 5,
+                })
+                .collect()
+        };
+        SwitchHookRun {
+            pre: hooks(pre),
+            post: hooks(post),
+            env: vec![("CC_SWITCH_PROVIDER_ID".to_string(), "p1".to_string())],
+        }
+    }
+
+    fn provider() -> Provider {
+        Provider::with_id(
+            "p1".to_string(),
+            "P1".to_string(),
+            serde_json::json!({}),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn slow_hook_does_not_block_runtime() {
+        let run = hook_run(&["sleep 1"], &[]);
+        let ticks = Cell::new(0);
+        let ticker = async {
+            loop {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                ticks.set(ticks.get() + 1);
+            }
+        };
+        tokio::select! {
+            result = run.run_pre() => result.unwrap(),
+            _ = ticker => unreachable!(),
+        }
+        // 单线程运行时：钩子阻塞工作线程时计时任务无法推进
+        assert!(ticks.get() >= 5, "ticks: {}", ticks.get());
+    }
+
+    #[tokio::test]
+    async fn failing_pre_hook_cancels_switch() {
+        let plan = SwitchPlan {
+            hooks: hook_run(&["test \"$CC_SWITCH_PROVIDER_ID\" = other"], &[]),
+            preflight: None,
+        };
+        let switched = Cell::new(false);
+        let result = plan
+            .execute(|| {
+                switched.set(true);
+                Ok(provider())
+            })
+            .await;
+        assert!(result.unwrap_err().contains("切换前钩子失败"));
+        assert!(!switched.get());
+    }
+
+    #[test]
+    fn huge_timeout_is_clamped() {
+        let hook = HookCommand {
+            command: "true".to_string(),
+            timeout_secs: u64::MAX,
+        };
+        run_hook(&hook, &[], "pre").unwrap();
+    }
+
+    #[test]
+    fn timeout_kills_background_children() {
+        let pid_file = std::env::temp_dir().join(format!(
+            "cc-switch-hook-{}-{:?}.pid",
+            std::process::id(),
+            Instant::now()
+        ));
+        let hook = HookCommand {
+            command: format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+            timeout_secs: 1,
+        };
+        let started = Instant::now();
+        let error = run_hook(&hook, &[], "pre").unwrap_err();
+        assert!(error.contains("超过 1 秒"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let _ = std::fs::remove_file(&pid_file);
+        // 后台的 sleep 应已随进程组终止（已退出或仅剩僵尸进程）
+        let deadline = Instant::now() + Duration::from_secs(2);
+        loop {
+            let output = Command::new("ps")
+                .args(["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .unwrap();
+            let stat = String::from_utf8_lossy(&output.stdout);
+            if stat.trim().is_empty() || stat.trim().starts_with('Z') {
+                break;
+            }
+            assert!(Instant::now() < deadline, "后台进程仍在运行: {}", stat);
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    #[tokio::test]
+    async fn post_hook_failure_keeps_switch() {
+        let plan = SwitchPlan {
+            hooks: hook_run(&["true"], &["exit 3"]),
+            preflight: None,
+        };
+        let report = plan.execute(|| Ok(provider())).await.unwrap();
+        assert_eq!(report.provider.id, "p1");
+    }
+}
@@ -0,0 +1,188 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_config::AppType;
+use crate::config::{atomic_write, get_app_config_enc_path, get_switch_journal_path};
+
+/// 事务涉及的单个文件及其写入前的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalFile {
+    pub path: String,
+    /// 原内容（base64）；None 表示事务开始时文件不存在
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+}
+
+impl JournalFile {
+    fn capture(path: &Path) -> Result<Self, String> {
+        let before = if path.exists() {
+            let bytes =
+                fs::read(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+            Some(BASE64.encode(bytes))
+        } else {
+            None
+        };
+        Ok(Self {
+            path: path.to_string_lossy().to_string(),
+            before,
+        })
+    }
+
+    fn before_bytes(&self) -> Result<Option<Vec<u8>>, String> {
+        self.before
+            .as_deref()
+            .map(|b| {
+                BASE64
+                    .decode(b)
+                    .map_err(|e| format!("日志中 {} 的内容无效: {}", self.path, e))
+            })
+            .transpose()
+    }
+
+    /// 文件当前内容是否仍与事务开始前一致
+    fn unchanged(&self) -> Result<bool, String> {
+        let path = Path::new(&self.path);
+        let current = if path.exists() {
+            Some(fs::read(path).map_err(|e| format!("读取 {} 失败: {}", self.path, e))?)
+        } else {
+            None
+        };
+        Ok(current == self.before_bytes()?)
+    }
+
+    /// 恢复为事务开始前的内容（原本不存在的文件被删除）
+    fn restore(&self) -> Result<(), String> {
+        let path = Path::new(&self.path);
+        match self.before_bytes()? {
+            Some(bytes) => atomic_write(path, &bytes),
+            None if path.exists() => {
+                fs::remove_file(path).map_err(|e| format!("删除 {} 失败: {}", self.path, e))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// 切换供应商的预写日志（`~/.cc-switch/switch.journal.json`）
+///
+/// 写入任何 live 文件之前落盘，记录事务涉及的全部文件（live 配置、密钥库与 `config.enc.json`）的原内容；
+/// 配置保存成功后删除。`config.enc.json` 最后写入，作为事务的提交点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchJournal {
+    /// 开始时间（Unix 毫秒）
+    pub started_at: u64,
+    pub app_type: String,
+    /// 切换前的当前供应商
+    pub from_provider: String,
+    /// 目标供应商
+    pub to_provider: String,
+    /// live 配置与密钥库
+    pub files: Vec<JournalFile>,
+    /// `config.enc.json`（提交点）
+    pub config: JournalFile,
+}
+
+/// 启动时处理未完成切换的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalOutcome {
+    /// 配置已保存，只是日志未删除：切换已生效
+    Completed,
+    /// 配置未保存：live 文件已恢复为切换前的内容
+    RolledBack,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl SwitchJournal {
+    /// 记录 `paths` 与配置文件的当前内容并写入日志，之后才可开始写 live 文件
+    pub fn begin(
+        app_type: &AppType,
+        from_provider: &str,
+        to_provider: &str,
+        paths: &[PathBuf],
+    ) -> Result<Self, String> {
+        let files = paths
+            .iter()
+            .map(|p| JournalFile::capture(p))
+            .collect::<Result<Vec<_>, String>>()?;
+        let journal = Self {
+            started_at: now_millis(),
+            app_type: app_type.as_str().to_string(),
+            from_provider: from_provider.to_string(),
+            to_provider: to_provider.to_string(),
+            files,
+            config: JournalFile::capture(&get_app_config_enc_path())?,
+        };
+        let json = serde_json::to_string_pretty(&journal)
+            .map_err(|e| format!("序列化切换日志失败: {}", e))?;
+        // 日志包含 live 配置中的明文密钥，与密钥库同样以 0600 写入
+        crate::secure_store::write_private_file(&get_switch_journal_path(), json.as_bytes())?;
+        Ok(journal)
+    }
+
+    /// 配置已保存：删除日志
+    pub fn commit(self) -> Result<(), String> {
+        remove_journal()
+    }
+
+    /// 把 live 文件与密钥库恢复为切换前的内容并删除日志（`config.enc.json` 未保存，无需恢复）
+    pub fn rollback(self) -> Result<(), String> {
+        for file in &self.files {
+            file.restore()?;
+        }
+        remove_journal()
+    }
+}
+
+fn remove_journal() -> Result<(), String> {
+    let path = get_switch_journal_path();
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除切换日志失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 读取上次未完成的切换日志（没有时返回 None）
+fn read_pending() -> Result<Option<SwitchJournal>, String> {
+    let path = get_switch_journal_path();
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("读取切换日志失败: {}", e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("解析切换日志失败: {}", e))
+}
+
+/// 处理上次进程在切换中途退出留下的日志（需在配置锁内、加载配置前调用）
+///
+/// 配置文件已不同于日志记录的原内容，说明保存已完成，切换视为完成；
+/// 否则把 live 文件恢复为切换前的内容，使其与配置中的当前供应商一致
+pub fn recover() -> Result<Option<JournalOutcome>, String> {
+    let Some(journal) = read_pending()? else {
+        return Ok(None);
+    };
+    let summary = format!(
+        "{} 供应商 {} -> {}",
+        journal.app_type, journal.from_provider, journal.to_provider
+    );
+    if journal.config.unchanged()? {
+        let count = journal.files.len();
+        journal.rollback()?;
+        log::warn!("上次切换 {} 未完成，已回滚 {} 个文件", summary, count);
+        Ok(Some(JournalOutcome::RolledBack))
+    } else {
+        journal.commit()?;
+        log::warn!("上次切换 {} 已保存但日志未清理，视为已完成", summary);
+        Ok(Some(JournalOutcome::Completed))
+    }
+}
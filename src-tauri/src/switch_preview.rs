@@ -0,0 +1,161 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// 文件内容格式，决定是否生成结构化补丁
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileFormat {
+    Json,
+    Toml,
+    /// shell 配置等纯文本，只提供文本差异
+    Text,
+}
+
+/// RFC 6902 JSON Patch 操作（`path` 为 JSON Pointer；数组整体替换）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+/// 切换涉及的单个文件：写入前后的完整内容、结构化补丁与统一格式文本差异
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePreview {
+    pub path: String,
+    pub format: FileFormat,
+    /// 文件当前是否存在（不存在时 `before` 为空字符串）
+    pub exists: bool,
+    pub before: String,
+    pub after: String,
+    pub changed: bool,
+    /// 仅 JSON / TOML 且两侧都能解析时提供
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub patch: Option<Vec<PatchOp>>,
+    /// 统一格式差异（与 `diff -u` 相同），无变化时为空字符串
+    pub diff: String,
+}
+
+impl FilePreview {
+    /// 由磁盘上的当前内容与将要写入的内容生成预览
+    pub fn new(path: &Path, format: FileFormat, after: String) -> Result<Self, String> {
+        let exists = path.exists();
+        let before = if exists {
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?
+        } else {
+            String::new()
+        };
+        Ok(Self::from_contents(path, format, exists, before, after))
+    }
+
+    /// 由给定的前后内容生成预览（不读取文件）
+    pub fn from_contents(
+        path: &Path,
+        format: FileFormat,
+        exists: bool,
+        before: String,
+        after: String,
+    ) -> Self {
+        let display = path.display().to_string();
+        let changed = before != after;
+        let patch = match (parse(format, &before), parse(format, &after)) {
+            (Some(a), Some(b)) => Some(json_patch(&a, &b)),
+            _ => None,
+        };
+        let diff = if changed {
+            unified_diff(&display, &before, &after)
+        } else {
+            String::new()
+        };
+        Self {
+            path: display,
+            format,
+            exists,
+            before,
+            after,
+            changed,
+            patch,
+            diff,
+        }
+    }
+}
+
+/// 一次切换的预演结果（不写入任何文件）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchPreview {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub files: Vec<FilePreview>,
+    /// 不以文件形式体现的改动说明（如 Windows 注册表环境变量）
+    pub notes: Vec<String>,
+}
+
+/// 解析为 JSON 值；空文本视为空对象，解析失败返回 None
+fn parse(format: FileFormat, text: &str) -> Option<Value> {
+    if text.trim().is_empty() {
+        return match format {
+            FileFormat::Text => None,
+            _ => Some(Value::Object(Default::default())),
+        };
+    }
+    match format {
+        FileFormat::Json => serde_json::from_str(text).ok(),
+        FileFormat::Toml => {
+            let table: toml::Value = toml::from_str(text).ok()?;
+            serde_json::to_value(table).ok()
+        }
+        FileFormat::Text => None,
+    }
+}
+
+/// 生成把 `before` 变为 `after` 的 JSON Patch（对象逐键递归，其他值整体替换）
+fn json_patch(before: &Value, after: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    patch_into(before, after, "", &mut ops);
+    ops
+}
+
+fn patch_into(before: &Value, after: &Value, path: &str, out: &mut Vec<PatchOp>) {
+    if before == after {
+        return;
+    }
+    match (before, after) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: HashSet<&String> = a.keys().chain(b.keys()).collect();
+            let mut keys: Vec<&String> = keys.into_iter().collect();
+            keys.sort();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => patch_into(x, y, &child, out),
+                    (None, Some(y)) => out.push(PatchOp::Add {
+                        path: child,
+                        value: y.clone(),
+                    }),
+                    (Some(_), None) => out.push(PatchOp::Remove { path: child }),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ => out.push(PatchOp::Replace {
+            path: path.to_string(),
+            value: after.clone(),
+        }),
+    }
+}
+
+/// 统一格式文本差异（3 行上下文）
+fn unified_diff(path: &str, before: &str, after: &str) -> String {
+    similar::TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(3)
+        .header(path, path)
+        .to_string()
+}
@@ -0,0 +1,49 @@
+//! 测试辅助：在临时 HOME 下运行依赖 `~/.cc-switch`、`~/.claude` 等路径的测试
+
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// HOME 是进程级环境变量，使用临时 HOME 的测试必须串行执行
+static HOME_LOCK: Mutex<()> = Mutex::new(());
+
+/// 临时 HOME：存续期间 `HOME` 指向新建的空目录，析构时恢复原值并删除目录
+pub struct TempHome {
+    path: PathBuf,
+    original: Option<String>,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl TempHome {
+    pub fn new() -> Self {
+        let guard = HOME_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path =
+            std::env::temp_dir().join(format!("cc-switch-test-{}-{}", std::process::id(), nanos));
+        std::fs::create_dir_all(&path).expect("创建临时 HOME 失败");
+        let original = std::env::var("HOME").ok();
+        std::env::set_var("HOME", &path);
+        std::env::remove_var(crate::secure_store::PASSPHRASE_ENV);
+        Self {
+            path,
+            original,
+            _guard: guard,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempHome {
+    fn drop(&mut self) {
+        match &self.original {
+            Some(home) => std::env::set_var("HOME", home),
+            None => std::env::remove_var("HOME"),
+        }
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -1,6 +1,6 @@
 import { useState, useEffect, useRef } from "react";
 import { useTranslation } from "react-i18next";
-import { Provider } from "./types";
+import { PreflightResult, Provider, TemporarySwitch } from "./types";
 import { AppType } from "./lib/tauri-api";
 import ProviderList from "./components/ProviderList";
 import AddProviderModal from "./components/AddProviderModal";
@@ -19,12 +19,17 @@ import { applyProviderToVSCode } from "./utils/vscodeSettings";
 import { getCodexBaseUrl } from "./utils/providerConfigUtils";
 import { useVSCodeAutoSync } from "./hooks/useVSCodeAutoSync";
 
+// 界面中临时切换的时长（分钟）
+const TEMPORARY_SWITCH_MINUTES = 30;
+
 function App() {
   const { t } = useTranslation();
   const { isDarkMode, toggleDarkMode } = useDarkMode();
   const { isAutoSyncEnabled } = useVSCodeAutoSync();
   const [activeApp, setActiveApp] = useState<AppType>("claude");
   const [providers, setProviders] = useState<Record<string, Provider>>({});
+  const [temporarySwitch, setTemporarySwitch] =
+    useState<TemporarySwitch | null>(null);
   const [currentProviderId, setCurrentProviderId] = useState<string>("");
   const [isAddModalOpen, setIsAddModalOpen] = useState(false);
   const [isBatchAddModalOpen, setIsBatchAddModalOpen] = useState(false);
@@ -86,6 +91,53 @@ function App() {
     };
   }, []);
 
+  // 启动时检查配置是否经过恢复（损坏后从备份恢复或进入只读模式）
+  useEffect(() => {
+    const checkRecovery = async () => {
+      const report = await window.api.getConfigRecoveryStatus();
+      if (!report) return;
+      if (report.readOnly) {
+        showNotification(
+          t("notifications.configReadOnly", { error: report.error }),
+          "error",
+          10000,
+        );
+      } else {
+        showNotification(
+          t(
+            report.source === "archive"
+              ? "notifications.configRecoveredFromArchive"
+              : "notifications.configRecoveredFromBackup",
+            { path: report.sourcePath ?? "" },
+          ),
+          "success",
+          6000,
+        );
+      }
+    };
+    checkRecovery();
+  }, []);
+
+  // 切换前连通性检查未通过（warn 模式下仍已切换）时的提示
+  const notifyPreflightWarning = (
+    appType: string,
+    preflight?: PreflightResult | null,
+  ) => {
+    if (!preflight || ["ok", "skipped"].includes(preflight.status)) {
+      return false;
+    }
+    showNotification(
+      t("notifications.switchPreflightWarning", {
+        appName: t(`apps.${appType}`),
+        status: t(`preflight.status.${preflight.status}`),
+        message: preflight.message,
+      }),
+      "error",
+      6000,
+    );
+    return true;
+  };
+
   // 监听托盘切换事件（包括菜单切换）
   useEffect(() => {
     let unlisten: (() => void) | null = null;
@@ -97,6 +149,8 @@ function App() {
             console.log(t("console.providerSwitchReceived"), data);
           }
 
+          notifyPreflightWarning(data.appType, data.preflight);
+
           // 如果当前应用类型匹配，则重新加载数据
           if (data.appType === activeApp) {
             await loadProviders();
@@ -126,6 +180,98 @@ function App() {
     };
   }, [activeApp, isAutoSyncEnabled]);
 
+  // 监听 config.enc.json 的外部修改（其他进程、git pull 等），后端热重载后刷新列表
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    const setupListener = async () => {
+      try {
+        unlisten = await window.api.onAppConfigReloaded(async () => {
+          await loadProviders();
+        });
+      } catch (error) {
+        console.error(t("console.setupListenerFailed"), error);
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [activeApp]);
+
+  // 监听备选地址自动故障转移：刷新列表并提示切换原因
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    const setupListener = async () => {
+      try {
+        unlisten = await window.api.onEndpointFailover(async (data) => {
+          if (data.appType === activeApp) {
+            await loadProviders();
+          }
+          showNotification(
+            t("notifications.endpointFailover", {
+              name: data.providerName,
+              url: data.toUrl,
+              reason: data.reason,
+            }),
+            "error",
+            6000,
+          );
+        });
+      } catch (error) {
+        console.error(t("console.setupListenerFailed"), error);
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [activeApp]);
+
+  // 监听 live 配置文件的外部修改（手动编辑、claude mcp add 等）
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    const setupListener = async () => {
+      try {
+        unlisten = await window.api.onLiveConfigChanged(async (data) => {
+          if (data.synced && data.appType === activeApp) {
+            await loadProviders();
+          }
+          showNotification(
+            t(
+              data.synced
+                ? "notifications.liveConfigSynced"
+                : "notifications.liveConfigChanged",
+              { path: data.path, count: data.changes.length },
+            ),
+            "success",
+            4000,
+          );
+        });
+      } catch (error) {
+        console.error(t("console.setupListenerFailed"), error);
+      }
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [activeApp]);
+
   const loadProviders = async () => {
     console.log(`[App] 加载供应商列表，应用类型: ${activeApp}`);
     const loadedProviders = await window.api.getProviders(activeApp);
@@ -134,6 +280,7 @@ function App() {
     console.log(`[App] 当前供应商 ID: ${currentId}`);
     setProviders(loadedProviders);
     setCurrentProviderId(currentId);
+    setTemporarySwitch(await window.api.getTemporarySwitch(activeApp));
 
     // 如果供应商列表为空，尝试自动从 live 导入一条默认供应商
     if (Object.keys(loadedProviders).length === 0) {
@@ -336,16 +483,31 @@ function App() {
   };
 
   const handleSwitchProvider = async (id: string) => {
-    const success = await window.api.switchProvider(id, activeApp);
-    if (success) {
-      setCurrentProviderId(id);
-      // 显示重启提示
-      const appName = t(`apps.${activeApp}`);
+    let report;
+    try {
+      report = await window.api.switchProvider(id, activeApp);
+    } catch (error) {
+      // 后端错误（如切换前钩子或连通性检查失败）直接展示原因
       showNotification(
-        t("notifications.switchSuccess", { appName }),
-        "success",
-        2000,
+        t("notifications.switchFailedWithError", { error: String(error) }),
+        "error",
+        5000,
       );
+      return;
+    }
+    if (report) {
+      setCurrentProviderId(id);
+      // 普通切换会结束进行中的临时切换
+      setTemporarySwitch(null);
+      // 显示重启提示（连通性检查未通过时改为提示检查结果）
+      if (!notifyPreflightWarning(activeApp, report.preflight)) {
+        const appName = t(`apps.${activeApp}`);
+        showNotification(
+          t("notifications.switchSuccess", { appName }),
+          "success",
+          2000,
+        );
+      }
       // 更新托盘菜单
       await window.api.updateTrayMenu();
 
@@ -362,12 +524,55 @@ function App() {
     }
   };
 
+  // 临时切换：到期后由后台自动恢复为切换前的供应商
+  const handleSwitchProviderTemporarily = async (id: string) => {
+    try {
+      const report = await window.api.switchProviderFor(
+        id,
+        TEMPORARY_SWITCH_MINUTES * 60,
+        activeApp,
+      );
+      setCurrentProviderId(id);
+      setTemporarySwitch(await window.api.getTemporarySwitch(activeApp));
+      if (!notifyPreflightWarning(activeApp, report.preflight)) {
+        showNotification(
+          t("notifications.temporarySwitchSuccess", {
+            name: report.provider.name,
+            minutes: TEMPORARY_SWITCH_MINUTES,
+          }),
+          "success",
+          3000,
+        );
+      }
+      await window.api.updateTrayMenu();
+    } catch (error) {
+      showNotification(
+        t("notifications.switchFailedWithError", { error: String(error) }),
+        "error",
+        5000,
+      );
+    }
+  };
+
+  // 放弃临时切换：保留当前供应商
+  const handleCancelTemporarySwitch = async () => {
+    try {
+      await window.api.cancelTemporarySwitch(activeApp);
+      setTemporarySwitch(null);
+      showNotification(t("notifications.temporarySwitchCanceled"), "success");
+      await window.api.updateTrayMenu();
+    } catch (error) {
+      showNotification(String(error), "error", 5000);
+    }
+  };
+
   // 停用当前供应商（清空 env 字段）
   const handleDisableProvider = async () => {
     try {
       const success = await window.api.disableCurrentProvider(activeApp);
       if (success) {
         setCurrentProviderId("");
+        setTemporarySwitch(null);
         showNotification(t("notifications.disableSuccess"), "success", 2000);
         // 更新托盘菜单
         await window.api.updateTrayMenu();
@@ -487,6 +692,9 @@ function App() {
               providers={providers}
               currentProviderId={currentProviderId}
               onSwitch={handleSwitchProvider}
+              temporarySwitch={temporarySwitch}
+              onSwitchTemporarily={handleSwitchProviderTemporarily}
+              onCancelTemporary={handleCancelTemporarySwitch}
               onDisable={handleDisableProvider}
               onDelete={handleDeleteProvider}
               onEdit={setEditingProviderId}
@@ -7,9 +7,14 @@ import {
   getApiKeyFromConfig,
   hasApiKeyField,
   setApiKeyInConfig,
+  getCredentialKindFromConfig,
+  setCredentialKindInConfig,
+  ClaudeCredentialKind,
   updateTomlCommonConfigSnippet,
   hasTomlCommonConfigSnippet,
   validateJsonConfig,
+  extractCodexBaseUrl,
+  setCodexBaseUrl,
 } from "../utils/providerConfigUtils";
 import { providerPresets } from "../config/providerPresets";
 import {
@@ -81,10 +86,28 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
   const [alternativeUrls, setAlternativeUrls] = useState<string[]>(
     initialData?.alternativeUrls || [],
   );
+  // 当前地址异常时自动切换到备选地址（Claude 专用）
+  const [failoverEnabled, setFailoverEnabled] = useState(
+    initialData?.failover?.enabled ?? false,
+  );
+  // Codex 只合并供应商相关字段，保留 config.toml 中的个人设置
+  const [codexSectionMode, setCodexSectionMode] = useState(
+    initialData?.codexConfigMode === "section",
+  );
+  // profile 模式下把 API Key 明文写入 config.toml（默认写入 env_key）
+  const [codexInlineToken, setCodexInlineToken] = useState(
+    initialData?.codexInlineToken ?? false,
+  );
+  // 由供应商管理的 settings.json 顶层字段（逗号分隔输入）
+  const [managedPaths, setManagedPaths] = useState(
+    (initialData?.managedPaths || []).join(", "),
+  );
 
   // Codex 特有的状态
   const [codexAuth, setCodexAuthState] = useState("");
   const [codexConfig, setCodexConfigState] = useState("");
+  // Codex: 当前 config.toml 中的 base_url（用于备选地址）
+  const codexBaseUrl = extractCodexBaseUrl(codexConfig) ?? "";
   const [codexApiKey, setCodexApiKey] = useState("");
   const [isCodexTemplateModalOpen, setIsCodexTemplateModalOpen] =
     useState(false);
@@ -463,29 +486,62 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
       }
     }
 
-    // 确保当前 baseUrl 包含在 alternativeUrls 中（Claude 专用）
+    // 确保当前地址包含在 alternativeUrls 中（Claude 与 Codex）
+    const currentBaseUrl = isCodex ? codexBaseUrl : baseUrl;
     let finalAlternativeUrls = [...alternativeUrls];
     if (
-      !isCodex &&
       !isDroid &&
-      baseUrl &&
-      baseUrl.trim() &&
-      (baseUrl.startsWith("http://") || baseUrl.startsWith("https://")) &&
-      !finalAlternativeUrls.includes(baseUrl)
+      currentBaseUrl &&
+      currentBaseUrl.trim() &&
+      (currentBaseUrl.startsWith("http://") ||
+        currentBaseUrl.startsWith("https://")) &&
+      !finalAlternativeUrls.includes(currentBaseUrl)
     ) {
-      finalAlternativeUrls = [baseUrl, ...finalAlternativeUrls];
+      finalAlternativeUrls = [currentBaseUrl, ...finalAlternativeUrls];
     }
 
+    const finalManagedPaths = managedPaths
+      .split(/[,，\s]+/)
+      .map((p) => p.trim())
+      .filter((p) => p && p !== "env");
+
     const submitData = {
       name: formData.name,
       websiteUrl: formData.websiteUrl,
       settingsConfig,
       // 仅在用户选择了预设或手动选择"自定义"时持久化分类
       ...(category ? { category } : {}),
-      // 保存备选地址列表（仅 Claude 且有地址时添加）
-      ...(!isCodex && !isDroid && finalAlternativeUrls.length > 0
+      // 保存备选地址列表（Claude 与 Codex 且有地址时添加）
+      ...(!isDroid && finalAlternativeUrls.length > 0
         ? { alternativeUrls: finalAlternativeUrls }
         : {}),
+      // 保留高级参数（间隔、阈值、冷却），只切换开关
+      ...(!isDroid
+        ? {
+            failover:
+              failoverEnabled || initialData?.failover
+                ? { ...initialData?.failover, enabled: failoverEnabled }
+                : undefined,
+          }
+        : {}),
+      // 受管顶层字段与凭据类型（仅 Claude；显式写 undefined 以便编辑时清除旧值，官方登录不限制凭据类型）
+      ...(!isCodex && !isDroid
+        ? {
+            managedPaths:
+              finalManagedPaths.length > 0 ? finalManagedPaths : undefined,
+            credentialKind: hasApiKeyField(formData.settingsConfig)
+              ? getCredentialKindFromConfig(formData.settingsConfig)
+              : undefined,
+          }
+        : {}),
+      ...(isCodex
+        ? {
+            codexConfigMode: codexSectionMode
+              ? ("section" as const)
+              : undefined,
+            codexInlineToken: codexInlineToken || undefined,
+          }
+        : {}),
     };
 
     console.log(`[${appType}] 提交数据:`, submitData);
@@ -765,6 +821,13 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
     setUseCommonConfig(hasCommon);
   };
 
+  // 切换凭据类型（ANTHROPIC_AUTH_TOKEN / ANTHROPIC_API_KEY），已填写的 Key 随之移动
+  const handleCredentialKindChange = (kind: ClaudeCredentialKind) => {
+    updateSettingsConfigValue(
+      setCredentialKindInConfig(formData.settingsConfig, kind),
+    );
+  };
+
   // 处理基础 URL 变化
   const handleBaseUrlChange = (url: string) => {
     setBaseUrl(url);
@@ -804,6 +867,19 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
     handleBaseUrlChange(url);
   };
 
+  // Codex: 从下拉菜单选择地址（先保存当前地址再改写 config.toml 中的 base_url）
+  const handleCodexBaseUrlSelect = (url: string) => {
+    if (
+      codexBaseUrl &&
+      (codexBaseUrl.startsWith("http://") ||
+        codexBaseUrl.startsWith("https://")) &&
+      !alternativeUrls.includes(codexBaseUrl)
+    ) {
+      setAlternativeUrls([...alternativeUrls, codexBaseUrl]);
+    }
+    handleCodexConfigChange(setCodexBaseUrl(codexConfig, url));
+  };
+
   // Codex: 处理 API Key 输入并写回 auth.json
   const handleCodexApiKeyChange = (key: string) => {
     setCodexApiKey(key);
@@ -1241,6 +1317,33 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
                   }
                   disabled={isOfficialPreset}
                 />
+                {!isOfficialPreset && (
+                  <div className="flex items-center gap-2 pl-1">
+                    <label
+                      htmlFor="credentialKind"
+                      className="text-xs text-gray-500 dark:text-gray-400"
+                    >
+                      认证方式
+                    </label>
+                    <select
+                      id="credentialKind"
+                      value={getCredentialKindFromConfig(
+                        formData.settingsConfig,
+                      )}
+                      onChange={(e) =>
+                        handleCredentialKindChange(
+                          e.target.value as ClaudeCredentialKind,
+                        )
+                      }
+                      className="px-2 py-1 border border-gray-200 dark:border-gray-700 rounded-md text-xs dark:bg-gray-800 dark:text-gray-100 focus:outline-none focus:ring-2 focus:ring-blue-500/20"
+                    >
+                      <option value="authToken">
+                        ANTHROPIC_AUTH_TOKEN（Bearer）
+                      </option>
+                      <option value="apiKey">ANTHROPIC_API_KEY（x-api-key）</option>
+                    </select>
+                  </div>
+                )}
                 {shouldShowApiKeyLink && getCurrentWebsiteUrl() && (
                   <div className="-mt-1 pl-1">
                     <a
@@ -1294,6 +1397,15 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
                       : '填写兼容 Claude API 的服务端点地址。点击"快捷选择"可保存常用地址'}
                   </p>
                 </div>
+                <label className="flex items-center gap-2 text-xs text-gray-600 dark:text-gray-400">
+                  <input
+                    type="checkbox"
+                    checked={failoverEnabled}
+                    onChange={(e) => setFailoverEnabled(e.target.checked)}
+                    className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                  />
+                  地址异常时自动切换到最快的备选地址（需保存至少两个地址）
+                </label>
               </div>
             )}
 
@@ -1341,6 +1453,57 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
               </div>
             )}
 
+            {isCodex && (
+              <label className="flex items-center gap-2 text-xs text-gray-600 dark:text-gray-400">
+                <input
+                  type="checkbox"
+                  checked={codexSectionMode}
+                  onChange={(e) => setCodexSectionMode(e.target.checked)}
+                  className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                />
+                分段模式：config.toml 只写入 model、model_provider 与
+                [model_providers.*]，保留其余个人设置
+              </label>
+            )}
+
+            {isCodex && (
+              <label className="flex items-center gap-2 text-xs text-gray-600 dark:text-gray-400">
+                <input
+                  type="checkbox"
+                  checked={codexInlineToken}
+                  onChange={(e) => setCodexInlineToken(e.target.checked)}
+                  className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                />
+                生成 profile 时将 API Key 明文写入 config.toml（默认写入
+                env_key，需自行导出环境变量）
+              </label>
+            )}
+
+            {isCodex && codexBaseUrl && (
+              <div className="space-y-2">
+                <div className="flex items-center justify-between gap-2">
+                  <span className="text-sm text-gray-900 dark:text-gray-100 truncate">
+                    请求地址 (base_url)：{codexBaseUrl}
+                  </span>
+                  <BaseUrlSelector
+                    urls={alternativeUrls}
+                    currentUrl={codexBaseUrl}
+                    onSelect={handleCodexBaseUrlSelect}
+                    onChange={setAlternativeUrls}
+                  />
+                </div>
+                <label className="flex items-center gap-2 text-xs text-gray-600 dark:text-gray-400">
+                  <input
+                    type="checkbox"
+                    checked={failoverEnabled}
+                    onChange={(e) => setFailoverEnabled(e.target.checked)}
+                    className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                  />
+                  地址异常时自动切换到最快的备选地址（需保存至少两个地址）
+                </label>
+              </div>
+            )}
+
             {/* Claude、Codex 或 Droid 的配置部分 */}
             {isDroid ? (
               <DroidConfigEditor
@@ -1474,6 +1637,27 @@ const ProviderForm: React.FC<ProviderFormProps> = ({
                   commonConfigError={commonConfigError}
                   configError={settingsConfigError}
                 />
+
+                <div className="space-y-2">
+                  <label
+                    htmlFor="managedPaths"
+                    className="block text-sm font-medium text-gray-900 dark:text-gray-100"
+                  >
+                    受管设置字段 (可选)
+                  </label>
+                  <input
+                    type="text"
+                    id="managedPaths"
+                    value={managedPaths}
+                    onChange={(e) => setManagedPaths(e.target.value)}
+                    placeholder="例如: model, permissions, statusLine"
+                    autoComplete="off"
+                    className="w-full px-3 py-2 border border-gray-200 dark:border-gray-700 dark:bg-gray-800 dark:text-gray-100 rounded-lg text-sm focus:outline-none focus:ring-2 focus:ring-blue-500/20 dark:focus:ring-blue-400/20 focus:border-blue-500 dark:focus:border-blue-400 transition-colors"
+                  />
+                  <p className="text-xs text-gray-500 dark:text-gray-400">
+                    切换时将上方配置中的这些顶层字段写入 settings.json，切走时移除并回填；未列出的字段不会改动
+                  </p>
+                </div>
               </>
             )}
           </div>
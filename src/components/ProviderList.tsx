@@ -1,7 +1,18 @@
 import React, { useEffect, useState } from "react";
 import { useTranslation } from "react-i18next";
-import { Provider } from "../types";
-import { Play, Edit3, Trash2, CheckCircle2, Users, Check, Zap, Loader2 } from "lucide-react";
+import { Provider, TemporarySwitch } from "../types";
+import {
+  Play,
+  Edit3,
+  Trash2,
+  CheckCircle2,
+  Users,
+  Check,
+  Zap,
+  Loader2,
+  Timer,
+  X,
+} from "lucide-react";
 import { buttonStyles, cardStyles, badgeStyles, cn } from "../lib/styles";
 import { AppType, EndpointLatency, BalanceInfo } from "../lib/tauri-api";
 import { BalanceDisplay } from "./BalanceDisplay";
@@ -18,6 +29,10 @@ interface ProviderListProps {
   providers: Record<string, Provider>;
   currentProviderId: string;
   onSwitch: (id: string) => void;
+  // 进行中的临时切换（到期后自动恢复）
+  temporarySwitch?: TemporarySwitch | null;
+  onSwitchTemporarily?: (id: string) => void;
+  onCancelTemporary?: () => void;
   onDisable: () => void;
   onDelete: (id: string) => void;
   onEdit: (id: string) => void;
@@ -33,6 +48,9 @@ const ProviderList: React.FC<ProviderListProps> = ({
   providers,
   currentProviderId,
   onSwitch,
+  temporarySwitch,
+  onSwitchTemporarily,
+  onCancelTemporary,
   onDisable,
   onDelete,
   onEdit,
@@ -234,6 +252,15 @@ const ProviderList: React.FC<ProviderListProps> = ({
   };
 
   // 测速状态管理
+  // 临时切换倒计时：每 30 秒刷新一次
+  const [now, setNow] = useState(() => Date.now());
+  useEffect(() => {
+    if (!temporarySwitch) return;
+    setNow(Date.now());
+    const timer = window.setInterval(() => setNow(Date.now()), 30000);
+    return () => window.clearInterval(timer);
+  }, [temporarySwitch]);
+
   const [testingProviderId, setTestingProviderId] = useState<string | null>(null);
   const [speedTestResults, setSpeedTestResults] = useState<Record<string, EndpointLatency>>({});
 
@@ -388,6 +415,8 @@ const ProviderList: React.FC<ProviderListProps> = ({
           {sortedProviders.map((provider) => {
             const isCurrent = provider.id === currentProviderId;
             const apiUrl = getApiUrl(provider);
+            const isTemporary =
+              isCurrent && temporarySwitch?.providerId === provider.id;
 
             return (
               <div
@@ -412,6 +441,31 @@ const ProviderList: React.FC<ProviderListProps> = ({
                         <CheckCircle2 size={12} />
                         {t("provider.currentlyUsing")}
                       </div>
+                      {isTemporary && temporarySwitch && (
+                        <div className={badgeStyles.warning}>
+                          <Timer size={12} />
+                          {t("provider.temporaryRevertIn", {
+                            minutes: Math.max(
+                              1,
+                              Math.ceil(
+                                (temporarySwitch.revertAt - now) / 60000,
+                              ),
+                            ),
+                            name:
+                              providers[temporarySwitch.previousProviderId]
+                                ?.name ?? temporarySwitch.previousProviderId,
+                          })}
+                          {onCancelTemporary && (
+                            <button
+                              onClick={onCancelTemporary}
+                              className="ml-0.5 hover:text-amber-600"
+                              title={t("provider.cancelTemporary")}
+                            >
+                              <X size={12} />
+                            </button>
+                          )}
+                        </div>
+                      )}
                     </div>
 
                     <div className="flex items-center gap-2 text-sm">
@@ -528,7 +582,18 @@ const ProviderList: React.FC<ProviderListProps> = ({
                       )}
                     </button>
 
-                    {(appType === "claude" || appType === "droid") && isCurrent ? (
+                    {/* 临时使用：到期后恢复为当前供应商 */}
+                    {onSwitchTemporarily && !isCurrent && currentProviderId && (
+                      <button
+                        onClick={() => onSwitchTemporarily(provider.id)}
+                        className={buttonStyles.icon}
+                        title={t("provider.switchTemporarily")}
+                      >
+                        <Timer size={16} />
+                      </button>
+                    )}
+
+                    {isCurrent ? (
                       <button
                         onClick={onDisable}
                         className="inline-flex items-center gap-1.5 px-3 py-1.5 text-sm font-medium rounded-md transition-colors w-[90px] justify-center whitespace-nowrap bg-orange-500 text-white hover:bg-orange-600 dark:bg-orange-600 dark:hover:bg-orange-700"
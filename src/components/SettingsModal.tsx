@@ -16,7 +16,7 @@ import { homeDir, join } from "@tauri-apps/api/path";
 import "../lib/tauri-api";
 import { relaunchApp } from "../lib/updater";
 import { useUpdate } from "../contexts/UpdateContext";
-import type { Settings } from "../types";
+import type { PreflightMode, ScheduledSwitch, Settings } from "../types";
 import type { AppType } from "../lib/tauri-api";
 import { isLinux } from "../lib/platform";
 
@@ -62,6 +62,7 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
   const [resolvedClaudeDir, setResolvedClaudeDir] = useState<string>("");
   const [resolvedCodexDir, setResolvedCodexDir] = useState<string>("");
   const [isPortable, setIsPortable] = useState(false);
+  const [nextSwitch, setNextSwitch] = useState<ScheduledSwitch | null>(null);
   const { hasUpdate, updateInfo, updateHandle, checkUpdate, resetDismiss } =
     useUpdate();
 
@@ -71,6 +72,7 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
     loadVersion();
     loadResolvedDirs();
     loadPortableFlag();
+    loadNextSwitch();
   }, []);
 
   const loadVersion = async () => {
@@ -102,6 +104,8 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
       );
 
       setSettings({
+        // 保留界面未展示的设置项（如快照保留策略），避免保存时被重置
+        ...(loadedSettings as Settings),
         showInTray,
         minimizeToTrayOnClose,
         claudeConfigDir:
@@ -164,6 +168,14 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
     }
   };
 
+  const loadNextSwitch = async () => {
+    try {
+      setNextSwitch(await window.api.nextScheduledSwitch());
+    } catch (error) {
+      console.error(t("console.loadNextSwitchFailed"), error);
+    }
+  };
+
   const saveSettings = async () => {
     try {
       const selectedLanguage = settings.language === "en" ? "en" : "zh";
@@ -463,9 +475,109 @@ export default function SettingsModal({ onClose }: SettingsModalProps) {
                   className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
                 />
               </label>
+              <label className="flex items-center justify-between">
+                <div>
+                  <span className="text-sm text-gray-900 dark:text-gray-100">
+                    {t("settings.autoSyncLiveConfig")}
+                  </span>
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
+                    {t("settings.autoSyncLiveConfigDescription")}
+                  </p>
+                </div>
+                <input
+                  type="checkbox"
+                  checked={settings.autoSyncLiveConfig ?? false}
+                  onChange={(e) =>
+                    setSettings((prev) => ({
+                      ...prev,
+                      autoSyncLiveConfig: e.target.checked,
+                    }))
+                  }
+                  className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                />
+              </label>
+              <label className="flex items-center justify-between">
+                <div>
+                  <span className="text-sm text-gray-900 dark:text-gray-100">
+                    {t("settings.codexProfiles")}
+                  </span>
+                  <p className="text-xs text-gray-500 dark:text-gray-400 mt-1">
+                    {t("settings.codexProfilesDescription")}
+                  </p>
+                </div>
+                <input
+                  type="checkbox"
+                  checked={settings.codexProfiles ?? false}
+                  onChange={(e) =>
+                    setSettings((prev) => ({
+                      ...prev,
+                      codexProfiles: e.target.checked,
+                    }))
+                  }
+                  className="w-4 h-4 text-blue-500 rounded focus:ring-blue-500/20"
+                />
+              </label>
+            </div>
+          </div>
+
+          {/* 切换前连通性检查 */}
+          <div>
+            <h3 className="text-sm font-medium text-gray-900 dark:text-gray-100 mb-3">
+              {t("settings.switchPreflight")}
+            </h3>
+            <div className="space-y-2">
+              {(["claude", "codex", "droid"] as AppType[]).map((app) => (
+                <label key={app} className="flex items-center justify-between">
+                  <span className="text-sm text-gray-900 dark:text-gray-100">
+                    {t(`apps.${app}`)}
+                  </span>
+                  <select
+                    value={settings.switchPreflight?.[app] ?? "off"}
+                    onChange={(e) =>
+                      setSettings((prev) => ({
+                        ...prev,
+                        switchPreflight: {
+                          ...prev.switchPreflight,
+                          [app]: e.target.value as PreflightMode,
+                        },
+                      }))
+                    }
+                    className="px-2 py-1 border border-gray-200 dark:border-gray-700 rounded-md text-xs dark:bg-gray-800 dark:text-gray-100 focus:outline-none focus:ring-2 focus:ring-blue-500/20"
+                  >
+                    <option value="off">
+                      {t("settings.switchPreflightOff")}
+                    </option>
+                    <option value="warn">
+                      {t("settings.switchPreflightWarn")}
+                    </option>
+                    <option value="block">
+                      {t("settings.switchPreflightBlock")}
+                    </option>
+                  </select>
+                </label>
+              ))}
+              <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                {t("settings.switchPreflightDescription")}
+              </p>
             </div>
           </div>
 
+          {/* 自动切换规则（在 settings.json 的 switchRules 中编辑） */}
+          <div>
+            <h3 className="text-sm font-medium text-gray-900 dark:text-gray-100 mb-3">
+              {t("settings.switchRules")}
+            </h3>
+            <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+              {nextSwitch
+                ? t("settings.nextScheduledSwitch", {
+                    time: nextSwitch.atLocal,
+                    appName: t(`apps.${nextSwitch.appType}`),
+                    provider: nextSwitch.providerName ?? nextSwitch.providerId,
+                  })
+                : t("settings.noScheduledSwitch")}
+            </p>
+          </div>
+
           {/* 全局快捷键设置 */}
           <div>
             <h3 className="text-sm font-medium text-gray-900 dark:text-gray-100 mb-3">
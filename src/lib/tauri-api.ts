@@ -1,6 +1,28 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
-import { Provider, Settings, McpConfigResponse, McpServer } from "../types";
+import {
+  Provider,
+  Settings,
+  McpConfigResponse,
+  McpServer,
+  ConfigRecoveryReport,
+  ConfigSnapshot,
+  SnapshotDiff,
+  LiveConfigChange,
+  EndpointFailoverEvent,
+  SwitchPreview,
+  SwitchReport,
+  PreflightResult,
+  ScheduledSwitch,
+  TemporarySwitch,
+} from "../types";
+
+// 供应商切换事件（托盘切换时附带连通性检查结果）
+interface ProviderSwitchedEvent {
+  appType: string;
+  providerId: string;
+  preflight?: PreflightResult | null;
+}
 
 // 应用类型
 export type AppType = "claude" | "codex" | "droid";
@@ -96,7 +118,7 @@ export const tauriAPI = {
   switchProvider: async (
     providerId: string,
     app?: AppType,
-  ): Promise<boolean> => {
+  ): Promise<SwitchReport> => {
     try {
       return await invoke("switch_provider", {
         id: providerId,
@@ -105,10 +127,74 @@ export const tauriAPI = {
       });
     } catch (error) {
       console.error("切换供应商失败:", error);
-      return false;
+      throw error;
+    }
+  },
+
+  // 预演切换：返回各配置文件的前后内容与差异，不写入任何文件
+  previewSwitch: async (
+    providerId: string,
+    app?: AppType,
+  ): Promise<SwitchPreview> => {
+    return await invoke("preview_switch", {
+      id: providerId,
+      app_type: app,
+      app,
+    });
+  },
+
+  // 检查供应商的连通性与凭据（不切换），baseUrl 覆盖配置中的地址
+  checkProviderConnectivity: async (
+    providerId: string,
+    app?: AppType,
+    baseUrl?: string,
+  ): Promise<PreflightResult> => {
+    return await invoke("check_provider_connectivity", {
+      id: providerId,
+      app_type: app,
+      app,
+      baseUrl,
+    });
+  },
+
+  // 按自动切换规则查询下一次计划中的切换（不指定应用时查询全部应用）
+  nextScheduledSwitch: async (
+    app?: AppType,
+  ): Promise<ScheduledSwitch | null> => {
+    return await invoke("next_scheduled_switch", { app_type: app, app });
+  },
+
+  // 临时切换供应商，durationSecs 秒后自动恢复
+  switchProviderFor: async (
+    providerId: string,
+    durationSecs: number,
+    app?: AppType,
+  ): Promise<SwitchReport> => {
+    try {
+      return await invoke("switch_provider_for", {
+        id: providerId,
+        durationSecs,
+        app_type: app,
+        app,
+      });
+    } catch (error) {
+      console.error("临时切换供应商失败:", error);
+      throw error;
     }
   },
 
+  // 进行中的临时切换
+  getTemporarySwitch: async (
+    app?: AppType,
+  ): Promise<TemporarySwitch | null> => {
+    return await invoke("get_temporary_switch", { app_type: app, app });
+  },
+
+  // 放弃临时切换（保留当前供应商）
+  cancelTemporarySwitch: async (app?: AppType): Promise<boolean> => {
+    return await invoke("cancel_temporary_switch", { app_type: app, app });
+  },
+
   // 停用当前供应商
   disableCurrentProvider: async (app?: AppType): Promise<boolean> => {
     try {
@@ -209,10 +295,35 @@ export const tauriAPI = {
 
   // 监听供应商切换事件
   onProviderSwitched: async (
-    callback: (data: { appType: string; providerId: string }) => void,
+    callback: (data: ProviderSwitchedEvent) => void,
   ): Promise<UnlistenFn> => {
     return await listen("provider-switched", (event) => {
-      callback(event.payload as { appType: string; providerId: string });
+      callback(event.payload as ProviderSwitchedEvent);
+    });
+  },
+
+  // 监听 live 配置文件的外部修改
+  onLiveConfigChanged: async (
+    callback: (data: LiveConfigChange) => void,
+  ): Promise<UnlistenFn> => {
+    return await listen("live-config-changed", (event) => {
+      callback(event.payload as LiveConfigChange);
+    });
+  },
+
+  // 监听 config.enc.json 被外部修改后的热重载
+  onAppConfigReloaded: async (callback: () => void): Promise<UnlistenFn> => {
+    return await listen("app-config-reloaded", () => {
+      callback();
+    });
+  },
+
+  // 监听备选地址自动故障转移
+  onEndpointFailover: async (
+    callback: (data: EndpointFailoverEvent) => void,
+  ): Promise<UnlistenFn> => {
+    return await listen("endpoint-failover", (event) => {
+      callback(event.payload as EndpointFailoverEvent);
     });
   },
 
@@ -319,6 +430,36 @@ export const tauriAPI = {
     }
   },
 
+  // 获取启动时的配置恢复结果（配置正常时为 null）
+  getConfigRecoveryStatus: async (): Promise<ConfigRecoveryReport | null> => {
+    try {
+      return await invoke("get_config_recovery_status");
+    } catch (error) {
+      console.error("获取配置恢复状态失败:", error);
+      return null;
+    }
+  },
+
+  // 列出配置快照（从新到旧）
+  listConfigSnapshots: async (): Promise<ConfigSnapshot[]> => {
+    try {
+      return await invoke("list_config_snapshots");
+    } catch (error) {
+      console.error("获取配置快照失败:", error);
+      return [];
+    }
+  },
+
+  // 比较配置快照与当前配置
+  diffConfigSnapshot: async (id: string): Promise<SnapshotDiff> => {
+    return await invoke("diff_config_snapshot", { id });
+  },
+
+  // 从快照恢复配置
+  restoreConfigSnapshot: async (id: string): Promise<boolean> => {
+    return await invoke("restore_config_snapshot", { id });
+  },
+
   // 打开应用配置文件夹
   openAppConfigFolder: async (): Promise<void> => {
     try {
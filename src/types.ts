@@ -15,10 +15,44 @@ export interface Provider {
   createdAt?: number; // 添加时间戳（毫秒）
   // 备选 URL 列表（用于快速切换 API 地址）
   alternativeUrls?: string[];
+  // Claude 凭据类型：authToken → ANTHROPIC_AUTH_TOKEN（Bearer），apiKey → ANTHROPIC_API_KEY（x-api-key）
+  credentialKind?: "authToken" | "apiKey";
+  // Claude 受管的 settings.json 顶层字段（如 model、permissions、statusLine），切换时写入与回填
+  managedPaths?: string[];
+  // 备选地址自动故障转移（Claude / Codex，需至少两个备选地址）
+  failover?: FailoverPolicy;
+  // Codex config.toml 写入方式：full 完整替换（默认）；section 只合并 model、model_provider 与 [model_providers.*]
+  codexConfigMode?: "full" | "section";
+  // profile 模式下把 API Key 明文写入 config.toml（默认写入 env_key）
+  codexInlineToken?: boolean;
   // 可选：供应商元数据（仅存于 ~/.cc-switch/config.json，不写入 live 配置）
   meta?: ProviderMeta;
 }
 
+// 备选地址自动故障转移策略（未填写的字段使用后端默认值）
+export interface FailoverPolicy {
+  enabled: boolean;
+  // 探测间隔秒数（默认 60，最小 10）
+  intervalSecs?: number;
+  // 连续异常次数阈值（默认 3）
+  failureThreshold?: number;
+  // 延迟阈值毫秒（默认 5000）
+  latencyThresholdMs?: number;
+  // 自动切换后的冷却秒数（默认 600，最长 7 天）
+  cooldownSecs?: number;
+}
+
+// 故障转移监控自动切换地址时的事件
+export interface EndpointFailoverEvent {
+  appType: "claude" | "codex";
+  providerId: string;
+  providerName: string;
+  fromUrl: string;
+  toUrl: string;
+  reason: string;
+  latencyMs?: number;
+}
+
 export interface AppConfig {
   providers: Record<string, Provider>;
   current: string;
@@ -55,6 +89,99 @@ export interface Settings {
   autoStart: boolean;
   // 全局快捷键
   globalShortcut?: string;
+  // 最多保留的配置快照数量（0 表示不限，默认 20）
+  snapshotRetentionCount?: number;
+  // 配置快照保留天数（未设置表示不按时间清理）
+  snapshotRetentionDays?: number;
+  // live 配置文件被外部修改后自动回填到当前供应商
+  autoSyncLiveConfig?: boolean;
+  // 为每个 Codex 供应商生成 config.toml 的 [profiles.<id>]（codex --profile <id>）
+  codexProfiles?: boolean;
+  // 切换前后执行的命令（以应用类型为键，界面不编辑，保存设置时原样保留）
+  switchHooks?: Record<string, AppSwitchHooks>;
+  // 切换前连通性检查方式（以应用类型为键，未配置表示不检查）
+  switchPreflight?: Record<string, PreflightMode>;
+  // 按时间自动切换供应商的规则（界面不编辑，保存设置时原样保留）
+  switchRules?: SwitchRule[];
+}
+
+// 自动切换规则：timeWindow 在 start 切换到 providerId、end 切换到 otherwise；monthDay 在每月第 day 天的 at 切换
+export type SwitchRule = {
+  enabled?: boolean;
+  appType: "claude" | "codex" | "droid";
+  providerId: string;
+} & (
+  | {
+      type: "timeWindow";
+      days?: ("mon" | "tue" | "wed" | "thu" | "fri" | "sat" | "sun")[];
+      start: string;
+      end: string;
+      otherwise?: string;
+    }
+  | { type: "monthDay"; day: number; at?: string }
+);
+
+// 下一次计划中的切换
+export interface ScheduledSwitch {
+  appType: "claude" | "codex" | "droid";
+  providerId: string;
+  providerName?: string;
+  // Unix 毫秒
+  at: number;
+  // 本地时间 YYYY-MM-DD HH:MM
+  atLocal: string;
+  ruleIndex: number;
+}
+
+// 临时切换：revertAt 之后恢复为 previousProviderId
+export interface TemporarySwitch {
+  providerId: string;
+  previousProviderId: string;
+  // Unix 毫秒
+  revertAt: number;
+}
+
+// 切换前连通性检查：off 不检查，warn 失败仍切换并提示，block 失败时取消切换
+export type PreflightMode = "off" | "warn" | "block";
+
+export type PreflightStatus =
+  | "ok"
+  | "authFailed"
+  | "unreachable"
+  | "wrongApiShape"
+  | "skipped";
+
+// 连通性与凭据检查结果
+export interface PreflightResult {
+  status: PreflightStatus;
+  // 实际请求的地址（跳过时为空字符串）
+  url: string;
+  httpStatus?: number;
+  latencyMs?: number;
+  message: string;
+}
+
+// 切换结果（未开启检查时没有 preflight）
+export interface SwitchReport {
+  provider: Provider;
+  preflight?: PreflightResult;
+}
+
+// 单条切换钩子命令
+export interface SwitchHookCommand {
+  command: string;
+  // 超时秒数（默认 30）
+  timeoutSecs?: number;
+}
+
+export interface SwitchHooks {
+  preSwitch?: SwitchHookCommand[];
+  postSwitch?: SwitchHookCommand[];
+}
+
+// 应用级钩子，可按供应商 ID 追加
+export interface AppSwitchHooks extends SwitchHooks {
+  providers?: Record<string, SwitchHooks>;
 }
 
 // MCP 服务器连接参数（宽松：允许扩展字段）
@@ -94,6 +221,91 @@ export interface McpStatus {
   serverCount: number;
 }
 
+// 启动时的配置恢复结果（配置损坏时由后端返回）
+export interface ConfigRecoveryReport {
+  error: string;
+  quarantinedPath: string | null;
+  // backup：.bak 备份；archive：归档副本；null：无法恢复
+  source: "backup" | "archive" | null;
+  sourcePath: string | null;
+  readOnly: boolean;
+}
+
+// 配置快照（id 为创建时的毫秒时间戳）
+export interface ConfigSnapshot {
+  id: string;
+  createdAt: number;
+  size: number;
+  path: string;
+}
+
+export interface SnapshotProviderRef {
+  id: string;
+  name: string;
+}
+
+// 以快照为基准：added/removed 为快照之后新增/删除的供应商
+export interface AppSnapshotDiff {
+  app: string;
+  added: SnapshotProviderRef[];
+  removed: SnapshotProviderRef[];
+  changed: SnapshotProviderRef[];
+  snapshotCurrent: string;
+  current: string;
+}
+
+export interface SnapshotDiff {
+  snapshotId: string;
+  apps: AppSnapshotDiff[];
+}
+
+// JSON Patch 操作（RFC 6902，path 为 JSON Pointer）
+export type SwitchPatchOp =
+  | { op: "add"; path: string; value: unknown }
+  | { op: "remove"; path: string }
+  | { op: "replace"; path: string; value: unknown };
+
+// 切换预演中单个文件写入前后的内容与差异
+export interface SwitchFilePreview {
+  path: string;
+  format: "json" | "toml" | "text";
+  exists: boolean;
+  before: string;
+  after: string;
+  changed: boolean;
+  // 仅 JSON / TOML 且两侧都能解析时提供
+  patch?: SwitchPatchOp[];
+  // 统一格式文本差异
+  diff: string;
+}
+
+// preview_switch 的返回值（不写入任何文件）
+export interface SwitchPreview {
+  appType: string;
+  providerId: string;
+  providerName: string;
+  files: SwitchFilePreview[];
+  notes: string[];
+}
+
+// live 配置文件中单个字段的变化（path 为 JSON Pointer，密钥类字段的值已遮蔽）
+export interface LiveConfigFieldChange {
+  path: string;
+  kind: "added" | "removed" | "changed";
+  before?: unknown;
+  after?: unknown;
+}
+
+// live 配置文件被外部修改（live-config-changed 事件）
+export interface LiveConfigChange {
+  appType: string;
+  file: "claudeSettings" | "claudeMcp" | "codexAuth" | "codexConfig";
+  path: string;
+  changes: LiveConfigFieldChange[];
+  // 是否已自动回填到当前供应商
+  synced: boolean;
+}
+
 // 新：来自 config.json 的 MCP 列表响应
 export interface McpConfigResponse {
   configPath: string;
@@ -162,24 +162,76 @@ export const hasCommonConfigSnippet = (
   }
 };
 
-// 读取配置中的 API Key（env.ANTHROPIC_AUTH_TOKEN）
+// Claude 凭据类型：authToken 以 Bearer 发送，apiKey 以 x-api-key 发送
+export type ClaudeCredentialKind = "authToken" | "apiKey";
+
+export const CLAUDE_CREDENTIAL_ENV_KEYS: Record<ClaudeCredentialKind, string> =
+  {
+    authToken: "ANTHROPIC_AUTH_TOKEN",
+    apiKey: "ANTHROPIC_API_KEY",
+  };
+
+const hasOwn = (obj: unknown, key: string): boolean =>
+  !!obj && Object.prototype.hasOwnProperty.call(obj, key);
+
+// 按 env 中存在的字段判断凭据类型（仅有 ANTHROPIC_API_KEY 时为 apiKey）
+const credentialKindOfEnv = (env: unknown): ClaudeCredentialKind =>
+  hasOwn(env, CLAUDE_CREDENTIAL_ENV_KEYS.apiKey) &&
+  !hasOwn(env, CLAUDE_CREDENTIAL_ENV_KEYS.authToken)
+    ? "apiKey"
+    : "authToken";
+
+// 读取配置中的凭据类型
+export const getCredentialKindFromConfig = (
+  jsonString: string,
+): ClaudeCredentialKind => {
+  try {
+    const config = JSON.parse(jsonString);
+    return credentialKindOfEnv(config?.env);
+  } catch (err) {
+    return "authToken";
+  }
+};
+
+// 切换凭据类型：把已填写的 Key 移到对应字段，并移除另一种字段
+export const setCredentialKindInConfig = (
+  jsonString: string,
+  kind: ClaudeCredentialKind,
+): string => {
+  try {
+    const config = JSON.parse(jsonString);
+    if (!config.env) config.env = {};
+    const target = CLAUDE_CREDENTIAL_ENV_KEYS[kind];
+    const other =
+      CLAUDE_CREDENTIAL_ENV_KEYS[kind === "apiKey" ? "authToken" : "apiKey"];
+    if (!hasOwn(config.env, target)) {
+      config.env[target] = hasOwn(config.env, other) ? config.env[other] : "";
+    }
+    delete config.env[other];
+    return JSON.stringify(config, null, 2);
+  } catch (err) {
+    return jsonString;
+  }
+};
+
+// 读取配置中的 API Key（env.ANTHROPIC_AUTH_TOKEN 或 env.ANTHROPIC_API_KEY）
 export const getApiKeyFromConfig = (jsonString: string): string => {
   try {
     const config = JSON.parse(jsonString);
-    const key = config?.env?.ANTHROPIC_AUTH_TOKEN;
+    const kind = credentialKindOfEnv(config?.env);
+    const key = config?.env?.[CLAUDE_CREDENTIAL_ENV_KEYS[kind]];
     return typeof key === "string" ? key : "";
   } catch (err) {
     return "";
   }
 };
 
-// 判断配置中是否存在 API Key 字段
+// 判断配置中是否存在 API Key 字段（任一凭据类型）
 export const hasApiKeyField = (jsonString: string): boolean => {
   try {
     const config = JSON.parse(jsonString);
-    return Object.prototype.hasOwnProperty.call(
-      config?.env ?? {},
-      "ANTHROPIC_AUTH_TOKEN",
+    return Object.values(CLAUDE_CREDENTIAL_ENV_KEYS).some((key) =>
+      hasOwn(config?.env ?? {}, key),
     );
   } catch (err) {
     return false;
@@ -199,10 +251,11 @@ export const setApiKeyInConfig = (
       if (!createIfMissing) return jsonString;
       config.env = {};
     }
-    if (!("ANTHROPIC_AUTH_TOKEN" in config.env) && !createIfMissing) {
+    const field = CLAUDE_CREDENTIAL_ENV_KEYS[credentialKindOfEnv(config.env)];
+    if (!(field in config.env) && !createIfMissing) {
       return jsonString;
     }
-    config.env.ANTHROPIC_AUTH_TOKEN = apiKey;
+    config.env[field] = apiKey;
     return JSON.stringify(config, null, 2);
   } catch (err) {
     return jsonString;
@@ -304,6 +357,14 @@ export const extractCodexBaseUrl = (
   }
 };
 
+// 替换 Codex TOML 配置文本中第一处 base_url 的值（没有 base_url 时原样返回）
+export const setCodexBaseUrl = (configText: string, url: string): string => {
+  return configText.replace(
+    /base_url\s*=\s*(['"])([^'\"]+)\1/,
+    `base_url = ${JSON.stringify(url.trim())}`,
+  );
+};
+
 // 从 Provider 对象中提取 Codex base_url（当 settingsConfig.config 为 TOML 字符串时）
 export const getCodexBaseUrl = (
   provider: { settingsConfig?: Record<string, any> } | undefined | null,
@@ -1,6 +1,21 @@
 /// <reference types="vite/client" />
 
-import { Provider, Settings, McpConfigResponse, McpServer } from "./types";
+import {
+  Provider,
+  Settings,
+  McpConfigResponse,
+  McpServer,
+  ConfigRecoveryReport,
+  ConfigSnapshot,
+  SnapshotDiff,
+  LiveConfigChange,
+  EndpointFailoverEvent,
+  SwitchPreview,
+  SwitchReport,
+  PreflightResult,
+  ScheduledSwitch,
+  TemporarySwitch,
+} from "./types";
 import { AppType } from "./lib/tauri-api";
 import type { UnlistenFn } from "@tauri-apps/api/event";
 
@@ -30,7 +45,27 @@ declare global {
       addProvider: (provider: Provider, app?: AppType) => Promise<boolean>;
       deleteProvider: (id: string, app?: AppType) => Promise<boolean>;
       updateProvider: (provider: Provider, app?: AppType) => Promise<boolean>;
-      switchProvider: (providerId: string, app?: AppType) => Promise<boolean>;
+      switchProvider: (
+        providerId: string,
+        app?: AppType,
+      ) => Promise<SwitchReport>;
+      previewSwitch: (
+        providerId: string,
+        app?: AppType,
+      ) => Promise<SwitchPreview>;
+      checkProviderConnectivity: (
+        providerId: string,
+        app?: AppType,
+        baseUrl?: string,
+      ) => Promise<PreflightResult>;
+      nextScheduledSwitch: (app?: AppType) => Promise<ScheduledSwitch | null>;
+      switchProviderFor: (
+        providerId: string,
+        durationSecs: number,
+        app?: AppType,
+      ) => Promise<SwitchReport>;
+      getTemporarySwitch: (app?: AppType) => Promise<TemporarySwitch | null>;
+      cancelTemporarySwitch: (app?: AppType) => Promise<boolean>;
       disableCurrentProvider: (app?: AppType) => Promise<boolean>;
       importCurrentConfigAsDefault: (app?: AppType) => Promise<ImportResult>;
       getClaudeCodeConfigPath: () => Promise<string>;
@@ -42,13 +77,28 @@ declare global {
       openExternal: (url: string) => Promise<void>;
       updateTrayMenu: () => Promise<boolean>;
       onProviderSwitched: (
-        callback: (data: { appType: string; providerId: string }) => void,
+        callback: (data: {
+          appType: string;
+          providerId: string;
+          preflight?: PreflightResult | null;
+        }) => void,
+      ) => Promise<UnlistenFn>;
+      onLiveConfigChanged: (
+        callback: (data: LiveConfigChange) => void,
+      ) => Promise<UnlistenFn>;
+      onAppConfigReloaded: (callback: () => void) => Promise<UnlistenFn>;
+      onEndpointFailover: (
+        callback: (data: EndpointFailoverEvent) => void,
       ) => Promise<UnlistenFn>;
       getSettings: () => Promise<Settings>;
       saveSettings: (settings: Settings) => Promise<boolean>;
       checkForUpdates: () => Promise<void>;
       isPortable: () => Promise<boolean>;
       getAppConfigPath: () => Promise<string>;
+      getConfigRecoveryStatus: () => Promise<ConfigRecoveryReport | null>;
+      listConfigSnapshots: () => Promise<ConfigSnapshot[]>;
+      diffConfigSnapshot: (id: string) => Promise<SnapshotDiff>;
+      restoreConfigSnapshot: (id: string) => Promise<boolean>;
       openAppConfigFolder: () => Promise<void>;
       // VS Code settings.json 能力
       getVSCodeSettingsStatus: () => Promise<ConfigStatus>;